@@ -0,0 +1,103 @@
+// Plabayo News
+// Copyright (C) 2021  Glen Henri J. De Cauwsemaecker
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+
+use crate::i18n::locales::Storage;
+
+/// summarize the strings added, removed and changed between two storage snapshots
+/// (e.g. the locales of two git revisions), grouped by locale and sorted by key,
+/// as markdown suitable for release notes
+pub fn format_changelog(old: &Storage, new: &Storage) -> String {
+    let mut changelog = String::new();
+    for locale in old.all_locales().chain(new.all_locales()).sorted().dedup() {
+        let old_values = locale_values(old, locale);
+        let new_values = locale_values(new, locale);
+        let mut changes = vec![];
+        for key in old_values.keys().chain(new_values.keys()).sorted().dedup() {
+            match (old_values.get(key), new_values.get(key)) {
+                (None, Some(value)) => changes.push(format!("- added `{}`: {:?}", key, value)),
+                (Some(value), None) => changes.push(format!("- removed `{}`: {:?}", key, value)),
+                (Some(old_value), Some(new_value)) if old_value != new_value => changes.push(
+                    format!("- changed `{}`: {:?} → {:?}", key, old_value, new_value),
+                ),
+                _ => (),
+            }
+        }
+        if changes.is_empty() {
+            continue;
+        }
+        if !changelog.is_empty() {
+            changelog.push('\n');
+        }
+        changelog.push_str(&format!("## {}\n\n{}\n", locale, changes.join("\n")));
+    }
+    changelog
+}
+
+fn locale_values(storage: &Storage, locale: &str) -> BTreeMap<String, String> {
+    storage
+        .get(locale)
+        .map(|locales| {
+            locales
+                .iter()
+                .map(|pair| (pair.path.join("."), pair.value))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changelog_lists_added_removed_and_changed_strings_per_locale() {
+        let old = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "site:\n  name: News\n  title: Welcome\n  motto: Stay curious\n",
+            ),
+            ("nl", "site:\n  name: Nieuws\n  title: Welkom\n"),
+        ])
+        .unwrap();
+        let new = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "site:\n  name: Plabayo News\n  title: Welcome\n  tagline: \"A \\\"conscious\\\" news site\"\n",
+            ),
+            ("nl", "site:\n  name: Nieuws\n  title: Welkom\n"),
+            ("de", "site:\n  name: Nachrichten\n"),
+        ])
+        .unwrap();
+        assert_eq!(
+            format_changelog(&old, &new),
+            r#"## de
+
+- added `site.name`: "Nachrichten"
+
+## en
+
+- removed `site.motto`: "Stay curious"
+- changed `site.name`: "News" → "Plabayo News"
+- added `site.tagline`: "A \"conscious\" news site"
+"#
+        );
+        assert_eq!(format_changelog(&new, &new), "");
+    }
+}
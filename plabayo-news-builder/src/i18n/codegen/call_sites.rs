@@ -0,0 +1,299 @@
+// Plabayo News
+// Copyright (C) 2021  Glen Henri J. De Cauwsemaecker
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use convert_case::{Case, Casing};
+use itertools::Itertools;
+
+use crate::i18n::config::I18n;
+use crate::i18n::locales::Storage;
+use crate::i18n::scaffold::template_sources;
+use crate::i18n::validation::named_placeholders;
+
+/// a generated string accessor (method) which templates can call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Accessor {
+    pub name: String,
+    /// end of the expression the accessor is called on, e.g. `strings()` for `Strings` methods,
+    /// `.site.nav` for methods of nested strings and `locale` for `Locale` methods
+    pub receiver: String,
+    /// dotted path of the string it gives access to
+    pub key: String,
+    pub arity: usize,
+}
+
+impl Accessor {
+    /// the call as found in templates, up to and including its opening parenthesis
+    fn call(&self) -> String {
+        format!("{}.{}(", self.receiver, self.name)
+    }
+}
+
+/// the string accessors generated for the given config, with the number of arguments they take
+pub fn accessors(cfg: &I18n, storage: &Storage) -> Vec<Accessor> {
+    let mut accessors = Vec::new();
+    if cfg.accessors {
+        if let Some(locales) = storage.get_default() {
+            accessors.extend(locales.iter().map(|pair| Accessor {
+                name: pair.path.iter().map(|s| s.to_case(Case::Snake)).join("_"),
+                receiver: "strings()".to_owned(),
+                key: pair.path.join("."),
+                arity: 0,
+            }));
+        }
+    }
+    // interpolated strings take an argument per placeholder,
+    // as methods of the (nested) strings struct holding them
+    if cfg.interpolation {
+        if let Some(locales) = storage.get_default() {
+            accessors.extend(
+                locales
+                    .iter()
+                    .filter(|pair| pair.bytes.is_none())
+                    .map(|pair| (named_placeholders(&pair.value).len(), pair))
+                    .filter(|(arity, _)| *arity > 0)
+                    .map(|(arity, pair)| {
+                        let parent = &pair.path[..pair.path.len() - 1];
+                        Accessor {
+                            name: pair.path[parent.len()].to_case(Case::Snake),
+                            receiver: if parent.is_empty() {
+                                "strings()".to_owned()
+                            } else {
+                                parent
+                                    .iter()
+                                    .map(|s| format!(".{}", s.to_case(Case::Snake)))
+                                    .join("")
+                            },
+                            key: pair.path.join("."),
+                            arity,
+                        }
+                    }),
+            );
+        }
+    }
+    // plural strings are selected by the count given to their `Locale` method
+    if cfg.plurals {
+        accessors.extend(storage.plural_paths().into_iter().map(|path| Accessor {
+            name: path.split('.').join("_").to_case(Case::Snake),
+            receiver: "locale".to_owned(),
+            key: path,
+            arity: 1,
+        }));
+    }
+    // gendered strings are selected by the gender given to their `Locale` method
+    accessors.extend(cfg.gendered.iter().map(|path| Accessor {
+        name: path.split('.').join("_").to_case(Case::Snake),
+        receiver: "locale".to_owned(),
+        key: path.clone(),
+        arity: 1,
+    }));
+    accessors
+}
+
+/// verify that all templates found in the given directory call the given accessors
+/// with the number of arguments they take, reporting all mismatches at once
+pub fn verify_template_call_sites(templates_path: &Path, accessors: &[Accessor]) -> Result<()> {
+    if accessors.is_empty() {
+        return Ok(());
+    }
+    let mut errors = Vec::new();
+    for (path, source) in template_sources(templates_path)? {
+        for accessor in accessors {
+            for args in call_site_arities(&source, &accessor.call()) {
+                if args != accessor.arity {
+                    errors.push(format!(
+                        "template {} calls '{}' of string '{}' with {} argument(s), expected {}",
+                        path.display(),
+                        accessor.name,
+                        accessor.key,
+                        args,
+                        accessor.arity
+                    ));
+                }
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        errors.sort();
+        Err(anyhow!(errors.join("\n")))
+    }
+}
+
+/// number of arguments of every call (e.g. `locale.greeting(`) found in the given template source,
+/// the receiver not being part of a longer name (e.g. `default_locale.greeting(`)
+fn call_site_arities(source: &str, call: &str) -> Vec<usize> {
+    source
+        .match_indices(call)
+        .filter(|(index, _)| {
+            call.starts_with('.')
+                || !source[..*index].ends_with(|c: char| c.is_alphanumeric() || c == '_')
+        })
+        .filter_map(|(index, call)| argument_count(&source[index + call.len()..]))
+        .collect()
+}
+
+/// number of (top level) arguments of the call the given source starts the arguments of,
+/// `None` in case the call isn't closed
+fn argument_count(args: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut commas = 0;
+    let mut in_string = None;
+    let mut empty = true;
+    let mut escaped = false;
+    for c in args.chars() {
+        if let Some(quote) = in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == quote {
+                in_string = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => in_string = Some(c),
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' if depth > 0 => depth -= 1,
+            ')' => return Some(if empty { 0 } else { commas + 1 }),
+            ',' if depth == 0 => commas += 1,
+            _ => (),
+        }
+        if !c.is_whitespace() {
+            empty = false;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn arguments_of_call_sites_are_counted() {
+        let source = "{{ page.locale.greeting(gender) }} {{ page.locale.greeting() }}
+{{ page.locale.greeting(user.gender(), \"a, (b\") }} {{ page.locale.site_greeting(a) }}
+{{ page.locale.greeting( ) }} {{ default_locale.greeting() }} {{ page.locale.greeting(";
+        assert_eq!(
+            call_site_arities(source, "locale.greeting("),
+            vec![1, 0, 2, 0]
+        );
+    }
+
+    #[test]
+    fn only_calls_on_the_accessor_receiver_are_checked() {
+        let storage = Storage::from_yaml_sources(&[(
+            "en",
+            "count: \"{n} items\"\nnav:\n  title: \"Hi {name}\"\npage:\n  title: Welcome\n  user:\n    title: \"{name}, {role}\"\n",
+        )])
+        .unwrap();
+        let cfg = I18n {
+            interpolation: true,
+            ..I18n::default()
+        };
+        let accessors = accessors(&cfg, &storage);
+        assert_eq!(
+            accessors
+                .iter()
+                .map(|accessor| (accessor.call(), accessor.arity))
+                .collect::<Vec<_>>(),
+            vec![
+                ("strings().count(".to_owned(), 1),
+                (".nav.title(".to_owned(), 1),
+                (".page.user.title(".to_owned(), 2),
+            ]
+        );
+
+        let dir = std::env::temp_dir().join("plabayo_news_builder_call_site_receivers");
+        fs::create_dir_all(dir.join("pages")).unwrap();
+        // unrelated methods sharing the name of a string are not string accessors
+        fs::write(
+            dir.join("pages").join("index.html"),
+            "{{ items.count() }} {{ item.title() }} {{ page.locale.strings().count(n) }}
+{{ page.locale.strings().nav.title(user) }} {{ s.page.user.title(user, role) }}",
+        )
+        .unwrap();
+        verify_template_call_sites(&dir, &accessors[..]).unwrap();
+
+        // strings sharing their name are told apart by their receiver
+        fs::write(
+            dir.join("pages").join("index.html"),
+            "{{ page.locale.strings().nav.title(user, role) }} {{ s.page.user.title(user) }}",
+        )
+        .unwrap();
+        let err = verify_template_call_sites(&dir, &accessors[..]).unwrap_err();
+        let path = dir.join("pages").join("index.html");
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "template {path} calls 'title' of string 'nav.title' with 2 argument(s), expected 1
+template {path} calls 'title' of string 'page.user.title' with 1 argument(s), expected 2",
+                path = path.display()
+            )
+        );
+    }
+
+    #[test]
+    fn template_calls_with_wrong_arity_fail() {
+        let storage = Storage::from_yaml_sources(&[(
+            "en",
+            "greeting:\n  neutral: Hi\n  female: Hi madam\nsite:\n  name: News\n",
+        )])
+        .unwrap();
+        let cfg = I18n {
+            accessors: true,
+            gendered: vec!["greeting".to_owned()],
+            ..I18n::default()
+        };
+        let accessors = accessors(&cfg, &storage);
+        assert!(accessors.contains(&Accessor {
+            name: "greeting".to_owned(),
+            receiver: "locale".to_owned(),
+            key: "greeting".to_owned(),
+            arity: 1,
+        }));
+
+        let dir = std::env::temp_dir().join("plabayo_news_builder_call_sites");
+        fs::create_dir_all(dir.join("pages")).unwrap();
+        fs::write(
+            dir.join("pages").join("about.html"),
+            "<h1>{{ page.locale.greeting() }}</h1><p>{{ page.locale.strings().site_name() }}</p>",
+        )
+        .unwrap();
+        let err = verify_template_call_sites(&dir, &accessors[..]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "template {} calls 'greeting' of string 'greeting' with 0 argument(s), expected 1",
+                dir.join("pages").join("about.html").display()
+            )
+        );
+
+        fs::write(
+            dir.join("pages").join("about.html"),
+            "<h1>{{ page.locale.greeting(Gender::Female) }}</h1>",
+        )
+        .unwrap();
+        verify_template_call_sites(&dir, &accessors[..]).unwrap();
+    }
+}
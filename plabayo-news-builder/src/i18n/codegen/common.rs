@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
 pub fn generate_copyright_file_header(mut w: impl std::io::Write) -> Result<()> {
     w.write_all(
@@ -39,3 +39,74 @@ pub fn generate_copyright_file_header(mut w: impl std::io::Write) -> Result<()>
 
     Ok(())
 }
+
+/// warnings emitted during codegen, printed as cargo warnings,
+/// or collected and returned as a single error in strict mode
+pub struct Warnings {
+    strict: bool,
+    messages: Vec<String>,
+}
+
+impl Warnings {
+    pub fn new(strict: bool) -> Warnings {
+        Warnings {
+            strict,
+            messages: Vec::new(),
+        }
+    }
+
+    pub fn warn(&mut self, message: String) {
+        if self.strict {
+            self.messages.push(message);
+        } else {
+            println!("cargo:warning=plabayo-news_builder: {}", message);
+        }
+    }
+
+    /// fail in case any warning was collected in strict mode
+    pub fn finish(self) -> Result<()> {
+        if self.messages.is_empty() {
+            return Ok(());
+        }
+        Err(anyhow!(
+            "strict mode: {} codegen warning(s) found:\n- {}",
+            self.messages.len(),
+            self.messages.join("\n- ")
+        ))
+    }
+}
+
+/// encode the given value as a JSON string, safe to embed in a script element
+pub fn json_string(value: &str) -> String {
+    // `<` is escaped such that the string can't close (or open) a script element
+    serde_json::Value::from(value)
+        .to_string()
+        .replace('<', "\\u003c")
+}
+
+/// compare generated code against the golden snapshot file with the given name,
+/// (re)writing the snapshot instead when `UPDATE_SNAPSHOTS` is set
+#[cfg(test)]
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/i18n/codegen/snapshots")
+        .join(name);
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|err| {
+        panic!(
+            "read snapshot {}: {} (run with UPDATE_SNAPSHOTS=1 to create it)",
+            path.display(),
+            err
+        )
+    });
+    assert!(
+        expected == actual,
+        "generated code does not match snapshot {}, run with UPDATE_SNAPSHOTS=1 to update it if intended:\n{}",
+        path.display(),
+        actual
+    );
+}
@@ -14,112 +14,522 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::fs::File;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 
 use anyhow::{anyhow, Context, Result};
 use convert_case::{Case, Casing};
 use itertools::Itertools;
+use serde::Serialize;
 
-use crate::i18n::codegen::common::generate_copyright_file_header;
-use crate::i18n::locales::{Storage, StringValuePathPair};
+use crate::i18n::codegen::common::{generate_copyright_file_header, Warnings};
+use crate::i18n::codegen::transliteration::{
+    generate_transliteration_fn, transliteration_fn_name, DEFAULT_SCRIPT,
+};
+use crate::i18n::config::I18n;
+use crate::i18n::locales::{Storage, StringValuePathPair, MIRROR_LOCALE};
+use crate::i18n::validation::{named_placeholders, Severity, ValidationRules, A11Y_KEYS};
 
-pub fn generate_locales(file_path: &Path, storage: &Storage) -> Result<()> {
-    let file = File::create(file_path)
+pub fn generate_locales(file_path: &Path, storage: &Storage, cfg: &I18n) -> Result<()> {
+    // the directory as well, such that an added locale file is picked up
+    println!("cargo:rerun-if-changed={}", cfg.path);
+    for source_path in storage.source_paths() {
+        println!("cargo:rerun-if-changed={}", source_path.display());
+    }
+    // generated in memory first, such that no partial file is left behind when failing
+    let mut code = Vec::new();
+    let report = write_locales(&mut code, storage, cfg)
+        .with_context(|| format!("generate locales in {}", file_path.display()))?;
+    std::fs::write(file_path, code)
         .with_context(|| format!("create locales rust file at {}", file_path.display()))?;
-
-    generate_copyright_file_header(&file).with_context(|| {
-        format!(
-            "generate locales module copyright (header) in {}",
-            file_path.display()
+    if !report.domain_modules.is_empty() {
+        // the directory of the sub modules of the locales module
+        let modules_dir = file_path.with_extension("");
+        std::fs::create_dir_all(&modules_dir)
+            .with_context(|| format!("create locales modules dir at {}", modules_dir.display()))?;
+        for (module, code) in report.domain_modules.iter() {
+            let module_path = modules_dir.join(format!("{}.rs", module));
+            std::fs::write(&module_path, code).with_context(|| {
+                format!("create locales domain module at {}", module_path.display())
+            })?;
+        }
+    }
+    if let Some(report_path) = cfg.duplicates_report.as_deref() {
+        std::fs::write(report_path, duplicates_report_json(&report.duplicates[..])?)
+            .with_context(|| format!("write duplicate values report at {}", report_path))?;
+    }
+    if cfg.coverage_report {
+        let report_path = file_path.with_file_name("coverage.json");
+        std::fs::write(
+            &report_path,
+            coverage_report_json(&report.diagnostics[..], &report.missing_paths[..])?,
         )
-    })?;
+        .with_context(|| format!("write coverage report at {}", report_path.display()))?;
+    }
+    Ok(())
+}
 
-    generate_locales_mod_docs(&file).with_context(|| {
-        format!(
-            "generate locales module docs (header) in {}",
-            file_path.display()
-        )
-    })?;
+/// what was found out about the locales while generating them, for the (opt-in) reports
+#[derive(Debug)]
+pub struct LocalesReport {
+    /// duplicate values per locale
+    pub duplicates: Vec<LocaleDuplicates>,
+    diagnostics: Vec<LocaleDiagnostics>,
+    /// paths of the strings filled in by a fallback locale, per locale
+    pub missing_paths: Vec<(String, Vec<String>)>,
+    /// name and code of the module generated for every domain (see `split_domains`)
+    pub domain_modules: Vec<(String, Vec<u8>)>,
+}
+
+/// write the generated locales module to the given writer,
+/// returning what was found out about the locales
+pub fn write_locales(
+    mut w: impl std::io::Write,
+    storage: &Storage,
+    cfg: &I18n,
+) -> Result<LocalesReport> {
+    let mut warnings = Warnings::new(cfg.strict);
+    let mut errors = Vec::new();
+    for diagnostic in storage.validate(ValidationRules::from_config(cfg)) {
+        match diagnostic.severity {
+            Severity::Error => errors.push(diagnostic.message),
+            Severity::Warning => warnings.warn(diagnostic.message),
+            Severity::Info => (),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(anyhow!(errors.join("\n")));
+    }
+
+    generate_copyright_file_header(&mut w).context("generate locales module copyright (header)")?;
+
+    generate_locales_mod_docs(&mut w).context("generate locales module docs (header)")?;
 
     let default_locales = storage
         .get_default()
         .ok_or_else(|| anyhow!("failed to get default locale in i18n storage"))?;
 
-    generate_locales_enum(&file, storage).with_context(|| {
-        format!(
-            "generate locales enum definition and its methods/traits implementation in {}",
-            file_path.display()
-        )
-    })?;
+    generate_locales_enum(&mut w, storage)
+        .context("generate locales enum definition and its methods/traits implementation")?;
+
+    generate_locales_from_shim(&mut w, storage, cfg)
+        .context("generate locales from (compat) shim")?;
+
+    generate_locales_alternate_links(&mut w, cfg).context("generate locales alternate links")?;
+
+    generate_locales_text_helpers(&mut w, storage, cfg)
+        .context("generate locales text helper methods")?;
+
+    check_locale_accessor_names(storage, cfg).context("check locales accessor names")?;
+
+    generate_locales_gendered_accessors(&mut w, storage, &cfg.gendered[..])
+        .context("generate locales gendered accessors")?;
+
+    // relative time selects its unit forms by plural category
+    if cfg.plurals || has_relative_time(storage) {
+        generate_locales_plurals(&mut w, storage, cfg.plurals)
+            .context("generate locales plurals")?;
+    }
+
+    generate_locales_relative_time(&mut w, storage).context("generate locales relative time")?;
+
+    generate_locales_a11y(&mut w, storage).context("generate locales accessibility labels")?;
+
+    if let Some(key) = cfg.unavailable_notice.as_deref() {
+        generate_locales_unavailable_notice(&mut w, storage, key)
+            .context("generate locales unavailable notice")?;
+    }
 
     let default_pairs: Vec<StringValuePathPair> = default_locales.iter().collect();
 
+    // every top level object (domain) is split into a module of its own, if enabled
+    let mut domain_modules: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+    if cfg.split_domains {
+        for domain in default_pairs
+            .iter()
+            .filter(|pair| pair.path.len() > 1)
+            .map(|pair| pair.path[0].clone())
+            .dedup()
+        {
+            let mut module = Vec::new();
+            generate_locales_domain_module(&mut module, &domain, &default_pairs[..], cfg)
+                .with_context(|| format!("generate locales module of domain {}", domain))?;
+            w.write_all(
+                format!(
+                    "mod {module};
+pub use {module}::*;
+
+",
+                    module = domain_module_name(&domain)
+                )
+                .as_bytes(),
+            )?;
+            domain_modules.insert(domain, module);
+        }
+    }
+
     generate_locales_strings_struct(
-        &file,
-        default_pairs.iter().map(|p| p.path.clone()).collect(),
+        &mut w,
+        default_pairs.clone(),
+        cfg,
+        "Strings",
+        if cfg.split_domains { 1 } else { usize::MAX },
     )
-    .with_context(|| {
-        format!(
-            "generate locales strings struct (def) in {}",
-            file_path.display()
-        )
-    })?;
+    .context("generate locales strings struct (def)")?;
 
-    generate_locales_strings_struct_methods(&file, storage).with_context(|| {
-        format!(
-            "generate locales string struct (utility) methods in {}",
-            file_path.display()
-        )
-    })?;
+    generate_locales_strings_struct_methods(&mut w, storage)
+        .context("generate locales string struct (utility) methods")?;
+
+    if cfg.accessors {
+        generate_locales_strings_accessors(&mut w, &default_pairs[..])
+            .context("generate locales strings accessors")?;
+    }
+
+    if cfg.interpolation {
+        generate_locales_strings_interpolation(&mut w, &default_pairs[..], cfg)
+            .context("generate locales strings interpolation")?;
+    }
 
     let default_pairs_stringified: Vec<StringValuePathPair> = default_pairs
         .iter()
         .map(|p| StringValuePathPair {
-            value: format!(
-                r#################"r################"{}"################"#################,
-                p.value
-            ),
+            value: value_literal(p),
             path: p.path.clone(),
+            feature: p.feature.clone(),
+            bytes: p.bytes.clone(),
         })
         .collect();
-    generate_locales_strings_instance(&file, "STRINGS_DEFAULT", default_pairs_stringified.iter())
-        .with_context(|| {
+    generate_locales_strings_instance(
+        &mut w,
+        "STRINGS_DEFAULT",
+        default_pairs_stringified.iter(),
+        cfg.split_domains.then_some(&mut domain_modules),
+    )
+    .with_context(|| {
         format!(
-            "generate strings for default locale {} in {}",
-            storage.default_locale(),
-            file_path.display()
+            "generate strings for default locale {}",
+            storage.default_locale()
         )
     })?;
 
+    let mut total_string_bytes: usize = default_pairs.iter().map(value_byte_len).sum();
+
+    let mut missing_paths = vec![(storage.default_locale().to_owned(), vec![])];
+
+    let mut duplicates = vec![LocaleDuplicates::new(
+        storage.default_locale(),
+        &default_pairs[..],
+    )];
+
+    let mut diagnostics = vec![LocaleDiagnostics {
+        locale: storage.default_locale().to_owned(),
+        translated_keys: default_pairs.len(),
+        total_keys: default_pairs.len(),
+    }];
+
+    locale_fallback_chain(cfg, storage, storage.default_locale())?;
+    let mut fallback_chains = vec![(storage.default_locale().to_owned(), vec![])];
     for locale in storage
         .all_locales()
         .filter(|locale| locale != &storage.default_locale())
     {
-        let iter = LocaleStringWithDefaultIter::new(
-            locale.to_owned(),
-            storage
-                .get(locale)
-                .ok_or_else(|| anyhow!("failed to get strings for locale {}", locale))?
-                .iter(),
+        let locale_values = storage
+            .get(locale)
+            .ok_or_else(|| anyhow!("failed to get strings for locale {}", locale))?;
+        let mut fallbacks = Vec::new();
+        let fallback_chain = locale_fallback_chain(cfg, storage, locale)?;
+        for fallback in &fallback_chain {
+            let fallback_values = storage
+                .get(fallback)
+                .ok_or_else(|| anyhow!("failed to get strings for locale {}", fallback))?;
+            fallbacks.push(LocaleFallback::new(
+                fallback,
+                &fallback_values.iter().collect::<Vec<_>>()[..],
+                &default_pairs[..],
+            ));
+        }
+        fallback_chains.push((locale.to_owned(), fallback_chain));
+        let mut iter = LocaleStringWithDefaultIter::new(
+            locale_values.iter(),
             default_pairs.clone().into_iter(),
+            fallbacks,
         );
-        let pairs: Vec<StringValuePathPair> = iter.collect();
+        let pairs: Vec<StringValuePathPair> = iter.by_ref().collect();
+        diagnostics.push(LocaleDiagnostics {
+            locale: locale.to_owned(),
+            translated_keys: default_pairs.len() - iter.missing_paths().len(),
+            total_keys: default_pairs.len(),
+        });
+        total_string_bytes += iter.value_bytes();
+        // only the values defined by the locale itself, as fallbacks refer to the strings of others
+        duplicates.push(LocaleDuplicates::new(
+            locale,
+            &locale_values.iter().collect::<Vec<_>>()[..],
+        ));
+        missing_paths.push((
+            locale.to_owned(),
+            iter.missing_paths()
+                .iter()
+                .map(|path| path.join("."))
+                .collect(),
+        ));
         generate_locales_strings_instance(
-            &file,
+            &mut w,
             &format!("STRINGS_{}", locale.to_case(Case::ScreamingSnake)),
             pairs.iter(),
+            cfg.split_domains.then_some(&mut domain_modules),
         )
-        .with_context(|| {
+        .with_context(|| format!("generate strings for locale {}", locale))?;
+    }
+
+    missing_paths.sort_by(|a, b| a.0.cmp(&b.0));
+    report_missing_strings(&mut warnings, &missing_paths[..], cfg.max_missing_strings)?;
+    report_coverage(
+        &mut warnings,
+        storage.default_locale(),
+        &default_pairs[..],
+        &missing_paths[..],
+        cfg,
+    )?;
+
+    generate_locales_dynamic_lookup(
+        &mut w,
+        &default_pairs[..],
+        &missing_paths[..],
+        cfg.reload.then_some(&fallback_chains[..]),
+    )
+    .context("generate locales dynamic lookup")?;
+
+    if cfg.render_json {
+        generate_locales_render_json(&mut w).context("generate locales json rendering")?;
+    }
+
+    if cfg.icu {
+        generate_locales_icu(&mut w, storage, cfg)
+            .context("generate locales icu language identifiers")?;
+    }
+
+    diagnostics.sort_by(|a, b| a.locale.cmp(&b.locale));
+    generate_locales_diagnostics(&mut w, &diagnostics[..], cfg)
+        .context("generate locales diagnostics")?;
+
+    generate_locales_metrics(&mut w, default_pairs.len(), total_string_bytes)
+        .context("generate locales metrics")?;
+
+    w.flush()?;
+    warnings.finish()?;
+
+    duplicates.sort_by(|a, b| a.locale.cmp(&b.locale));
+    Ok(LocalesReport {
+        duplicates,
+        diagnostics,
+        missing_paths,
+        domain_modules: domain_modules
+            .into_iter()
+            .map(|(domain, code)| (domain_module_name(&domain), code))
+            .collect(),
+    })
+}
+
+/// number of missing strings named per locale when failing on too many missing strings
+const MISSING_STRINGS_LISTED: usize = 10;
+
+/// list the strings missing per locale (filled in by a fallback locale) as cargo warnings,
+/// the translation todo list, failing for locales missing more than the given maximum
+fn report_missing_strings(
+    warnings: &mut Warnings,
+    missing_paths: &[(String, Vec<String>)],
+    max_missing_strings: Option<usize>,
+) -> Result<()> {
+    for (locale, paths) in missing_paths {
+        for path in paths {
+            warnings.warn(format!(
+                "locale {} is missing string '{}', please add it to {}.yml!",
+                locale, path, locale
+            ));
+        }
+    }
+    let max = match max_missing_strings {
+        Some(max) => max,
+        None => return Ok(()),
+    };
+    let errors: Vec<String> = missing_paths
+        .iter()
+        .filter(|(_, paths)| paths.len() > max)
+        .map(|(locale, paths)| {
             format!(
-                "generate strings for locale {} in {}",
+                "locale {} is missing {} string(s), more than the maximum of {}: {}{}",
                 locale,
-                file_path.display()
+                paths.len(),
+                max,
+                paths.iter().take(MISSING_STRINGS_LISTED).join(", "),
+                if paths.len() > MISSING_STRINGS_LISTED {
+                    ", ..."
+                } else {
+                    ""
+                }
             )
-        })?;
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!(errors.join("\n")))
+    }
+}
+
+/// warn about the translation coverage of every incomplete locale,
+/// not counting the verbatim strings, failing for locales below the minimum coverage
+fn report_coverage(
+    warnings: &mut Warnings,
+    default_locale: &str,
+    default_pairs: &[StringValuePathPair],
+    missing_paths: &[(String, Vec<String>)],
+    cfg: &I18n,
+) -> Result<()> {
+    let is_verbatim = |path: &str| {
+        cfg.verbatim.iter().any(|verbatim| {
+            path == verbatim
+                || (path.starts_with(verbatim.as_str()) && path[verbatim.len()..].starts_with('.'))
+        })
+    };
+    let total = default_pairs
+        .iter()
+        .filter(|pair| !is_verbatim(&pair.path.join(".")))
+        .count();
+    let mut errors = Vec::new();
+    for (locale, paths) in missing_paths {
+        if locale == default_locale {
+            continue;
+        }
+        let translated = total - paths.iter().filter(|path| !is_verbatim(path)).count();
+        let coverage = if total == 0 {
+            100.0
+        } else {
+            (translated as f64 * 100.0) / total as f64
+        };
+        if translated < total {
+            warnings.warn(format!(
+                "locale {} translation coverage is {:.1}% ({}/{} keys)",
+                locale, coverage, translated, total
+            ));
+        }
+        if let Some(min_coverage) = cfg.min_coverage {
+            if coverage < min_coverage {
+                errors.push(format!(
+                    "locale {} has a translation coverage of {:.1}%, below the minimum of {:.1}%",
+                    locale, coverage, min_coverage
+                ));
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!(errors.join("\n")))
     }
+}
 
-    Ok(())
+/// values shared by multiple keys of a locale, as (value, key paths), most duplicated first
+#[derive(Debug)]
+pub struct LocaleDuplicates {
+    locale: String,
+    values: Vec<(String, Vec<String>)>,
+}
+
+impl LocaleDuplicates {
+    fn new(locale: &str, pairs: &[StringValuePathPair]) -> LocaleDuplicates {
+        let mut paths_by_value: HashMap<&str, Vec<String>> = HashMap::new();
+        for pair in pairs {
+            paths_by_value
+                .entry(pair.value.as_str())
+                .or_default()
+                .push(pair.path.join("."));
+        }
+        let values = paths_by_value
+            .into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .map(|(value, mut paths)| {
+                paths.sort();
+                (value.to_owned(), paths)
+            })
+            .sorted_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(&b.0)))
+            .collect();
+        LocaleDuplicates {
+            locale: locale.to_owned(),
+            values,
+        }
+    }
+}
+
+/// a value shared by several strings of a locale, as listed in the duplicates report
+#[derive(Serialize)]
+struct DuplicateValue<'a> {
+    value: &'a str,
+    count: usize,
+    saved_bytes: usize,
+    keys: &'a [String],
+}
+
+/// the duplicate values per locale, most shared first, as a JSON object by locale
+fn duplicates_report_json(duplicates: &[LocaleDuplicates]) -> Result<String> {
+    let report: BTreeMap<&str, Vec<DuplicateValue>> = duplicates
+        .iter()
+        .map(|locale| {
+            let values = locale
+                .values
+                .iter()
+                .map(|(value, paths)| DuplicateValue {
+                    value,
+                    count: paths.len(),
+                    saved_bytes: value.len() * (paths.len() - 1),
+                    keys: paths,
+                })
+                .collect();
+            (locale.locale.as_str(), values)
+        })
+        .collect();
+    Ok(serde_json::to_string_pretty(&report).context("serialize duplicate values report")? + "\n")
+}
+
+#[derive(Debug)]
+struct LocaleDiagnostics {
+    locale: String,
+    translated_keys: usize,
+    total_keys: usize,
+}
+
+/// the translation coverage of a locale, as listed in the coverage report
+#[derive(Serialize)]
+struct LocaleCoverage<'a> {
+    translated_keys: usize,
+    total_keys: usize,
+    missing: &'a [String],
+}
+
+/// the translation coverage per locale, with the keys it misses, as a JSON object by locale
+fn coverage_report_json(
+    diagnostics: &[LocaleDiagnostics],
+    missing_paths: &[(String, Vec<String>)],
+) -> Result<String> {
+    let report: BTreeMap<&str, LocaleCoverage> = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let missing = missing_paths
+                .iter()
+                .find(|(locale, _)| *locale == diagnostic.locale)
+                .map_or(&[][..], |(_, paths)| &paths[..]);
+            (
+                diagnostic.locale.as_str(),
+                LocaleCoverage {
+                    translated_keys: diagnostic.translated_keys,
+                    total_keys: diagnostic.total_keys,
+                    missing,
+                },
+            )
+        })
+        .collect();
+    Ok(serde_json::to_string_pretty(&report).context("serialize coverage report")? + "\n")
 }
 
 fn generate_locales_mod_docs(mut w: impl std::io::Write) -> Result<()> {
@@ -167,25 +577,171 @@ pub enum Locale {
     )?;
 
     w.write_all(
-        b"    pub fn all() -> impl Iterator<Item = Locale> {
-        vec![",
+        format!(
+            "    /// all supported locales, the default locale first
+    pub const ALL: &'static [Locale] = &[{}];
+
+    pub fn all() -> impl Iterator<Item = Locale> {{
+        Self::ALL.iter().copied()
+    }}
+",
+            std::iter::once(default_locale)
+                .chain(
+                    storage
+                        .all_locales()
+                        .filter(|locale| *locale != default_locale)
+                )
+                .map(|locale| format!("Self::{}", locale.to_case(Case::Pascal)))
+                .join(", ")
+        )
+        .as_bytes(),
     )?;
+
     w.write_all(
-        storage
-            .all_locales()
-            .map(|locale| format!("Self::{}", locale.to_case(Case::Pascal)))
-            .join(", ")
-            .as_bytes(),
+        format!(
+            "
+    pub fn is_default(&self) -> bool {{
+        matches!(self, Self::{})
+    }}
+",
+            default_locale.to_case(Case::Pascal)
+        )
+        .as_bytes(),
+    )?;
+
+    w.write_all(
+        format!(
+            "
+    pub fn is_supported_tag(tag: &str) -> bool {{
+        matches!(
+            tag.trim().to_lowercase().replace('_', \"-\").as_str(),
+            {}
+        )
+    }}
+",
+            storage
+                .all_locales()
+                .map(|locale| format!("\"{}\"", locale.to_case(Case::Kebab)))
+                .join(" | ")
+        )
+        .as_bytes(),
     )?;
+
     w.write_all(
-        b"].into_iter()
+        b"
+    pub fn from_accept_language(header: &str) -> Locale {
+        Self::negotiate_with_default(header, Self::default())
+    }
+
+    pub fn negotiate_with_default(header: &str, site_default: Locale) -> Locale {
+        Self::ranked(header, site_default)
+            .first()
+            .copied()
+            .unwrap_or(site_default)
+    }
+
+    /// all supported locales matching the ranges of the given header, in quality order,
+    /// without duplicates, the wildcard range matching the default locale
+    pub fn parse_accept_language_ranked(header: &str) -> Vec<Locale> {
+        Self::ranked(header, Self::default())
+    }
+
+    fn ranked(header: &str, wildcard: Locale) -> Vec<Locale> {
+        let mut ranges: Vec<(&str, f32)> = header
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';').map(str::trim);
+                let tag = parts.next().filter(|tag| !tag.is_empty())?;
+                // malformed weights (e.g. `q=abc` or `q=inf`) drop their range
+                let quality = parts
+                    .find_map(|param| param.strip_prefix(\"q=\"))
+                    .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())
+                    .filter(|quality| (0.0..=1.0).contains(quality))?;
+                if quality > 0.0 {
+                    Some((tag, quality))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        // stable sort, so ranges of equal quality keep the order of the header
+        ranges.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        let mut locales: Vec<Locale> = Vec::with_capacity(ranges.len());
+        for (tag, _) in ranges {
+            let locale = if tag == \"*\" {
+                Some(wildcard)
+            } else {
+                let tag = tag.replace('_', \"-\");
+                // drop trailing subtags until a supported locale matches (e.g. `zh-hant-tw` to `zh`)
+                let mut prefix = tag.as_str();
+                loop {
+                    if let Ok(locale) = Locale::try_from(prefix) {
+                        break Some(locale);
+                    }
+                    match prefix.rfind('-') {
+                        Some(end) => prefix = &prefix[..end],
+                        None => break None,
+                    }
+                }
+            };
+            if let Some(locale) = locale.filter(|locale| !locales.contains(locale)) {
+                locales.push(locale);
+            }
+        }
+        locales
+    }
+
+    pub fn parse_query(query_string: &str, key: &str) -> Option<Locale> {
+        let value = query_string
+            .trim_start_matches('?')
+            .split('&')
+            .map(|param| param.split_once('=').unwrap_or((param, \"\")))
+            .find(|(name, _)| percent_decode(name).as_deref() == Some(key))?
+            .1;
+        Locale::try_from(percent_decode(value)?.as_str()).ok()
+    }
+
+    /// strict match of the first label of a host (e.g. `fr.example.com:8080`),
+    /// which is only considered a subdomain when followed by at least two labels
+    pub fn from_subdomain(host: &str) -> Option<Locale> {
+        if host.starts_with('[') {
+            // ipv6 address
+            return None;
+        }
+        let host = host.split(':').next()?.trim_end_matches('.');
+        let mut labels = host.split('.');
+        let label = labels.next()?.to_ascii_lowercase();
+        if labels.count() < 2 {
+            return None;
+        }
+        Locale::all().find(|locale| locale.as_str() == label)
     }
 ",
     )?;
 
     w.write_all(
         b"
-    pub fn strings(&self) -> &Strings {
+    /// stable (FNV-1a) hash of the locale, user key and experiment, used to deterministically
+    /// assign experiment variants per locale, identical across runs, builds and platforms
+    pub fn variant_seed(&self, user_key: &str, experiment: &str) -> u64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for part in [self.as_str(), user_key, experiment] {
+            // 0xff never occurs in utf-8, so parts can't shift into one another
+            for byte in part.bytes().chain(std::iter::once(0xff)) {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+        hash
+    }
+
+    /// resolve a tag tolerantly (falling back to the default locale), together with its strings
+    pub fn resolve(tag: &str) -> (Locale, &'static Strings) {
+        let locale = Locale::try_from(tag).unwrap_or_default();
+        (locale, locale.strings())
+    }
+
+    pub fn strings(&self) -> &'static Strings {
         match self {
 ",
     )?;
@@ -230,6 +786,35 @@ pub enum Locale {
         b"
         }
     }
+
+    /// primary language subtag of the locale (e.g. `pt` for `pt-br`)
+    pub fn language_subtag(&self) -> &'static str {
+        match self {",
+    )?;
+    for locale in storage.all_locales() {
+        let tag = locale.to_case(Case::Kebab);
+        w.write_all(
+            format!(
+                r#"
+            Self::{} => "{}","#,
+                locale.to_case(Case::Pascal),
+                tag.split('-').next().unwrap_or(&tag),
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"
+        }
+    }
+
+    /// all supported locales of the given primary language subtag
+    /// (e.g. `pt`, `pt-br` and `pt-pt` for `pt`), in the order of `Locale::all`
+    pub fn variants_for_language(lang: &str) -> Vec<Locale> {
+        Locale::all()
+            .filter(|locale| locale.language_subtag().eq_ignore_ascii_case(lang.trim()))
+            .collect()
+    }
 }
 
 ",
@@ -239,7 +824,8 @@ pub enum Locale {
 
     w.write_all(
         format!(
-            "impl std::default::Default for Locale {{
+            "#[allow(clippy::derivable_impls)]
+impl std::default::Default for Locale {{
     fn default() -> Self {{
         Locale::{}
     }}
@@ -269,15 +855,29 @@ pub enum Locale {
         b"impl TryFrom<&str> for Locale {
     type Error = anyhow::Error;
     fn try_from(s: &str) -> Result<Self, Self::Error> {
-        match s.to_lowercase().trim() {
+        // only non-ascii input needs the (allocating) unicode lowercase,
+        // as ascii input is matched case insensitively by length first
+        let lowercase;
+        let tag = if s.is_ascii() {
+            s.trim()
+        } else {
+            lowercase = s.to_lowercase();
+            lowercase.trim()
+        };
+        match tag.len() {
 ",
     )?;
-    for locale in storage.all_locales() {
+    for locale in storage
+        .all_locales()
+        .map(|locale| locale.to_lowercase().trim().to_owned())
+        .sorted_by_key(|locale| locale.len())
+    {
         w.write_all(
             format!(
-                r#"            "{}" => Ok(Self::{}),
+                r#"            {} if tag.eq_ignore_ascii_case("{}") => Ok(Self::{}),
 "#,
-                locale.to_lowercase().trim(),
+                locale.len(),
+                locale,
                 locale.to_case(Case::Pascal)
             )
             .as_bytes(),
@@ -295,354 +895,4793 @@ pub enum Locale {
 ",
     )?;
 
-    // x. all good
+    // 5.1 strict parsing, with an error type that tells which tag was not recognized
 
-    Ok(())
-}
+    w.write_all(
+        format!(
+            "#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownLocaleError(pub String);
+
+impl std::fmt::Display for UnknownLocaleError {{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {{
+        write!(
+            f,
+            \"unknown locale '{{}}', expected one of: {}\",
+            self.0
+        )
+    }}
+}}
+
+impl std::error::Error for UnknownLocaleError {{}}
+
+impl std::str::FromStr for Locale {{
+    type Err = UnknownLocaleError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {{
+        Self::try_from(s).map_err(|_| UnknownLocaleError(s.to_owned()))
+    }}
+}}
 
-fn generate_locales_strings_struct(
-    mut w: impl std::io::Write,
-    mut paths: Vec<Vec<String>>,
-) -> Result<()> {
-    let mut layer: usize = 0;
-    while !paths.is_empty() {
-        if layer == 0 {
-            w.write_all(
-                b"pub struct Strings {
 ",
-            )?;
+            storage.all_locales().join(", ")
+        )
+        .as_bytes(),
+    )?;
+
+    // 6. decoding of url query components, used to parse a locale from a query string
+
+    w.write_all(
+        b"fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = |b: u8| (b as char).to_digit(16);
+                match (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                    (Some(high), Some(low)) => {
+                        decoded.push((high * 16 + low) as u8);
+                        i += 2;
+                    }
+                    _ => decoded.push(b'%'),
+                }
+            }
+            b => decoded.push(b),
         }
-        let mut previous: Option<String> = None;
-        let mut previous_property: Option<String> = None;
-        let mut retained_paths = Vec::new();
-        for path in paths {
-            // create new struct if needed
-            let current = if layer == 0 {
-                None
-            } else {
-                Some(path[layer - 1].clone())
-            };
-            if previous != current {
-                w.write_all(
-                    b"}
+        i += 1;
+    }
+    String::from_utf8(decoded).ok()
+}
 
 ",
-                )?;
-                w.write_all(
-                    format!(
-                        "pub struct Strings{} {{
-",
-                        path[..layer]
-                            .iter()
-                            .map(|s| s.to_case(Case::Pascal))
-                            .join("")
-                    )
-                    .as_bytes(),
-                )?;
-                previous = current;
-            }
+    )?;
 
-            let key = &path[layer];
-            let current_property = Some(key.clone());
-            let drop = path.len() == layer + 1;
+    // x. all good
 
-            // write struct property
-            if drop {
-                // str
-                w.write_all(
-                    format!(
-                        "    pub {}: &'static str,
-",
-                        key.to_case(Case::Snake)
-                    )
-                    .as_bytes(),
-                )?;
-            } else if current_property != previous_property {
-                // object
-                w.write_all(
-                    format!(
-                        "    pub {}: Strings{},
-",
-                        key.to_case(Case::Snake),
-                        path[..layer + 1]
-                            .iter()
-                            .map(|s| s.to_case(Case::Pascal))
-                            .join("")
-                    )
-                    .as_bytes(),
-                )?;
-                previous_property = current_property;
-            }
+    Ok(())
+}
 
-            // retain if we do not wish to drop
-            if !drop {
-                retained_paths.push(path);
+/// symbols of common currencies, per (ISO 4217) currency code, used by all locales
+const DEFAULT_CURRENCY_SYMBOLS: &[(&str, &str)] = &[
+    ("EUR", "\u{20AC}"),
+    ("GBP", "\u{A3}"),
+    ("JPY", "\u{A5}"),
+    ("USD", "$"),
+];
+
+/// globe with meridians, used as the flag of locales without a region subtag by default
+const DEFAULT_REGION_LESS_FLAG: &str = "\u{1F310}";
+
+/// the given locale tag in its conventional BCP 47 casing and hyphen separated
+/// (e.g. `zh-Hant-TW` for `zh-hant-tw` or `zh_hant_tw`)
+fn bcp47_tag(locale: &str) -> String {
+    locale
+        .split(&['-', '_'][..])
+        .enumerate()
+        .map(|(i, subtag)| match subtag.len() {
+            2 if i > 0 => subtag.to_ascii_uppercase(),
+            4 if i > 0 => subtag[..1].to_ascii_uppercase() + &subtag[1..].to_ascii_lowercase(),
+            _ => subtag.to_ascii_lowercase(),
+        })
+        .join("-")
+}
+
+/// the tag of the given locale for `hreflang` and `lang` attributes,
+/// the configured `hreflang` override or the canonicalized locale tag
+fn locale_hreflang(cfg: &I18n, locale: &str) -> Result<String> {
+    match cfg
+        .locale_settings(locale)
+        .and_then(|settings| settings.hreflang.as_deref())
+    {
+        Some(hreflang) => {
+            if hreflang.is_empty()
+                || !hreflang.split('-').all(|subtag| {
+                    !subtag.is_empty() && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+                })
+            {
+                return Err(anyhow!(
+                    "invalid hreflang '{}' for locale {}, expected hyphen separated alphanumeric subtags",
+                    hreflang,
+                    locale
+                ));
             }
+            Ok(hreflang.to_owned())
         }
-
-        layer += 1;
-        paths = retained_paths;
+        None => Ok(bcp47_tag(locale)),
     }
-    w.write_all(
-        b"}
-",
+}
+
+/// locales to take the strings missing for the given locale from, in order,
+/// before falling back to the default locale
+fn locale_fallback_chain(cfg: &I18n, storage: &Storage, locale: &str) -> Result<Vec<String>> {
+    let mut chain = Vec::new();
+    collect_locale_fallbacks(
+        cfg,
+        storage,
+        locale,
+        &mut vec![locale.to_owned()],
+        &mut chain,
     )?;
+    Ok(chain)
+}
+
+fn collect_locale_fallbacks(
+    cfg: &I18n,
+    storage: &Storage,
+    locale: &str,
+    visiting: &mut Vec<String>,
+    chain: &mut Vec<String>,
+) -> Result<()> {
+    let fallbacks = cfg
+        .locale_settings(locale)
+        .map(|settings| &settings.fallbacks[..])
+        .unwrap_or_default();
+    if !fallbacks.is_empty() && locale == storage.default_locale() {
+        return Err(anyhow!(
+            "default locale {} cannot have fallbacks, as it defines all strings",
+            locale
+        ));
+    }
+    for fallback in fallbacks {
+        if fallback == locale {
+            return Err(anyhow!("locale {} cannot be its own fallback", locale));
+        }
+        if fallback == storage.default_locale() {
+            return Err(anyhow!(
+                "fallback {} of locale {} is the default locale, which is always the last fallback",
+                fallback,
+                locale
+            ));
+        }
+        if !storage.all_locales().any(|other| other == fallback) {
+            return Err(anyhow!(
+                "fallback {} of locale {} is not a supported locale",
+                fallback,
+                locale
+            ));
+        }
+        if visiting.contains(fallback) {
+            return Err(anyhow!(
+                "fallbacks of locale {} form a cycle: {} -> {}",
+                visiting[0],
+                visiting.join(" -> "),
+                fallback
+            ));
+        }
+        if !chain.contains(fallback) {
+            chain.push(fallback.clone());
+            visiting.push(fallback.clone());
+            collect_locale_fallbacks(cfg, storage, fallback, visiting, chain)?;
+            visiting.pop();
+        }
+    }
     Ok(())
 }
 
-fn generate_locales_strings_struct_methods(
+/// the flag emoji for a two letter region code, made out of its regional indicator symbols,
+/// or a subdivision flag (tag sequence) for a region code with subdivision (e.g. `GB-WLS`),
+/// any other flag is expected to be an emoji already and returned as is
+fn flag_emoji(flag: &str) -> String {
+    let flag = flag.trim();
+    let mut subtags = flag.split('-');
+    match (subtags.next(), subtags.next(), subtags.next()) {
+        (Some(region), None, None) if is_region_subtag(region) => region_flag(region),
+        (Some(region), Some(subdivision), None)
+            if is_region_subtag(region) && is_subdivision_subtag(subdivision) =>
+        {
+            subdivision_flag(region, subdivision)
+        }
+        _ => flag.to_owned(),
+    }
+}
+
+/// the flag emoji derived from the region (and subdivision) subtags of the given locale tag
+/// (e.g. `pt-br` or `en-gb-wls`), `None` for locales without a region subtag
+fn locale_tag_flag_emoji(tag: &str) -> Option<String> {
+    // skip the language and optional (four letter) script subtags
+    let mut subtags = tag
+        .split('-')
+        .skip(1)
+        .skip_while(|subtag| subtag.len() == 4);
+    let region = subtags.next().filter(|subtag| is_region_subtag(subtag))?;
+    Some(match subtags.next() {
+        Some(subdivision) if is_subdivision_subtag(subdivision) => {
+            subdivision_flag(region, subdivision)
+        }
+        _ => region_flag(region),
+    })
+}
+
+fn is_region_subtag(subtag: &str) -> bool {
+    subtag.len() == 2 && subtag.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+fn is_subdivision_subtag(subtag: &str) -> bool {
+    (1..=3).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+fn region_flag(region: &str) -> String {
+    region
+        .to_ascii_uppercase()
+        .chars()
+        .filter_map(|c| char::from_u32(0x1F1E6 + (c as u32 - 'A' as u32)))
+        .collect()
+}
+
+/// black flag, followed by the region and subdivision as tag characters and a cancel tag
+fn subdivision_flag(region: &str, subdivision: &str) -> String {
+    std::iter::once('\u{1F3F4}')
+        .chain(
+            region
+                .chars()
+                .chain(subdivision.chars())
+                .filter_map(|c| char::from_u32(0xE0000 + c.to_ascii_lowercase() as u32)),
+        )
+        .chain(std::iter::once('\u{E007F}'))
+        .collect()
+}
+
+/// `From<&str>` cannot be implemented next to `TryFrom<&str>`, due to the blanket
+/// `TryFrom` impl for all `Into` types, so an inherent function keeps `Locale::from` working
+fn generate_locales_from_shim(
     mut w: impl std::io::Write,
     storage: &Storage,
+    cfg: &I18n,
 ) -> Result<()> {
     w.write_all(
-        b"
-impl StringsSiteLocales {
-    pub fn iter(&self) -> impl Iterator<Item = (Locale, &str)> {
-        vec![
+        b"impl Locale {
+    /// Lossy conversion from a (case insensitive) locale tag, dropping trailing subtags
+    /// until a supported locale matches (e.g. `en-US` to `en`, `zh-Hant-TW` to `zh-Hant`),
+    /// falling back to the default locale, usable in const contexts
+    /// (e.g. `const NL: Locale = Locale::from_tag_const(\"nl\");`).
+    pub const fn from_tag_const(tag: &str) -> Locale {
+        let tag = tag.as_bytes();
+        // slices cannot be taken in a const fn, so the tag is trimmed by index
+        let mut start = 0;
+        while start < tag.len() && tag[start].is_ascii_whitespace() {
+            start += 1;
+        }
+        let mut end = tag.len();
+        while end > start && tag[end - 1].is_ascii_whitespace() {
+            end -= 1;
+        }
+        loop {
 ",
     )?;
     for locale in storage.all_locales() {
         w.write_all(
             format!(
-                r#"            (Locale::{}, self.{}),
-"#,
-                locale.to_case(Case::Pascal),
-                locale.to_case(Case::Snake)
+                "            if tag_eq_ignore_ascii_case(tag, start, end, b{:?}) {{
+                return Self::{};
+            }}
+",
+                locale.to_lowercase(),
+                locale.to_case(Case::Pascal)
             )
             .as_bytes(),
         )?;
     }
     w.write_all(
-        b"        ]
-        .into_iter()
+        format!(
+            "            // drop the last subtag, and its separator
+            while end > start && tag[end - 1] != b'-' && tag[end - 1] != b'_' {{
+                end -= 1;
+            }}
+            if end == start {{
+                return Self::{};
+            }}
+            end -= 1;
+        }}
+    }}
+
+    /// Lossy conversion from str, falling back to the default locale,
+    /// kept for compatibility, prefer `Locale::try_from` instead.
+",
+            storage.default_locale().to_case(Case::Pascal)
+        )
+        .as_bytes(),
+    )?;
+    if cfg.deprecate_from {
+        w.write_all(
+            b"    #[deprecated(note = \"use `Locale::try_from`, which does not hide unsupported locales\")]
+",
+        )?;
+    }
+    w.write_all(
+        b"    #[allow(clippy::should_implement_trait)]
+    pub fn from(s: &str) -> Locale {
+        if s.is_ascii() {
+            Locale::from_tag_const(s)
+        } else {
+            // non-ascii input might still lowercase (and trim) into a supported tag
+            Locale::from_tag_const(s.to_lowercase().trim())
+        }
+    }
+}
+
+/// whether `s[start..end]` equals the given lowercase tag, ignoring ascii case
+const fn tag_eq_ignore_ascii_case(s: &[u8], start: usize, end: usize, tag: &[u8]) -> bool {
+    if end - start != tag.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < tag.len() {
+        if s[start + i].to_ascii_lowercase() != tag[i] {
+            return false;
+        }
+        i += 1;
     }
+    true
 }
+
 ",
     )?;
-
     Ok(())
 }
 
-fn generate_locales_strings_instance<'a>(
-    mut w: impl std::io::Write,
-    const_name: &str,
-    pairs: impl Iterator<Item = &'a StringValuePathPair>,
-) -> Result<()> {
+fn generate_locales_alternate_links(mut w: impl std::io::Write, cfg: &I18n) -> Result<()> {
     w.write_all(
         format!(
-            "
-const {}: Strings = Strings {{
+            "/// query parameter used to select a locale
+pub const LOCALE_QUERY_KEY: &str = {:?};
+
+/// a `<link rel=\"alternate\" hreflang=\"..\" href=\"..\">` of a page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlternateLink {{
+    pub hreflang: &'static str,
+    pub href: String,
+}}
+
+impl Locale {{
+    /// alternate links of the page at the given endpoint for all locales,
+    /// and the `x-default` link which leaves the locale up to negotiation
+    pub fn alternate_links(endpoint: &str) -> Vec<AlternateLink> {{
+        let path = format!(\"/{{}}\", endpoint.trim_start_matches('/'));
+        Locale::all()
+            .map(|locale| AlternateLink {{
+                hreflang: locale.hreflang(),
+                href: format!(\"{{}}?{{}}={{}}\", path, LOCALE_QUERY_KEY, locale.as_str()),
+            }})
+            .chain(std::iter::once(AlternateLink {{
+                hreflang: \"x-default\",
+                href: path.clone(),
+            }}))
+            .collect()
+    }}
+
+    /// alternate links of all locales except the current one, `x-default` included
+    pub fn alternate_links_excluding(endpoint: &str, current: Locale) -> Vec<AlternateLink> {{
+        Self::alternate_links(endpoint)
+            .into_iter()
+            .filter(|link| link.hreflang != current.hreflang())
+            .collect()
+    }}
+
+    /// absolute url of the page at the given endpoint for this locale, e.g. to be used
+    /// as the `<link rel=\"canonical\">` of the page, origin being the scheme and host
+    pub fn canonical_url(&self, origin: &str, endpoint: &str) -> String {{
+        format!(
+            \"{{}}/{{}}?{{}}={{}}\",
+            origin.trim_end_matches('/'),
+            endpoint.trim_start_matches('/'),
+            LOCALE_QUERY_KEY,
+            self.as_str()
+        )
+    }}
+}}
+
 ",
-            const_name
+            cfg.locale_query_key.as_deref().unwrap_or("lang")
         )
         .as_bytes(),
     )?;
-    let mut previous_layer = 0;
-    let mut previous_path = None;
-    // for each locale string...
-    for pair in pairs {
-        let current_layer = pair.path.len() - 1;
+    Ok(())
+}
 
-        // prior to writing key,
-        // we need to ensure we're within the correct struct constructor
-        let mut overlap_layer = 0;
-        if let Some(previous_path) = previous_path {
-            for (key_a, key_b) in pair.path.iter().zip(previous_path) {
-                if key_a != key_b {
-                    break;
-                }
-                overlap_layer += 1;
-            }
-        }
-        if overlap_layer < previous_layer {
-            for idx in 0..(previous_layer - overlap_layer) {
-                w.write_all(
-                    format!(
-                        "{}}},
-",
-                        "    ".repeat(previous_layer - idx)
-                    )
-                    .as_bytes(),
-                )?;
-            }
-        }
-        while overlap_layer < current_layer {
-            let key = &pair.path[overlap_layer];
-            w.write_all(
-                format!(
-                    "{}{}: Strings{} {{
-",
-                    "    ".repeat(overlap_layer + 1),
-                    key.to_case(Case::Snake),
-                    pair.path[..=overlap_layer]
-                        .iter()
-                        .map(|s| s.to_case(Case::Pascal))
-                        .join(""),
-                )
-                .as_bytes(),
-            )?;
-            overlap_layer += 1;
+/// font stack used for locales without a configured `font_stack`
+const DEFAULT_FONT_STACK: &str = "sans-serif";
+
+fn generate_locales_text_helpers(
+    mut w: impl std::io::Write,
+    storage: &Storage,
+    cfg: &I18n,
+) -> Result<()> {
+    w.write_all(
+        b"#[derive(Debug, PartialEq, Eq, Clone, Copy, std::hash::Hash)]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+impl Direction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ltr => \"ltr\",
+            Self::Rtl => \"rtl\",
         }
+    }
+}
 
-        assert!(overlap_layer == current_layer);
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, \"{}\", self.as_str())
+    }
+}
 
-        // write the actual locale string...
-        let key = &pair.path[current_layer];
+impl Locale {
+    pub fn ellipsis(&self) -> &'static str {
+        match self {
+",
+    )?;
+    for locale in storage.all_locales() {
+        let ellipsis = cfg
+            .locale_settings(locale)
+            .and_then(|settings| settings.ellipsis.as_deref())
+            .unwrap_or("\u{2026}");
         w.write_all(
             format!(
-                r#################"{}{}: {},
-"#################,
-                "    ".repeat(current_layer + 1),
-                key.to_case(Case::Snake),
-                pair.value
+                "            Self::{} => {:?},
+",
+                locale.to_case(Case::Pascal),
+                ellipsis
             )
             .as_bytes(),
         )?;
-        // keep track of the previous path to be handle the more complex nesting cases
-        previous_path = Some(&pair.path);
-        previous_layer = current_layer;
     }
-    // add all the final curly brackets... including the last one
-    while previous_layer > 0 {
-        previous_layer -= 1;
+    w.write_all(
+        b"        }
+    }
+
+    pub fn font_css(&self) -> &'static str {
+        match self {
+",
+    )?;
+    for locale in storage.all_locales() {
+        let font_stack = cfg
+            .locale_settings(locale)
+            .and_then(|settings| settings.font_stack.as_deref())
+            .unwrap_or(DEFAULT_FONT_STACK);
         w.write_all(
             format!(
-                "{}}},
+                "            Self::{} => {:?},
 ",
-                "    ".repeat(previous_layer + 1)
+                locale.to_case(Case::Pascal),
+                format!("--font-body: {};", font_stack.trim().trim_end_matches(';'))
             )
             .as_bytes(),
         )?;
     }
     w.write_all(
-        b"};
+        b"        }
+    }
+
+    pub fn flag(&self) -> Option<&'static str> {
+        match self {
 ",
     )?;
-    Ok(())
-}
+    for locale in storage.all_locales() {
+        let flag = cfg
+            .locale_settings(locale)
+            .and_then(|settings| settings.flag.as_deref())
+            .map(flag_emoji);
+        w.write_all(
+            format!(
+                "            Self::{} => {:?},
+",
+                locale.to_case(Case::Pascal),
+                flag
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"        }
+    }
 
-struct LocaleStringWithDefaultIter<
-    T: Iterator<Item = StringValuePathPair>,
-    U: Iterator<Item = StringValuePathPair>,
-> {
-    locale: String,
-    pairs: Box<T>,
+    pub fn emoji_flag_sequence(&self) -> Option<&'static str> {
+        match self {
+",
+    )?;
+    let region_less_flag = cfg
+        .region_less_flag
+        .as_deref()
+        .unwrap_or(DEFAULT_REGION_LESS_FLAG)
+        .trim();
+    for locale in storage.all_locales() {
+        let flag = cfg
+            .locale_settings(locale)
+            .and_then(|settings| settings.flag.as_deref())
+            .map(flag_emoji)
+            .or_else(|| locale_tag_flag_emoji(locale))
+            .or_else(|| (!region_less_flag.is_empty()).then(|| region_less_flag.to_owned()));
+        w.write_all(
+            format!(
+                "            Self::{} => {:?},
+",
+                locale.to_case(Case::Pascal),
+                flag
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"        }
+    }
+
+    pub fn direction(&self) -> Direction {
+        match self {
+",
+    )?;
+    let mut directions = Vec::new();
+    for locale in storage.all_locales() {
+        let direction = cfg
+            .locale_settings(locale)
+            .and_then(|settings| settings.direction.as_deref())
+            .unwrap_or(if locale == MIRROR_LOCALE {
+                "rtl"
+            } else {
+                "ltr"
+            });
+        if direction != "ltr" && direction != "rtl" {
+            return Err(anyhow!(
+                "invalid direction '{}' for locale {}, expected ltr or rtl",
+                direction,
+                locale
+            ));
+        }
+        w.write_all(
+            format!(
+                "            Self::{} => Direction::{},
+",
+                locale.to_case(Case::Pascal),
+                direction.to_case(Case::Pascal)
+            )
+            .as_bytes(),
+        )?;
+        directions.push((locale, direction));
+    }
+    w.write_all(
+        b"        }
+    }
+
+    /// `lang` and `dir` attributes of the `<html>` element (e.g. `lang=\"pt-BR\" dir=\"ltr\"`)
+    pub fn html_lang_meta(&self) -> &'static str {
+        match self {
+",
+    )?;
+    for (locale, direction) in directions {
+        w.write_all(
+            format!(
+                "            Self::{} => {:?},
+",
+                locale.to_case(Case::Pascal),
+                format!(
+                    "lang=\"{}\" dir=\"{}\"",
+                    locale_hreflang(cfg, locale)?,
+                    direction
+                )
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"        }
+    }
+
+    /// well-formed tag of the locale for `hreflang` (and `lang`) attributes (e.g. `zh-Hans`)
+    pub fn hreflang(&self) -> &'static str {
+        match self {
+",
+    )?;
+    for locale in storage.all_locales() {
+        w.write_all(
+            format!(
+                "            Self::{} => {:?},
+",
+                locale.to_case(Case::Pascal),
+                locale_hreflang(cfg, locale)?
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"        }
+    }
+
+",
+    )?;
+    if cfg.bidi_isolation {
+        w.write_all(
+            b"    /// isolate a (dynamic) value interpolated in text of this locale, such that the value
+    /// keeps its own direction without the bidi algorithm reordering the surrounding text
+    /// and punctuation, using a left-to-right or right-to-left isolate when the direction
+    /// of the value is known (e.g. a string of another locale), a first strong isolate otherwise
+    pub fn isolate(&self, value: &str, direction: Option<Direction>) -> String {
+        let isolate = match direction {
+            Some(Direction::Ltr) => '\\u{2066}',
+            Some(Direction::Rtl) => '\\u{2067}',
+            None => '\\u{2068}',
+        };
+        format!(\"{}{}\\u{2069}\", isolate, value)
+    }
+
+",
+        )?;
+    } else {
+        w.write_all(
+            b"    /// bidi isolation is disabled, see the `bidi_isolation` i18n config
+    pub fn isolate(&self, value: &str, _direction: Option<Direction>) -> String {
+        value.to_owned()
+    }
+
+",
+        )?;
+    }
+    w.write_all(
+        b"    /// format the given percentage (e.g. `12.5` for 12.5%) with the given number of fraction digits
+    pub fn format_percent(&self, value: f64, fraction_digits: usize) -> String {
+        let mut number = format!(\"{:.*}\", fraction_digits, value);
+        // values rounding to zero are not negative
+        if number.starts_with('-') && number[1..].chars().all(|c| c == '0' || c == '.') {
+            number.remove(0);
+        }
+        match self {
+",
+    )?;
+    for locale in storage.all_locales() {
+        let settings = cfg.locale_settings(locale);
+        let decimal_separator = settings
+            .and_then(|settings| settings.decimal_separator.as_deref())
+            .unwrap_or(".");
+        let percent_pattern = settings
+            .and_then(|settings| settings.percent_pattern.as_deref())
+            .unwrap_or("{}%");
+        if percent_pattern.matches("{}").count() != 1
+            || percent_pattern.matches(&['{', '}'][..]).count() != 2
+        {
+            return Err(anyhow!(
+                "invalid percent pattern '{}' for locale {}, expected a single '{{}}' placeholder",
+                percent_pattern,
+                locale
+            ));
+        }
+        let number = if decimal_separator == "." {
+            "number".to_owned()
+        } else {
+            format!("number.replace('.', {:?})", decimal_separator)
+        };
+        w.write_all(
+            format!(
+                "            Self::{} => format!({:?}, {}),
+",
+                locale.to_case(Case::Pascal),
+                percent_pattern,
+                number
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"        }
+    }
+
+",
+    )?;
+    for (name, default_pattern) in [
+        ("date", "%Y-%m-%d"),
+        ("time", "%H:%M"),
+        ("datetime", "%Y-%m-%d %H:%M"),
+    ] {
+        w.write_all(
+            format!(
+                "    /// {} format pattern of this locale, to be passed to a (strftime-like) formatter
+    pub fn {}_pattern(&self) -> &'static str {{
+        match self {{
+",
+                name, name
+            )
+            .as_bytes(),
+        )?;
+        for locale in storage.all_locales() {
+            let pattern = cfg
+                .locale_settings(locale)
+                .and_then(|settings| match name {
+                    "date" => settings.date_pattern.as_deref(),
+                    "time" => settings.time_pattern.as_deref(),
+                    _ => settings.datetime_pattern.as_deref(),
+                })
+                .unwrap_or(default_pattern);
+            w.write_all(
+                format!(
+                    "            Self::{} => {:?},
+",
+                    locale.to_case(Case::Pascal),
+                    pattern
+                )
+                .as_bytes(),
+            )?;
+        }
+        w.write_all(
+            b"        }
+    }
+
+",
+        )?;
+    }
+    w.write_all(
+        b"    /// format the given amount of the given (ISO 4217) currency code (e.g. `EUR`),
+    /// using the code itself for currencies without a known symbol
+    pub fn format_currency(&self, amount: f64, currency: &str) -> String {
+        let number = format!(\"{:.2}\", amount.abs());
+        let (integer, fraction) = number.split_at(number.len().saturating_sub(3));
+        let fraction = fraction.trim_start_matches('.');
+        // amounts rounding to zero are not negative
+        let sign = if amount < 0.0 && number.chars().any(|c| c != '0' && c != '.') {
+            \"-\"
+        } else {
+            \"\"
+        };
+        match self {
+",
+    )?;
+    for locale in storage.all_locales() {
+        let settings = cfg.locale_settings(locale);
+        let decimal_separator = settings
+            .and_then(|settings| settings.decimal_separator.as_deref())
+            .unwrap_or(".");
+        let group_separator = settings
+            .and_then(|settings| settings.group_separator.as_deref())
+            .unwrap_or(",");
+        let currency_pattern = settings
+            .and_then(|settings| settings.currency_pattern.as_deref())
+            .unwrap_or("{symbol}{amount}");
+        if currency_pattern.matches("{amount}").count() != 1
+            || currency_pattern.matches("{symbol}").count() != 1
+            || currency_pattern.matches(&['{', '}'][..]).count() != 4
+        {
+            return Err(anyhow!(
+                "invalid currency pattern '{}' for locale {}, expected a single '{{amount}}' and '{{symbol}}' placeholder",
+                currency_pattern,
+                locale
+            ));
+        }
+        let mut symbols: BTreeMap<String, &str> = DEFAULT_CURRENCY_SYMBOLS
+            .iter()
+            .map(|(code, symbol)| ((*code).to_owned(), *symbol))
+            .collect();
+        if let Some(settings) = settings {
+            for (code, symbol) in settings.currency_symbols.iter() {
+                symbols.insert(code.to_ascii_uppercase(), symbol.as_str());
+            }
+        }
+        w.write_all(
+            format!(
+                "            Self::{} => {{
+                let symbol = match currency.to_ascii_uppercase().as_str() {{
+",
+                locale.to_case(Case::Pascal)
+            )
+            .as_bytes(),
+        )?;
+        for (code, symbol) in symbols.iter() {
+            w.write_all(
+                format!(
+                    "                    {:?} => {:?},
+",
+                    code, symbol
+                )
+                .as_bytes(),
+            )?;
+        }
+        w.write_all(
+            format!(
+                "                    _ => currency,
+                }};
+                let amount = format!(
+                    \"{{}}{{}}{{}}\",
+                    Self::group_digits(integer, {:?}),
+                    {:?},
+                    fraction
+                );
+                format!({:?}, sign = sign, amount = amount, symbol = symbol)
+            }}
+",
+                group_separator,
+                decimal_separator,
+                format!("{{sign}}{}", currency_pattern)
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"        }
+    }
+
+    fn group_digits(integer: &str, separator: &str) -> String {
+        let first = match integer.len() % 3 {
+            0 => integer.len().min(3),
+            n => n,
+        };
+        let mut grouped = integer[..first].to_owned();
+        let mut start = first;
+        while start < integer.len() {
+            grouped.push_str(separator);
+            grouped.push_str(&integer[start..start + 3]);
+            start += 3;
+        }
+        grouped
+    }
+
+",
+    )?;
+    w.write_all(
+        b"    pub fn truncate(&self, s: &str, max_graphemes: usize) -> String {
+        use unicode_segmentation::UnicodeSegmentation;
+
+        if s.graphemes(true).nth(max_graphemes).is_none() {
+            return s.to_owned();
+        }
+        let ellipsis = self.ellipsis();
+        let ellipsis_len = ellipsis.graphemes(true).count();
+        if ellipsis_len > max_graphemes {
+            return ellipsis.graphemes(true).take(max_graphemes).collect();
+        }
+        let budget = max_graphemes - ellipsis_len;
+        let mut truncated: String = s.graphemes(true).take(budget).collect();
+        truncated.truncate(truncated.trim_end().len());
+        truncated.push_str(ellipsis);
+        truncated
+    }
+
+    pub fn slugify(&self, title: &str) -> String {
+        let transliterate: fn(char) -> Option<&'static str> = match self {
+",
+    )?;
+    let mut scripts = vec![DEFAULT_SCRIPT];
+    for locale in storage.all_locales() {
+        let script = cfg
+            .locale_settings(locale)
+            .and_then(|settings| settings.script.as_deref())
+            .unwrap_or(DEFAULT_SCRIPT);
+        if !scripts.contains(&script) {
+            scripts.push(script);
+        }
+        w.write_all(
+            format!(
+                "            Self::{} => {},
+",
+                locale.to_case(Case::Pascal),
+                transliteration_fn_name(script)
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        format!(
+            "        }};
+        let mut slug = String::with_capacity(title.len());
+        let mut pending_hyphen = false;
+        for c in title.chars().flat_map(char::to_lowercase) {{
+            let transliterated = if c.is_ascii_alphanumeric() {{
+                None
+            }} else {{
+                transliterate(c).or_else(|| {}(c))
+            }};
+            if transliterated.is_none() && !c.is_alphanumeric() {{
+                pending_hyphen = true;
+                continue;
+            }}
+            if pending_hyphen && !slug.is_empty() {{
+                slug.push('-');
+            }}
+            pending_hyphen = false;
+            match transliterated {{
+                Some(ascii) => slug.push_str(ascii),
+                None => slug.push(c),
+            }}
+        }}
+        slug
+    }}
+}}
+",
+            transliteration_fn_name(DEFAULT_SCRIPT)
+        )
+        .as_bytes(),
+    )?;
+    for script in scripts {
+        generate_transliteration_fn(&mut w, script)
+            .with_context(|| format!("generate transliteration for script {}", script))?;
+    }
+    w.write_all(
+        b"
+",
+    )?;
+    Ok(())
+}
+
+/// gender variants in order of preference, `neutral` being the fallback of any missing variant
+const GENDER_VARIANTS: &[&str] = &["male", "female", "neutral"];
+
+/// variants defined for the given gendered path by the given locale, empty if not defined at all
+fn gender_variants(storage: &Storage, locale: &str, path: &str) -> Result<Vec<String>> {
+    let mut variants = vec![];
+    for pair in storage
+        .get(locale)
+        .into_iter()
+        .flat_map(|locales| locales.iter())
+    {
+        let pair_path = pair.path.join(".");
+        let variant = match pair_path
+            .strip_prefix(path)
+            .and_then(|rest| rest.strip_prefix('.'))
+        {
+            Some(variant) => variant,
+            None if pair_path == path => {
+                return Err(anyhow!(
+                    "gendered string '{}' of locale {} is not an object of variants",
+                    path,
+                    locale
+                ))
+            }
+            None => continue,
+        };
+        if !GENDER_VARIANTS.contains(&variant) {
+            return Err(anyhow!(
+                "unknown variant '{}' of gendered string '{}' in locale {}, expected one of: {}",
+                variant,
+                path,
+                locale,
+                GENDER_VARIANTS.join(", ")
+            ));
+        }
+        if pair.feature.is_some() {
+            return Err(anyhow!(
+                "variant '{}' of gendered string '{}' in locale {} cannot be feature gated",
+                variant,
+                path,
+                locale
+            ));
+        }
+        variants.push(variant.to_owned());
+    }
+    variants.sort_by_key(|variant| GENDER_VARIANTS.iter().position(|v| v == variant));
+    Ok(variants)
+}
+
+/// methods of `Locale` which a plural or gendered string accessor may not shadow
+const LOCALE_METHODS: &[&str] = &[
+    "a11y",
+    "all",
+    "alternate_links",
+    "alternate_links_excluding",
+    "as_str",
+    "canonical_url",
+    "completeness",
+    "current",
+    "date_pattern",
+    "datetime_pattern",
+    "direction",
+    "effective",
+    "ellipsis",
+    "emoji_flag_sequence",
+    "flag",
+    "font_css",
+    "format_currency",
+    "format_percent",
+    "from",
+    "from_accept_language",
+    "from_subdomain",
+    "from_tag_const",
+    "hreflang",
+    "html_lang_meta",
+    "is_default",
+    "is_supported_tag",
+    "isolate",
+    "language_subtag",
+    "negotiate_with_default",
+    "parse_accept_language_ranked",
+    "parse_query",
+    "plural_category",
+    "relative_time",
+    "resolve",
+    "slugify",
+    "strings",
+    "supports",
+    "time_pattern",
+    "to_langid",
+    "truncate",
+    "unavailable_notice",
+    "variant_seed",
+    "variants_for_language",
+];
+
+/// name of the `Locale` accessor of the plural or gendered string at the given dotted path
+fn locale_accessor_name(path: &str) -> String {
+    path.split('.').join("_").to_case(Case::Snake)
+}
+
+/// fail in case the `Locale` accessor of a plural or gendered string
+/// collides with another `Locale` method or accessor
+fn check_locale_accessor_names(storage: &Storage, cfg: &I18n) -> Result<()> {
+    let plural_paths = if cfg.plurals {
+        storage.plural_paths()
+    } else {
+        Vec::new()
+    };
+    let mut names: HashMap<String, &str> = HashMap::new();
+    for path in cfg.gendered.iter().chain(plural_paths.iter()) {
+        let name = locale_accessor_name(path);
+        if LOCALE_METHODS.contains(&name.as_str()) {
+            return Err(anyhow!(
+                "accessor of string '{}' collides with the Locale::{} method",
+                path,
+                name
+            ));
+        }
+        if let Some(other) = names.insert(name.clone(), path) {
+            return Err(anyhow!(
+                "accessor '{}' of string '{}' collides with the accessor of string '{}'",
+                name,
+                path,
+                other
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn generate_locales_gendered_accessors(
+    mut w: impl std::io::Write,
+    storage: &Storage,
+    gendered: &[String],
+) -> Result<()> {
+    if gendered.is_empty() {
+        return Ok(());
+    }
+
+    w.write_all(
+        b"#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Gender {
+    Male,
+    Female,
+    #[default]
+    Neutral,
+}
+
+impl Locale {
+",
+    )?;
+    for path in gendered {
+        let variants = gender_variants(storage, storage.default_locale(), path)?;
+        if !variants.iter().any(|variant| variant == "neutral") {
+            return Err(anyhow!(
+                "gendered string '{}' of default locale {} has no neutral variant",
+                path,
+                storage.default_locale()
+            ));
+        }
+        for locale in storage.all_locales() {
+            let locale_variants = gender_variants(storage, locale, path)?;
+            if !locale_variants.is_empty() && locale_variants != variants {
+                return Err(anyhow!(
+                    "gendered string '{}' of locale {} defines variants [{}] while the default locale defines [{}]",
+                    path,
+                    locale,
+                    locale_variants.join(", "),
+                    variants.join(", ")
+                ));
+            }
+        }
+        let field = path
+            .split('.')
+            .map(|segment| segment.to_case(Case::Snake))
+            .join(".");
+        w.write_all(
+            format!(
+                "    pub fn {}(&self, gender: Gender) -> &'static str {{
+        let strings = self.strings();
+        match gender {{
+",
+                locale_accessor_name(path)
+            )
+            .as_bytes(),
+        )?;
+        for variant in GENDER_VARIANTS {
+            let selected = if variants.iter().any(|v| v == variant) {
+                variant
+            } else {
+                "neutral"
+            };
+            w.write_all(
+                format!(
+                    "            Gender::{} => strings.{}.{},
+",
+                    variant.to_case(Case::Pascal),
+                    field,
+                    selected
+                )
+                .as_bytes(),
+            )?;
+        }
+        w.write_all(
+            b"        }
+    }
+",
+        )?;
+    }
+    w.write_all(
+        b"}
+
+",
+    )?;
+    Ok(())
+}
+
+/// CLDR cardinal plural rules (of integers) of the languages listed
+struct PluralRule {
+    languages: &'static [&'static str],
+    /// conditions on `n` checked in order, with the category selected by the first one to match
+    conditions: &'static [(&'static str, &'static str)],
+    /// category of the counts matching none of the conditions
+    otherwise: &'static str,
+}
+
+/// the plural rules by primary language subtag, other languages follow the rule of english
+const PLURAL_RULES: &[PluralRule] = &[
+    PluralRule {
+        languages: &["id", "ja", "km", "ko", "lo", "ms", "my", "th", "vi", "zh"],
+        conditions: &[],
+        otherwise: "Other",
+    },
+    PluralRule {
+        languages: &["am", "bn", "fa", "gu", "hi", "kn", "zu"],
+        conditions: &[("One", "n <= 1")],
+        otherwise: "Other",
+    },
+    PluralRule {
+        languages: &["fr", "pt"],
+        conditions: &[
+            ("One", "n <= 1"),
+            ("Many", "n != 0 && matches!(n % 1_000_000, 0)"),
+        ],
+        otherwise: "Other",
+    },
+    PluralRule {
+        languages: &["ca", "es", "it"],
+        conditions: &[
+            ("One", "n == 1"),
+            ("Many", "n != 0 && matches!(n % 1_000_000, 0)"),
+        ],
+        otherwise: "Other",
+    },
+    PluralRule {
+        languages: &["be", "ru", "uk"],
+        conditions: &[
+            ("One", "n % 10 == 1 && n % 100 != 11"),
+            (
+                "Few",
+                "(2..=4).contains(&(n % 10)) && !(12..=14).contains(&(n % 100))",
+            ),
+        ],
+        otherwise: "Many",
+    },
+    PluralRule {
+        languages: &["pl"],
+        conditions: &[
+            ("One", "n == 1"),
+            (
+                "Few",
+                "(2..=4).contains(&(n % 10)) && !(12..=14).contains(&(n % 100))",
+            ),
+        ],
+        otherwise: "Many",
+    },
+    PluralRule {
+        languages: &["bs", "hr", "sr"],
+        conditions: &[
+            ("One", "n % 10 == 1 && n % 100 != 11"),
+            (
+                "Few",
+                "(2..=4).contains(&(n % 10)) && !(12..=14).contains(&(n % 100))",
+            ),
+        ],
+        otherwise: "Other",
+    },
+    PluralRule {
+        languages: &["cs", "sk"],
+        conditions: &[("One", "n == 1"), ("Few", "(2..=4).contains(&n)")],
+        otherwise: "Other",
+    },
+    PluralRule {
+        languages: &["lt"],
+        conditions: &[
+            ("One", "n % 10 == 1 && !(11..=19).contains(&(n % 100))"),
+            (
+                "Few",
+                "(2..=9).contains(&(n % 10)) && !(11..=19).contains(&(n % 100))",
+            ),
+        ],
+        otherwise: "Other",
+    },
+    PluralRule {
+        languages: &["lv"],
+        conditions: &[
+            (
+                "Zero",
+                "matches!(n % 10, 0) || (11..=19).contains(&(n % 100))",
+            ),
+            ("One", "n % 10 == 1 && n % 100 != 11"),
+        ],
+        otherwise: "Other",
+    },
+    PluralRule {
+        languages: &["ro"],
+        conditions: &[
+            ("One", "n == 1"),
+            ("Few", "n == 0 || (1..=19).contains(&(n % 100))"),
+        ],
+        otherwise: "Other",
+    },
+    PluralRule {
+        languages: &["sl"],
+        conditions: &[
+            ("One", "n % 100 == 1"),
+            ("Two", "n % 100 == 2"),
+            ("Few", "(3..=4).contains(&(n % 100))"),
+        ],
+        otherwise: "Other",
+    },
+    PluralRule {
+        languages: &["he"],
+        conditions: &[("One", "n == 1"), ("Two", "n == 2")],
+        otherwise: "Other",
+    },
+    PluralRule {
+        languages: &["ga"],
+        conditions: &[
+            ("One", "n == 1"),
+            ("Two", "n == 2"),
+            ("Few", "(3..=6).contains(&n)"),
+            ("Many", "(7..=10).contains(&n)"),
+        ],
+        otherwise: "Other",
+    },
+    PluralRule {
+        languages: &["ar"],
+        conditions: &[
+            ("Zero", "n == 0"),
+            ("One", "n == 1"),
+            ("Two", "n == 2"),
+            ("Few", "(3..=10).contains(&(n % 100))"),
+            ("Many", "(11..=99).contains(&(n % 100))"),
+        ],
+        otherwise: "Other",
+    },
+];
+
+const PLURAL_RULE_ENGLISH: PluralRule = PluralRule {
+    languages: &["en"],
+    conditions: &[("One", "n == 1")],
+    otherwise: "Other",
+};
+
+fn plural_rule(locale: &str) -> &'static PluralRule {
+    let language = locale.split(['-', '_']).next().unwrap_or(locale);
+    PLURAL_RULES
+        .iter()
+        .find(|rule| {
+            rule.languages
+                .iter()
+                .any(|l| l.eq_ignore_ascii_case(language))
+        })
+        .unwrap_or(&PLURAL_RULE_ENGLISH)
+}
+
+/// generate the plural category of a count for every locale,
+/// and (if enabled) a `Locale` method per plural string selecting its form for a count
+fn generate_locales_plurals(
+    mut w: impl std::io::Write,
+    storage: &Storage,
+    accessors: bool,
+) -> Result<()> {
+    let rules: Vec<(&str, &PluralRule)> = storage
+        .all_locales()
+        .map(|locale| (locale, plural_rule(locale)))
+        .collect();
+    w.write_all(
+        format!(
+            "/// CLDR plural category, selecting the form of a plural string for a count
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PluralCategory {{
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}}
+
+impl Locale {{
+    /// plural category of the given count, following the CLDR plural rules of the locale
+    pub fn plural_category(&self, {}: u64) -> PluralCategory {{
+        match self {{
+",
+            if rules.iter().all(|(_, rule)| rule.conditions.is_empty()) {
+                "_n"
+            } else {
+                "n"
+            }
+        )
+        .as_bytes(),
+    )?;
+    for (locale, rule) in rules {
+        let variant = locale.to_case(Case::Pascal);
+        if rule.conditions.is_empty() {
+            w.write_all(
+                format!(
+                    "            Self::{} => PluralCategory::{},
+",
+                    variant, rule.otherwise
+                )
+                .as_bytes(),
+            )?;
+            continue;
+        }
+        w.write_all(
+            format!(
+                "            Self::{} => {{
+",
+                variant
+            )
+            .as_bytes(),
+        )?;
+        for (i, (category, condition)) in rule.conditions.iter().enumerate() {
+            w.write_all(
+                format!(
+                    "                {}if {} {{
+                    PluralCategory::{}
+",
+                    if i == 0 { "" } else { "} else " },
+                    condition,
+                    category
+                )
+                .as_bytes(),
+            )?;
+        }
+        w.write_all(
+            format!(
+                "                }} else {{
+                    PluralCategory::{}
+                }}
+            }}
+",
+                rule.otherwise
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"        }
+    }
+",
+    )?;
+
+    let default_pairs: Vec<StringValuePathPair> = storage
+        .get_default()
+        .map(|locales| locales.iter().collect())
+        .unwrap_or_default();
+    let paths = if accessors {
+        storage.plural_paths()
+    } else {
+        Vec::new()
+    };
+    for path in paths {
+        if let Some(pair) = default_pairs.iter().find(|pair| {
+            pair.feature.is_some() && pair.path[..pair.path.len() - 1].join(".") == path
+        }) {
+            return Err(anyhow!(
+                "form '{}' of plural string '{}' cannot be feature gated",
+                pair.path[pair.path.len() - 1],
+                path
+            ));
+        }
+        let field = path
+            .split('.')
+            .map(|segment| segment.to_case(Case::Snake))
+            .join(".");
+        w.write_all(
+            format!(
+                "
+    pub fn {}(&self, n: u64) -> &'static str {{
+        let forms = &self.strings().{};
+        match self.plural_category(n) {{
+",
+                locale_accessor_name(&path),
+                field
+            )
+            .as_bytes(),
+        )?;
+        for category in ["zero", "one", "two", "few", "many", "other"] {
+            // forms not defined by the default locale (e.g. without `add_plural_forms`) use `other`
+            let defined = default_pairs
+                .iter()
+                .any(|pair| pair.path.join(".") == format!("{}.{}", path, category));
+            w.write_all(
+                format!(
+                    "            PluralCategory::{} => forms.{},
+",
+                    category.to_case(Case::Pascal),
+                    if defined { category } else { "other" }
+                )
+                .as_bytes(),
+            )?;
+        }
+        w.write_all(
+            b"        }
+    }
+",
+        )?;
+    }
+    w.write_all(
+        b"}
+
+",
+    )?;
+    Ok(())
+}
+
+/// keys (within `relative_time`) of the relative time templates, `{}` being the placeholder
+const RELATIVE_TIME_KEYS: &[&str] = &["past", "future"];
+
+/// plural strings (within `relative_time`) of the relative time units, with the expression
+/// counting the unit in `seconds`, and the upper bound (in seconds) of the unit
+const RELATIVE_TIME_UNITS: &[(&str, &str, Option<&str>)] = &[
+    ("seconds", "seconds", Some("60")),
+    ("minutes", "seconds / 60", Some("60 * 60")),
+    ("hours", "seconds / (60 * 60)", Some("24 * 60 * 60")),
+    ("days", "seconds / (24 * 60 * 60)", None),
+];
+
+/// whether or not the default locale defines relative time strings
+fn has_relative_time(storage: &Storage) -> bool {
+    storage.get_default().is_some_and(|locales| {
+        locales
+            .iter()
+            .any(|pair| pair.path.first().map(String::as_str) == Some("relative_time"))
+    })
+}
+
+fn generate_locales_relative_time(mut w: impl std::io::Write, storage: &Storage) -> Result<()> {
+    if !has_relative_time(storage) {
+        return Ok(());
+    }
+    let default_paths: Vec<String> = storage
+        .get_default()
+        .map(|locales| locales.iter().map(|pair| pair.path.join(".")).collect())
+        .unwrap_or_default();
+    let plural_paths = storage.plural_paths();
+    let missing: Vec<String> = RELATIVE_TIME_KEYS
+        .iter()
+        .map(|key| format!("relative_time.{}", key))
+        .filter(|path| !default_paths.contains(path))
+        .chain(
+            RELATIVE_TIME_UNITS
+                .iter()
+                .map(|(unit, _, _)| format!("relative_time.{}", unit))
+                .filter(|path| !plural_paths.contains(path))
+                .map(|path| format!("{}.other", path)),
+        )
+        .collect();
+    if !missing.is_empty() {
+        return Err(anyhow!(
+            "default locale {} misses relative time keys: {}",
+            storage.default_locale(),
+            missing.join(", ")
+        ));
+    }
+
+    w.write_all(
+        b"impl Locale {
+    /// relative time (e.g. `3 hours ago`) for the given number of seconds ago,
+    /// negative for a moment in the future
+    pub fn relative_time(&self, seconds_ago: i64) -> String {
+        let strings = &self.strings().relative_time;
+        let seconds = seconds_ago.unsigned_abs();
+",
+    )?;
+    for (i, (unit, count, bound)) in RELATIVE_TIME_UNITS.iter().enumerate() {
+        w.write_all(
+            match (i, bound) {
+                (0, Some(bound)) => format!(
+                    "        let (count, unit) = if seconds < {} {{
+",
+                    bound
+                ),
+                (_, Some(bound)) => format!(
+                    "        }} else if seconds < {} {{
+",
+                    bound
+                ),
+                (_, None) => "        } else {
+"
+                .to_owned(),
+            }
+            .as_bytes(),
+        )?;
+        w.write_all(
+            format!(
+                "            let count = {};
+            let unit = match self.plural_category(count) {{
+",
+                count
+            )
+            .as_bytes(),
+        )?;
+        for category in ["zero", "one", "two", "few", "many", "other"] {
+            // forms not defined by the default locale (e.g. without `add_plural_forms`) use `other`
+            let path = format!("relative_time.{}.{}", unit, category);
+            w.write_all(
+                format!(
+                    "                PluralCategory::{} => strings.{}.{},
+",
+                    category.to_case(Case::Pascal),
+                    unit,
+                    if default_paths.contains(&path) {
+                        category
+                    } else {
+                        "other"
+                    }
+                )
+                .as_bytes(),
+            )?;
+        }
+        w.write_all(
+            b"            };
+            (count, unit)
+",
+        )?;
+    }
+    w.write_all(
+        b"        };
+        let amount = unit.replacen(\"{}\", &count.to_string(), 1);
+        let template = if seconds_ago >= 0 {
+            strings.past
+        } else {
+            strings.future
+        };
+        template.replacen(\"{}\", &amount, 1)
+    }
+}
+
+",
+    )?;
+    Ok(())
+}
+
+/// notice shown when the requested locale isn't supported, rendered from the string of the given key,
+/// with the served language named by the `site.locales` string of the served locale
+fn generate_locales_unavailable_notice(
+    mut w: impl std::io::Write,
+    storage: &Storage,
+    key: &str,
+) -> Result<()> {
+    if storage
+        .value_or_default(storage.default_locale(), key)
+        .is_none()
+    {
+        return Err(anyhow!(
+            "unavailable notice string '{}' is not defined by the default locale {}",
+            key,
+            storage.default_locale()
+        ));
+    }
+    w.write_all(
+        b"impl Locale {
+    /// notice that the requested (unsupported) locale isn't available,
+    /// naming the requested locale tag and the language of the locale served instead
+    pub fn unavailable_notice(requested: &str, served: Locale) -> String {
+        let notice = match served {
+",
+    )?;
+    for locale in storage.all_locales() {
+        let language = storage
+            .value_or_default(locale, &format!("site.locales.{}", locale))
+            .unwrap_or_else(|| locale.to_owned());
+        let notice = storage
+            .value_or_default(locale, key)
+            .unwrap_or_default()
+            .replace("{served}", &language);
+        w.write_all(
+            format!(
+                "            Self::{} => {:?},
+",
+                locale.to_case(Case::Pascal),
+                notice
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"        };
+        notice.replace(\"{requested}\", requested)
+    }
+}
+
+",
+    )?;
+    Ok(())
+}
+
+/// generate the `a11y` labels of every locale, using the english fallback
+/// for the conventional a11y keys not defined by the default locale either
+fn generate_locales_a11y(mut w: impl std::io::Write, storage: &Storage) -> Result<()> {
+    w.write_all(
+        b"/// localized accessibility labels (skip links, ARIA labels), shared by all pages
+#[derive(Debug, Clone, Copy)]
+pub struct A11yLabels {
+",
+    )?;
+    for (key, _) in A11Y_KEYS {
+        w.write_all(
+            format!(
+                "    pub {}: &'static str,
+",
+                key
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"}
+
+impl Locale {
+    pub fn a11y(&self) -> A11yLabels {
+        match self {
+",
+    )?;
+    for locale in storage.all_locales() {
+        w.write_all(
+            format!(
+                "            Self::{} => A11yLabels {{
+",
+                locale.to_case(Case::Pascal)
+            )
+            .as_bytes(),
+        )?;
+        for (key, fallback) in A11Y_KEYS {
+            let value = storage
+                .value_or_default(locale, &format!("a11y.{}", key))
+                .unwrap_or_else(|| (*fallback).to_owned());
+            w.write_all(
+                format!(
+                    "                {}: {:?},
+",
+                    key, value
+                )
+                .as_bytes(),
+            )?;
+        }
+        w.write_all(
+            b"            },
+",
+        )?;
+    }
+    w.write_all(
+        b"        }
+    }
+}
+
+",
+    )?;
+    Ok(())
+}
+
+/// the (nested) strings struct of the given name, and the structs of its objects
+/// up to the given depth, named after the struct and their path (e.g. `StringsNavUser`)
+fn generate_locales_strings_struct(
+    mut w: impl std::io::Write,
+    mut pairs: Vec<StringValuePathPair>,
+    cfg: &I18n,
+    type_name: &str,
+    depth: usize,
+) -> Result<()> {
+    let mut attributes = String::new();
+    if cfg.repr_c {
+        attributes.push_str("#[repr(C)]\n");
+    }
+    // with accessors the nesting of the fields is an implementation detail of the crate
+    let visibility = if cfg.accessors { "pub(crate)" } else { "pub" };
+    let mut layer: usize = 0;
+    while !pairs.is_empty() && layer < depth {
+        if layer == 0 {
+            w.write_all(attributes.as_bytes())?;
+            w.write_all(format!("pub struct {} {{\n", type_name).as_bytes())?;
+        }
+        let mut previous: Option<String> = None;
+        let mut previous_property: Option<String> = None;
+        let mut retained_pairs = Vec::new();
+        for pair in pairs {
+            let path = &pair.path;
+            // create new struct if needed
+            let current = if layer == 0 {
+                None
+            } else {
+                Some(path[layer - 1].clone())
+            };
+            if previous != current {
+                w.write_all(
+                    b"}
+
+",
+                )?;
+                w.write_all(attributes.as_bytes())?;
+                w.write_all(
+                    format!(
+                        "pub struct {}{} {{
+",
+                        type_name,
+                        path[..layer]
+                            .iter()
+                            .map(|s| s.to_case(Case::Pascal))
+                            .join("")
+                    )
+                    .as_bytes(),
+                )?;
+                previous = current;
+            }
+
+            let key = &path[layer];
+            let current_property = Some(key.clone());
+            let drop = path.len() == layer + 1;
+
+            // write struct property
+            if drop {
+                // str (or bytes)
+                generate_feature_cfg_attribute(&mut w, &pair.feature, 1)?;
+                w.write_all(
+                    format!(
+                        "    {} {}: &'static {},
+",
+                        visibility,
+                        key.to_case(Case::Snake),
+                        if pair.bytes.is_some() { "[u8]" } else { "str" }
+                    )
+                    .as_bytes(),
+                )?;
+            } else if current_property != previous_property {
+                // object
+                w.write_all(
+                    format!(
+                        "    {} {}: {}{},
+",
+                        visibility,
+                        key.to_case(Case::Snake),
+                        type_name,
+                        path[..layer + 1]
+                            .iter()
+                            .map(|s| s.to_case(Case::Pascal))
+                            .join("")
+                    )
+                    .as_bytes(),
+                )?;
+                previous_property = current_property;
+            }
+
+            // retain if we do not wish to drop
+            if !drop {
+                retained_pairs.push(pair);
+            }
+        }
+
+        layer += 1;
+        pairs = retained_pairs;
+    }
+    w.write_all(
+        b"}
+",
+    )?;
+    Ok(())
+}
+
+/// methods of `Strings` which a flattened accessor name may not shadow
+const STRINGS_METHODS: &[&str] = &["diff", "get", "get_by_path", "render_json", "set"];
+
+/// `Strings` methods returning each string by its flattened name (e.g. `site_name` for `site.name`)
+fn generate_locales_strings_accessors(
+    mut w: impl std::io::Write,
+    pairs: &[StringValuePathPair],
+) -> Result<()> {
+    let mut names: HashMap<String, &[String]> = HashMap::new();
+    w.write_all(
+        b"
+impl Strings {
+",
+    )?;
+    for pair in pairs {
+        let name = pair.path.iter().map(|s| s.to_case(Case::Snake)).join("_");
+        if STRINGS_METHODS.contains(&name.as_str()) {
+            return Err(anyhow!(
+                "accessor of string '{}' collides with the Strings::{} method",
+                pair.path.join("."),
+                name
+            ));
+        }
+        if let Some(other) = names.insert(name.clone(), &pair.path[..]) {
+            return Err(anyhow!(
+                "accessor '{}' of string '{}' collides with the accessor of string '{}'",
+                name,
+                pair.path.join("."),
+                other.join(".")
+            ));
+        }
+        generate_feature_cfg_attribute(&mut w, &pair.feature, 1)?;
+        w.write_all(
+            format!(
+                "    pub fn {}(&self) -> &'static {} {{
+        self.{}
+    }}
+
+",
+                name,
+                if pair.bytes.is_some() { "[u8]" } else { "str" },
+                pair.path.iter().map(|s| s.to_case(Case::Snake)).join(".")
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"}
+",
+    )?;
+    Ok(())
+}
+
+/// keywords which cannot name the argument of a placeholder
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use",
+    "where", "while",
+];
+
+/// methods named after the strings with `{name}` placeholders (e.g. `greeting(name)`
+/// for `greeting: Welcome back, {name}`), substituting their arguments, next to the fields
+/// holding the strings as is
+fn generate_locales_strings_interpolation(
+    mut w: impl std::io::Write,
+    pairs: &[StringValuePathPair],
+    cfg: &I18n,
+) -> Result<()> {
+    let interpolated = pairs
+        .iter()
+        .filter(|pair| pair.bytes.is_none())
+        .map(|pair| (pair, named_placeholders(&pair.value)))
+        .filter(|(_, names)| !names.is_empty())
+        .into_group_map_by(|(pair, _)| &pair.path[..pair.path.len() - 1]);
+    if interpolated.is_empty() {
+        return Ok(());
+    }
+    // the string is rendered in a single pass, such that arguments are never interpolated
+    w.write_all(
+        b"
+fn interpolate(template: &str, args: &[(&str, &str)]) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let placeholder = &rest[start + 1..];
+        let arg = placeholder.find('}').and_then(|end| {
+            args.iter()
+                .find(|(name, _)| *name == &placeholder[..end])
+        });
+        match arg {
+            Some((name, value)) => {
+                push_arg(&mut rendered, value);
+                rest = &placeholder[name.len() + 1..];
+            }
+            None => {
+                // not a placeholder of the string (e.g. `{}`), kept as is
+                rendered.push('{');
+                rest = placeholder;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+",
+    )?;
+    if cfg.bidi_isolation {
+        // the direction of the arguments is unknown, so they are isolated by their first strong character
+        w.write_all(
+            b"
+fn push_arg(rendered: &mut String, value: &str) {
+    rendered.push('\\u{2068}');
+    rendered.push_str(value);
+    rendered.push('\\u{2069}');
+}
+",
+        )?;
+    } else {
+        w.write_all(
+            b"
+fn push_arg(rendered: &mut String, value: &str) {
+    rendered.push_str(value);
+}
+",
+        )?;
+    }
+    for (parent, strings) in interpolated
+        .into_iter()
+        .sorted_by_key(|(parent, _)| *parent)
+    {
+        w.write_all(
+            format!(
+                "
+impl Strings{} {{
+",
+                parent.iter().map(|s| s.to_case(Case::Pascal)).join("")
+            )
+            .as_bytes(),
+        )?;
+        for (pair, names) in strings {
+            let name = pair.path[parent.len()].to_case(Case::Snake);
+            if parent.is_empty() && (cfg.accessors || STRINGS_METHODS.contains(&name.as_str())) {
+                return Err(anyhow!(
+                    "interpolation method of string '{}' collides with the Strings::{} method",
+                    pair.path.join("."),
+                    name
+                ));
+            }
+            if let Some(arg) = names.iter().find(|arg| {
+                !arg.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+                    || !arg
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+                    || RUST_KEYWORDS.contains(&arg.as_str())
+            }) {
+                return Err(anyhow!(
+                    "placeholder '{{{}}}' of string '{}' cannot be used as an argument name, please use a snake case name",
+                    arg,
+                    pair.path.join(".")
+                ));
+            }
+            generate_feature_cfg_attribute(&mut w, &pair.feature, 1)?;
+            w.write_all(
+                format!(
+                    "    pub fn {}(&self, {}) -> String {{
+        interpolate(self.{}, &[{}])
+    }}
+
+",
+                    name,
+                    names.iter().map(|arg| format!("{}: &str", arg)).join(", "),
+                    name,
+                    names
+                        .iter()
+                        .map(|arg| format!("({:?}, {})", arg, arg))
+                        .join(", ")
+                )
+                .as_bytes(),
+            )?;
+        }
+        w.write_all(
+            b"}
+",
+        )?;
+    }
+    Ok(())
+}
+
+fn generate_locales_strings_struct_methods(
+    mut w: impl std::io::Write,
+    storage: &Storage,
+) -> Result<()> {
+    w.write_all(
+        b"
+impl StringsSiteLocales {
+    pub fn iter(&self) -> impl Iterator<Item = (Locale, &str)> {
+        vec![
+",
+    )?;
+    for locale in storage.all_locales() {
+        w.write_all(
+            format!(
+                r#"            (Locale::{}, self.{}),
+"#,
+                locale.to_case(Case::Pascal),
+                locale.to_case(Case::Snake)
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"        ]
+        .into_iter()
+    }
+}
+",
+    )?;
+
+    Ok(())
+}
+
+/// name of the module generated for the strings of the given top level object (domain)
+fn domain_module_name(domain: &str) -> String {
+    format!("strings_{}", domain.to_case(Case::Snake))
+}
+
+/// module of the strings structs of the given domain, to which the consts
+/// of the domain are added for every locale (see `generate_locales_strings_instance`)
+fn generate_locales_domain_module(
+    mut w: impl std::io::Write,
+    domain: &str,
+    pairs: &[StringValuePathPair],
+    cfg: &I18n,
+) -> Result<()> {
+    generate_copyright_file_header(&mut w)?;
+    generate_locales_mod_docs(&mut w)?;
+    // fallback strings refer to the `Strings` consts of other locales
+    w.write_all(
+        b"#[allow(unused_imports)]
+use super::*;
+
+",
+    )?;
+    let domain_pairs: Vec<StringValuePathPair> = pairs
+        .iter()
+        .filter(|pair| pair.path.len() > 1 && pair.path[0] == domain)
+        .map(|pair| StringValuePathPair {
+            path: pair.path[1..].to_vec(),
+            ..pair.clone()
+        })
+        .collect();
+    generate_locales_strings_struct(
+        w,
+        domain_pairs,
+        cfg,
+        &format!("Strings{}", domain.to_case(Case::Pascal)),
+        usize::MAX,
+    )
+}
+
+/// the `Strings` const of a locale, with the const of every top level object (domain)
+/// generated in the module of the domain instead, in case domain modules are given
+fn generate_locales_strings_instance<'a>(
+    mut w: impl std::io::Write,
+    const_name: &str,
+    pairs: impl Iterator<Item = &'a StringValuePathPair>,
+    domain_modules: Option<&mut BTreeMap<String, Vec<u8>>>,
+) -> Result<()> {
+    let domain_modules = match domain_modules {
+        Some(domain_modules) => domain_modules,
+        None => {
+            return generate_locales_strings_struct_instance(w, const_name, "Strings", "", pairs)
+        }
+    };
+    let pairs: Vec<&StringValuePathPair> = pairs.collect();
+    let mut fields: Vec<(&Option<String>, &str, String)> = Vec::new();
+    let mut start = 0;
+    while start < pairs.len() {
+        let domain = &pairs[start].path[0];
+        let end = start
+            + pairs[start..]
+                .iter()
+                .take_while(|pair| &pair.path[0] == domain)
+                .count();
+        if pairs[start].path.len() == 1 {
+            fields.push((&pairs[start].feature, domain, pairs[start].value.clone()));
+        } else {
+            let domain_const = format!("{}_{}", const_name, domain.to_case(Case::ScreamingSnake));
+            let domain_pairs: Vec<StringValuePathPair> = pairs[start..end]
+                .iter()
+                .map(|pair| StringValuePathPair {
+                    path: pair.path[1..].to_vec(),
+                    ..(*pair).clone()
+                })
+                .collect();
+            let module = domain_modules
+                .get_mut(domain)
+                .ok_or_else(|| anyhow!("no module generated for domain {}", domain))?;
+            generate_locales_strings_struct_instance(
+                module,
+                &domain_const,
+                &format!("Strings{}", domain.to_case(Case::Pascal)),
+                "pub(super) ",
+                domain_pairs.iter(),
+            )?;
+            fields.push((&None, domain, domain_const));
+        }
+        start = end;
+    }
+    w.write_all(
+        format!(
+            "
+const {}: Strings = Strings {{
+",
+            const_name
+        )
+        .as_bytes(),
+    )?;
+    for (feature, key, value) in fields {
+        generate_feature_cfg_attribute(&mut w, feature, 1)?;
+        w.write_all(format!("    {}: {},\n", key.to_case(Case::Snake), value).as_bytes())?;
+    }
+    w.write_all(
+        b"};
+",
+    )?;
+    Ok(())
+}
+
+/// const of the given (nested) strings struct type, with the given paths relative to it
+fn generate_locales_strings_struct_instance<'a>(
+    mut w: impl std::io::Write,
+    const_name: &str,
+    type_name: &str,
+    visibility: &str,
+    pairs: impl Iterator<Item = &'a StringValuePathPair>,
+) -> Result<()> {
+    w.write_all(
+        format!(
+            "
+{}const {}: {} = {} {{
+",
+            visibility, const_name, type_name, type_name
+        )
+        .as_bytes(),
+    )?;
+    let mut previous_layer = 0;
+    let mut previous_path = None;
+    // for each locale string...
+    for pair in pairs {
+        let current_layer = pair.path.len() - 1;
+
+        // prior to writing key,
+        // we need to ensure we're within the correct struct constructor
+        let mut overlap_layer = 0;
+        if let Some(previous_path) = previous_path {
+            for (key_a, key_b) in pair.path.iter().zip(previous_path) {
+                if key_a != key_b {
+                    break;
+                }
+                overlap_layer += 1;
+            }
+        }
+        if overlap_layer < previous_layer {
+            for idx in 0..(previous_layer - overlap_layer) {
+                w.write_all(
+                    format!(
+                        "{}}},
+",
+                        "    ".repeat(previous_layer - idx)
+                    )
+                    .as_bytes(),
+                )?;
+            }
+        }
+        while overlap_layer < current_layer {
+            let key = &pair.path[overlap_layer];
+            w.write_all(
+                format!(
+                    "{}{}: {}{} {{
+",
+                    "    ".repeat(overlap_layer + 1),
+                    key.to_case(Case::Snake),
+                    type_name,
+                    pair.path[..=overlap_layer]
+                        .iter()
+                        .map(|s| s.to_case(Case::Pascal))
+                        .join(""),
+                )
+                .as_bytes(),
+            )?;
+            overlap_layer += 1;
+        }
+
+        assert!(overlap_layer == current_layer);
+
+        // write the actual locale string...
+        let key = &pair.path[current_layer];
+        generate_feature_cfg_attribute(&mut w, &pair.feature, current_layer + 1)?;
+        w.write_all(
+            format!(
+                r#################"{}{}: {},
+"#################,
+                "    ".repeat(current_layer + 1),
+                key.to_case(Case::Snake),
+                pair.value
+            )
+            .as_bytes(),
+        )?;
+        // keep track of the previous path to be handle the more complex nesting cases
+        previous_path = Some(&pair.path);
+        previous_layer = current_layer;
+    }
+    // add all the final curly brackets... including the last one
+    while previous_layer > 0 {
+        previous_layer -= 1;
+        w.write_all(
+            format!(
+                "{}}},
+",
+                "    ".repeat(previous_layer + 1)
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"};
+",
+    )?;
+    Ok(())
+}
+
+/// rust literal of the given (translated) value, a byte string literal in case of bytes
+fn value_literal(pair: &StringValuePathPair) -> String {
+    match &pair.bytes {
+        Some(bytes) => format!(
+            "b\"{}\"",
+            bytes
+                .iter()
+                .flat_map(|b| std::ascii::escape_default(*b))
+                .map(char::from)
+                .collect::<String>()
+        ),
+        None => raw_string_literal(&pair.value),
+    }
+}
+
+/// raw string literal of the given value, with the fewest hashes that can delimit it
+fn raw_string_literal(value: &str) -> String {
+    let hashes = value
+        .match_indices('"')
+        .map(|(index, _)| {
+            value[index + 1..].len() - value[index + 1..].trim_start_matches('#').len() + 1
+        })
+        .max()
+        .unwrap_or(0);
+    format!(
+        "r{hashes}\"{}\"{hashes}",
+        value,
+        hashes = "#".repeat(hashes)
+    )
+}
+
+fn generate_feature_cfg_attribute(
+    mut w: impl std::io::Write,
+    feature: &Option<String>,
+    indent: usize,
+) -> Result<()> {
+    if let Some(feature) = feature {
+        w.write_all(
+            format!(
+                "{}#[cfg(feature = \"{}\")]
+",
+                "    ".repeat(indent),
+                feature
+            )
+            .as_bytes(),
+        )?;
+    }
+    Ok(())
+}
+
+/// generate the dynamic (dotted path) lookup of strings, which is aware of
+/// which strings are translated for a locale and which fall back to the default locale
+fn generate_locales_dynamic_lookup(
+    mut w: impl std::io::Write,
+    default_pairs: &[StringValuePathPair],
+    missing_paths: &[(String, Vec<String>)],
+    reload_fallbacks: Option<&[(String, Vec<String>)]>,
+) -> Result<()> {
+    w.write_all(
+        b"
+impl Strings {
+    pub fn get(&self, path: &str) -> Option<&'static str> {
+        match path {
+",
+    )?;
+    // bytes are not strings, and thus not part of the dynamic lookup
+    for pair in default_pairs.iter().filter(|pair| pair.bytes.is_none()) {
+        generate_feature_cfg_attribute(&mut w, &pair.feature, 3)?;
+        w.write_all(
+            format!(
+                "            {:?} => Some(self.{}),
+",
+                pair.path.join("."),
+                pair.path.iter().map(|s| s.to_case(Case::Snake)).join(".")
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"            _ => None,
+        }
+    }
+
+    /// the string at the given path of keys (e.g. `[\"errors\", code]`), `None` if it doesn't resolve
+    pub fn get_by_path(&self, path: &[&str]) -> Option<&'static str> {
+        match path {
+",
+    )?;
+    for pair in default_pairs.iter().filter(|pair| pair.bytes.is_none()) {
+        generate_feature_cfg_attribute(&mut w, &pair.feature, 3)?;
+        w.write_all(
+            format!(
+                "            [{}] => Some(self.{}),
+",
+                pair.path.iter().map(|key| format!("{:?}", key)).join(", "),
+                pair.path.iter().map(|s| s.to_case(Case::Snake)).join(".")
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"            _ => None,
+        }
+    }
+
+    /// the strings which differ between these and the other strings
+    /// (e.g. of another locale), as (path, value, other value)
+    pub fn diff(&self, other: &Strings) -> Vec<(&'static str, &'static str, &'static str)> {
+        let mut diff = Vec::new();
+",
+    )?;
+    for pair in default_pairs.iter().filter(|pair| pair.bytes.is_none()) {
+        generate_feature_cfg_attribute(&mut w, &pair.feature, 2)?;
+        w.write_all(
+            format!(
+                "        if self.{field} != other.{field} {{
+            diff.push(({path:?}, self.{field}, other.{field}));
+        }}
+",
+                path = pair.path.join("."),
+                field = pair.path.iter().map(|s| s.to_case(Case::Snake)).join(".")
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"        diff
+    }
+",
+    )?;
+    w.write_all(
+        b"}
+",
+    )?;
+    if let Some(fallback_chains) = reload_fallbacks {
+        generate_locales_reload(&mut w, fallback_chains)?;
+    }
+    w.write_all(
+        b"
+impl Locale {
+    /// the value rendered for this locale, which is the value of its first fallback locale
+    /// translating the string (or else the default locale's value) in case the string
+    /// isn't translated for this locale
+    pub fn effective(&self, path: &str) -> Option<&'static str> {
+        self.strings().get(path)
+    }
+
+    /// true if the string is translated for this locale,
+    /// rather than falling back to the default locale
+    pub fn supports(&self, path: &str) -> bool {
+        match self {
+",
+    )?;
+    for (locale, paths) in missing_paths {
+        if paths.is_empty() {
+            w.write_all(
+                format!(
+                    "            Self::{} => self.effective(path).is_some(),
+",
+                    locale.to_case(Case::Pascal)
+                )
+                .as_bytes(),
+            )?;
+        } else {
+            w.write_all(
+                format!(
+                    "            Self::{} => {{
+                self.effective(path).is_some() && !matches!(path, {})
+            }}
+",
+                    locale.to_case(Case::Pascal),
+                    paths.iter().map(|path| format!("{:?}", path)).join(" | ")
+                )
+                .as_bytes(),
+            )?;
+        }
+    }
+    w.write_all(
+        b"        }
+    }
+}
+",
+    )?;
+    Ok(())
+}
+
+/// generate the runtime reload store, consulted by `Locale::current` when the consuming
+/// crate enables its `reload` feature (which requires a `serde_json` dependency),
+/// resolving fallbacks at lookup time such that reloaded fallback strings are picked up
+fn generate_locales_reload(
+    mut w: impl std::io::Write,
+    fallback_chains: &[(String, Vec<String>)],
+) -> Result<()> {
+    w.write_all(
+        b"
+#[cfg(feature = \"reload\")]
+type ReloadedStrings =
+    std::collections::HashMap<Locale, std::collections::HashMap<String, String>>;
+
+#[cfg(feature = \"reload\")]
+static RELOADED_STRINGS: std::sync::RwLock<Option<std::sync::Arc<ReloadedStrings>>> =
+    std::sync::RwLock::new(None);
+
+/// the locales the strings of the given locale fall back to, in order
+#[cfg(feature = \"reload\")]
+fn reload_fallbacks(locale: Locale) -> &'static [Locale] {
+    match locale {
+",
+    )?;
+    let default_locale = fallback_chains
+        .first()
+        .map(|(locale, _)| locale.to_case(Case::Pascal))
+        .ok_or_else(|| anyhow!("failed to get default locale of fallback chains"))?;
+    for (index, (locale, chain)) in fallback_chains.iter().enumerate() {
+        let mut fallbacks: Vec<_> = chain
+            .iter()
+            .map(|fallback| format!("Locale::{}", fallback.to_case(Case::Pascal)))
+            .collect();
+        // the default locale is the last fallback of every other locale
+        if index > 0 {
+            fallbacks.push(format!("Locale::{}", default_locale));
+        }
+        w.write_all(
+            format!(
+                "        Locale::{} => &[{}],
+",
+                locale.to_case(Case::Pascal),
+                fallbacks.join(", ")
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"    }
+}
+
+#[cfg(feature = \"reload\")]
+impl Locale {
+    /// the current value of the string at the given dotted path, which is the value reloaded
+    /// for this locale or else its generated value, where untranslated strings resolve
+    /// through the (reloaded) values of the fallback locales at the time of the lookup
+    pub fn current(&self, path: &str) -> Option<std::borrow::Cow<'static, str>> {
+        let generated = self.strings().get(path)?;
+        let reloaded = RELOADED_STRINGS.read().ok().and_then(|reloaded| reloaded.clone());
+        for locale in std::iter::once(self).chain(reload_fallbacks(*self)) {
+            let value = reloaded
+                .as_ref()
+                .and_then(|reloaded| reloaded.get(locale)?.get(path));
+            if let Some(value) = value {
+                return Some(std::borrow::Cow::Owned(value.clone()));
+            }
+            // the generated value is the one of the first locale translating the string
+            if locale.supports(path) {
+                break;
+            }
+        }
+        Some(std::borrow::Cow::Borrowed(generated))
+    }
+}
+
+/// Replace strings at runtime from a json object, mapping locale tags
+/// to objects of dotted string paths (e.g. `site.name`) and their new value,
+/// strings that are not given keep their current value.
+///
+/// All locales and paths are validated before the reloaded strings are swapped,
+/// after which they are visible through `Locale::current`.
+#[cfg(feature = \"reload\")]
+pub fn reload_from_json(json: &str) -> anyhow::Result<()> {
+    let updates: std::collections::HashMap<String, std::collections::HashMap<String, String>> =
+        serde_json::from_str(json)?;
+    let mut locale_updates = Vec::with_capacity(updates.len());
+    for (tag, values) in updates {
+        let locale = Locale::try_from(tag.as_str())?;
+        if let Some(path) = values.keys().find(|path| locale.strings().get(path).is_none()) {
+            return Err(anyhow::anyhow!(\"unknown string '{}' for locale {}\", path, tag));
+        }
+        locale_updates.push((locale, values));
+    }
+    let mut store = RELOADED_STRINGS
+        .write()
+        .map_err(|_| anyhow::anyhow!(\"reloaded strings lock is poisoned\"))?;
+    let mut reloaded = store.as_deref().cloned().unwrap_or_default();
+    for (locale, values) in locale_updates {
+        reloaded.entry(locale).or_default().extend(values);
+    }
+    *store = Some(std::sync::Arc::new(reloaded));
+    Ok(())
+}
+",
+    )?;
+    Ok(())
+}
+
+/// generate the conversion of locales into ICU language identifiers, gated behind
+/// the consuming crate's `icu` feature (which requires an `icu_locid` dependency)
+fn generate_locales_icu(mut w: impl std::io::Write, storage: &Storage, cfg: &I18n) -> Result<()> {
+    w.write_all(
+        b"
+#[cfg(feature = \"icu\")]
+impl Locale {
+    /// the ICU language identifier of this locale
+    pub fn to_langid(&self) -> icu_locid::LanguageIdentifier {
+        match self {
+",
+    )?;
+    for locale in storage.all_locales() {
+        w.write_all(
+            format!(
+                "            Self::{} => {:?},
+",
+                locale.to_case(Case::Pascal),
+                locale_cldr_id(cfg, locale)?
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"        }
+        .parse()
+        .expect(\"language identifier validated by plabayo-news-builder\")
+    }
+}
+
+#[cfg(feature = \"icu\")]
+impl From<Locale> for icu_locid::LanguageIdentifier {
+    fn from(locale: Locale) -> Self {
+        locale.to_langid()
+    }
+}
+",
+    )?;
+    Ok(())
+}
+
+/// the (unicode) language identifier of the given locale,
+/// the configured `cldr_id` or the canonicalized locale tag
+fn locale_cldr_id(cfg: &I18n, locale: &str) -> Result<String> {
+    let cldr_id = match cfg
+        .locale_settings(locale)
+        .and_then(|settings| settings.cldr_id.as_deref())
+    {
+        Some(cldr_id) => cldr_id.to_owned(),
+        None => bcp47_tag(locale),
+    };
+    if !is_language_identifier(&cldr_id) {
+        return Err(anyhow!(
+            "invalid language identifier '{}' for locale {}, expected a language subtag (2-3 or 5-8 letters), optionally followed by a script (4 letters), a region (2 letters or 3 digits) and variants (5-8 alphanumerics, or 4 starting with a digit)",
+            cldr_id,
+            locale
+        ));
+    }
+    Ok(cldr_id)
+}
+
+/// whether or not the given tag is a unicode language identifier as parsed by ICU
+/// (language, script, region and variant subtags, in that order)
+fn is_language_identifier(tag: &str) -> bool {
+    let is_alpha = |subtag: &str| subtag.chars().all(|c| c.is_ascii_alphabetic());
+    let mut subtags = tag.split(&['-', '_'][..]).peekable();
+    let language = subtags.next().unwrap_or_default();
+    if !matches!(language.len(), 2 | 3 | 5..=8) || !is_alpha(language) {
+        return false;
+    }
+    // optional script and region
+    subtags.next_if(|subtag| subtag.len() == 4 && is_alpha(subtag));
+    subtags.next_if(|subtag| {
+        (subtag.len() == 2 && is_alpha(subtag))
+            || (subtag.len() == 3 && subtag.chars().all(|c| c.is_ascii_digit()))
+    });
+    subtags.all(|variant| {
+        variant.chars().all(|c| c.is_ascii_alphanumeric())
+            && match variant.len() {
+                5..=8 => true,
+                4 => variant.starts_with(|c: char| c.is_ascii_digit()),
+                _ => false,
+            }
+    })
+}
+
+/// generate the rendering of strings with `{name}` placeholders from a json object,
+/// gated behind the consuming crate's `serde_json` feature (e.g. an optional dependency)
+fn generate_locales_render_json(mut w: impl std::io::Write) -> Result<()> {
+    w.write_all(
+        b"
+#[cfg(feature = \"serde_json\")]
+impl Strings {
+    /// render the string of the given (dotted) path, replacing its `{name}` placeholders
+    /// by the value of the field with that name in the given json object,
+    /// failing in case a placeholder has no (non-null) field to fill it
+    pub fn render_json(&self, key: &str, context: &serde_json::Value) -> anyhow::Result<String> {
+        let template = self
+            .get(key)
+            .ok_or_else(|| anyhow::anyhow!(\"unknown string '{}'\", key))?;
+        let mut rendered = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            rendered.push_str(&rest[..start]);
+            let placeholder = &rest[start + 1..];
+            let name = placeholder
+                .find('}')
+                .map(|end| &placeholder[..end])
+                .filter(|name| {
+                    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+                });
+            match name {
+                Some(name) => {
+                    match context.get(name) {
+                        Some(serde_json::Value::String(value)) => rendered.push_str(value),
+                        Some(serde_json::Value::Null) | None => {
+                            return Err(anyhow::anyhow!(
+                                \"missing field '{}' to render string '{}'\",
+                                name,
+                                key
+                            ))
+                        }
+                        Some(value) => rendered.push_str(&value.to_string()),
+                    }
+                    rest = &placeholder[name.len() + 1..];
+                }
+                None => {
+                    // not a named placeholder (e.g. `{}`), kept as is
+                    rendered.push('{');
+                    rest = placeholder;
+                }
+            }
+        }
+        rendered.push_str(rest);
+        Ok(rendered)
+    }
+}
+",
+    )?;
+    Ok(())
+}
+
+fn generate_locales_diagnostics(
+    mut w: impl std::io::Write,
+    diagnostics: &[LocaleDiagnostics],
+    cfg: &I18n,
+) -> Result<()> {
+    w.write_all(
+        b"
+pub struct LocaleDiagnostics {
+    pub locale: Locale,
+    pub translated_keys: usize,
+    pub total_keys: usize,
+    pub hidden: bool,
+}
+
+impl LocaleDiagnostics {
+    pub fn completeness(&self) -> f64 {
+        if self.total_keys == 0 {
+            return 100.0;
+        }
+        (self.translated_keys as f64 * 100.0) / self.total_keys as f64
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.locale.direction()
+    }
+}
+
+impl std::fmt::Display for LocaleDiagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            \"{}: {}/{} keys translated ({:.1}%), {}{}\",
+            self.locale,
+            self.translated_keys,
+            self.total_keys,
+            self.completeness(),
+            self.direction(),
+            if self.hidden { \", hidden\" } else { \"\" }
+        )
+    }
+}
+
+pub const LOCALE_DIAGNOSTICS: &[LocaleDiagnostics] = &[
+",
+    )?;
+    for diagnostic in diagnostics {
+        w.write_all(
+            format!(
+                "    LocaleDiagnostics {{
+        locale: Locale::{},
+        translated_keys: {},
+        total_keys: {},
+        hidden: {},
+    }},
+",
+                diagnostic.locale.to_case(Case::Pascal),
+                diagnostic.translated_keys,
+                diagnostic.total_keys,
+                diagnostic.locale == MIRROR_LOCALE
+                    || cfg
+                        .locale_settings(&diagnostic.locale)
+                        .is_some_and(|settings| settings.hidden)
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"];
+
+pub fn locale_diagnostics() -> String {
+    LOCALE_DIAGNOSTICS
+        .iter()
+        .map(|diagnostic| diagnostic.to_string())
+        .collect::<Vec<_>>()
+        .join(\"\\n\")
+}
+",
+    )?;
+    Ok(())
+}
+
+fn generate_locales_metrics(
+    mut w: impl std::io::Write,
+    key_count: usize,
+    total_string_bytes: usize,
+) -> Result<()> {
+    w.write_all(
+        format!(
+            "
+/// amount of translation keys, equal for all locales
+pub const I18N_KEY_COUNT: usize = {};
+
+/// sum of the byte lengths of all final string values over all locales,
+/// with fallbacks and formatters (e.g. markdown) applied
+pub const I18N_TOTAL_STRING_BYTES: usize = {};
+",
+            key_count, total_string_bytes
+        )
+        .as_bytes(),
+    )?;
+    Ok(())
+}
+
+/// a locale to take missing strings from, with the paths of the strings it translates itself
+/// byte length of the value emitted for the given pair,
+/// which is the decoded length for bytes (e.g. given as base64)
+fn value_byte_len(pair: &StringValuePathPair) -> usize {
+    pair.bytes.as_ref().map_or(pair.value.len(), Vec::len)
+}
+
+struct LocaleFallback {
+    const_name: String,
+    pairs: HashMap<Vec<String>, StringValuePathPair>,
+}
+
+impl LocaleFallback {
+    fn new(
+        locale: &str,
+        pairs: &[StringValuePathPair],
+        default_pairs: &[StringValuePathPair],
+    ) -> LocaleFallback {
+        // only strings of the same type (string or bytes) as the default string can be used
+        let default_types: HashMap<&[String], bool> = default_pairs
+            .iter()
+            .map(|pair| (&pair.path[..], pair.bytes.is_some()))
+            .collect();
+        LocaleFallback {
+            const_name: format!("STRINGS_{}", locale.to_case(Case::ScreamingSnake)),
+            pairs: pairs
+                .iter()
+                .filter(|pair| default_types.get(&pair.path[..]) == Some(&pair.bytes.is_some()))
+                .map(|pair| (pair.path.clone(), pair.clone()))
+                .collect(),
+        }
+    }
+}
+
+struct LocaleStringWithDefaultIter<
+    T: Iterator<Item = StringValuePathPair>,
+    U: Iterator<Item = StringValuePathPair>,
+> {
+    pairs: Box<T>,
     default_pairs: Box<U>,
     next_pair: Option<StringValuePathPair>,
     next_default_pair: Option<StringValuePathPair>,
+    fallbacks: Vec<LocaleFallback>,
+    missing_paths: Vec<Vec<String>>,
+    value_bytes: usize,
+}
+
+impl<T: Iterator<Item = StringValuePathPair>, U: Iterator<Item = StringValuePathPair>>
+    LocaleStringWithDefaultIter<T, U>
+{
+    pub fn new(
+        pairs: T,
+        mut default_pairs: U,
+        fallbacks: Vec<LocaleFallback>,
+    ) -> LocaleStringWithDefaultIter<T, U> {
+        let next_default_pair = default_pairs.next();
+        LocaleStringWithDefaultIter {
+            pairs: Box::new(pairs),
+            default_pairs: Box::new(default_pairs),
+            next_pair: None,
+            next_default_pair,
+            fallbacks,
+            missing_paths: Vec::new(),
+            value_bytes: 0,
+        }
+    }
+
+    /// paths of the default strings which had to be used as fallback,
+    /// only complete once the iterator is exhausted
+    pub fn missing_paths(&self) -> &[Vec<String>] {
+        &self.missing_paths[..]
+    }
+
+    /// total byte length of the (translated or fallback) values,
+    /// only complete once the iterator is exhausted
+    pub fn value_bytes(&self) -> usize {
+        self.value_bytes
+    }
+
+    /// the value of the first fallback locale translating the path of the given default pair,
+    /// or else the value of the default locale, together with the byte length of that value
+    fn fallback_value(&self, default_pair: &StringValuePathPair) -> (String, usize) {
+        let (const_name, pair) = self
+            .fallbacks
+            .iter()
+            .find_map(|fallback| {
+                fallback
+                    .pairs
+                    .get(&default_pair.path)
+                    .map(|pair| (fallback.const_name.as_str(), pair))
+            })
+            .unwrap_or(("STRINGS_DEFAULT", default_pair));
+        (
+            format!(
+                "{}.{}",
+                const_name,
+                default_pair
+                    .path
+                    .iter()
+                    .map(|s| s.to_case(Case::Snake))
+                    .join("."),
+            ),
+            value_byte_len(pair),
+        )
+    }
+}
+
+impl<T: Iterator<Item = StringValuePathPair>, U: Iterator<Item = StringValuePathPair>> Iterator
+    for LocaleStringWithDefaultIter<T, U>
+{
+    type Item = StringValuePathPair;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // load the next item to render
+        match self.next_default_pair.take() {
+            // if there is no next default pair,
+            // than we can immediately stop as it means we're finished,
+            // with all possible properties,
+            // anything left in our main pairs iter are non-standard properties
+            None => None,
+            Some(next_default_pair) => {
+                loop {
+                    // get the last peeked pair if there was one,
+                    // or else get the next one, so we can start comparing
+                    let pair = if self.next_pair.is_some() {
+                        self.next_pair.take()
+                    } else {
+                        self.pairs.next()
+                    };
+                    // if we didn't found a pair we'll need to start filling up all defaults
+                    let pair = match pair {
+                        Some(pair) => pair,
+                        None => {
+                            // missing keys, we'll fill up...
+                            self.next_default_pair = self.default_pairs.next();
+                            let (value, value_bytes) = self.fallback_value(&next_default_pair);
+                            self.missing_paths.push(next_default_pair.path.clone());
+                            self.value_bytes += value_bytes;
+                            return Some(StringValuePathPair {
+                                path: next_default_pair.path,
+                                value,
+                                feature: next_default_pair.feature,
+                                bytes: next_default_pair.bytes,
+                            });
+                        }
+                    };
+                    // if we have a match we mean our pairs has the required property at the current
+                    // position and thus we render the correct value
+                    if pair == next_default_pair {
+                        self.next_default_pair = self.default_pairs.next();
+                        if pair.bytes.is_some() != next_default_pair.bytes.is_some() {
+                            // bytes given as string (or vice versa) cannot be used,
+                            // so we fall back to the default value instead
+                            let (value, value_bytes) = self.fallback_value(&next_default_pair);
+                            self.value_bytes += value_bytes;
+                            self.missing_paths.push(next_default_pair.path.clone());
+                            return Some(StringValuePathPair {
+                                value,
+                                path: next_default_pair.path,
+                                feature: next_default_pair.feature,
+                                bytes: next_default_pair.bytes,
+                            });
+                        }
+                        self.value_bytes += value_byte_len(&pair);
+                        return Some(StringValuePathPair {
+                            value: value_literal(&pair),
+                            path: pair.path,
+                            // the default locale defines what is feature gated,
+                            // as all instances have to stay consistent with the struct def
+                            feature: next_default_pair.feature,
+                            bytes: pair.bytes,
+                        });
+                    }
+                    // in case we have not yet reached the current next default pair,
+                    // we want to skip the current pair, as it is a non-standard one
+                    // (reported as a codegen warning by the validation of the storage)
+                    if pair < next_default_pair {
+                        continue;
+                    }
+                    // our next pair is already beyond the next desired property path,
+                    // so we need to fill up until we reach our pair's current path
+                    self.next_default_pair = self.default_pairs.next();
+                    // keep our fetched pair for next time
+                    self.next_pair = Some(pair);
+                    let (value, value_bytes) = self.fallback_value(&next_default_pair);
+                    self.missing_paths.push(next_default_pair.path.clone());
+                    self.value_bytes += value_bytes;
+                    return Some(StringValuePathPair {
+                        path: next_default_pair.path,
+                        value,
+                        feature: next_default_pair.feature,
+                        bytes: next_default_pair.bytes,
+                    });
+                }
+            }
+        }
+    }
 }
 
-impl<T: Iterator<Item = StringValuePathPair>, U: Iterator<Item = StringValuePathPair>>
-    LocaleStringWithDefaultIter<T, U>
-{
-    pub fn new(
-        locale: String,
-        pairs: T,
-        mut default_pairs: U,
-    ) -> LocaleStringWithDefaultIter<T, U> {
-        let next_default_pair = default_pairs.next();
-        LocaleStringWithDefaultIter {
-            locale,
-            pairs: Box::new(pairs),
-            default_pairs: Box::new(default_pairs),
-            next_pair: None,
-            next_default_pair,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::codegen::common::assert_snapshot;
+    use crate::i18n::config::LocaleSettings;
+
+    const A11Y_SOURCE: &str = "a11y:
+  skip_to_content: Skip to content
+  main_nav: Main navigation
+  user_nav: User navigation
+  footer_nav: Footer navigation
+";
+
+    fn fixture_storage() -> Storage {
+        Storage::from_yaml_sources(&[
+            ("en", "site:\n  name: News\n  title: Welcome\n"),
+            ("nl", "site:\n  name: Nieuws\n"),
+            ("pt-br", "site:\n  name: Notícias\n"),
+        ])
+        .unwrap()
+    }
+
+    fn write_code(storage: &Storage, cfg: &I18n) -> String {
+        let mut out = Vec::new();
+        write_locales(&mut out, storage, cfg).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn generate_enum(storage: &Storage) -> String {
+        let mut out = Vec::new();
+        generate_locales_enum(&mut out, storage).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn generated_locales_match_snapshot() {
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                r##"
+site:
+  name: News
+  tagline: "\"Quoted\", back\\slash, hash # and \"# raw delimiters\"#"
+  nav:
+    header:
+      login: Log in
+      logout: Log out
+    footer: Made with ❤ in 🇧🇪
+page:
+  intro:
+    value: "**Bold** and _emphasis_"
+    format: md
+"##,
+            ),
+            (
+                "nl",
+                r##"
+site:
+  name: Nieuws — één
+  nav:
+    header:
+      login: Inloggen
+page:
+  intro:
+    value: "**Vet** en _nadruk_"
+    format: md
+"##,
+            ),
+        ])
+        .unwrap();
+        assert_snapshot("locales.rs.snap", &write_code(&storage, &I18n::default()));
+    }
+
+    #[test]
+    fn is_default_matches_only_default_variant() {
+        let code = generate_enum(&fixture_storage());
+        assert!(code.contains(
+            "    pub fn is_default(&self) -> bool {
+        matches!(self, Self::En)
+    }"
+        ));
+        assert!(!code.contains("matches!(self, Self::Nl)"));
+        assert!(!code.contains("matches!(self, Self::PtBr)"));
+    }
+
+    #[test]
+    fn base64_values_are_generated_as_bytes() {
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "site:\n  name: News\n  disclaimer:\n    value: AAEC/w==\n    format: base64\n",
+            ),
+            (
+                "nl",
+                "site:\n  name: Nieuws\n  disclaimer:\n    value: SGk=\n    format: base64\n",
+            ),
+            ("pt-br", "site:\n  name: Notícias\n  disclaimer: Oi\n"),
+        ])
+        .unwrap();
+        let code = write_code(&storage, &I18n::default());
+        assert!(code.contains(
+            "pub struct StringsSite {
+    pub disclaimer: &'static [u8],
+    pub name: &'static str,
+}"
+        ));
+        assert!(code.contains("        disclaimer: b\"\\x00\\x01\\x02\\xff\",\n"));
+        assert!(code.contains("        disclaimer: b\"Hi\",\n"));
+        // a string given for bytes falls back to the default value
+        assert!(code.contains("        disclaimer: STRINGS_DEFAULT.site.disclaimer,\n"));
+        // bytes are not part of the dynamic (str) lookup
+        assert!(code.contains("            \"site.name\" => Some(self.site.name),"));
+        assert!(!code.contains("\"site.disclaimer\" =>"));
+        // bytes are counted decoded: News + 4 bytes, Nieuws + 2 bytes, Notícias + 4 bytes
+        let bytes = "News".len() + 4 + "Nieuws".len() + 2 + "Notícias".len() + 4;
+        assert!(code.contains(&format!(
+            "pub const I18N_TOTAL_STRING_BYTES: usize = {};",
+            bytes
+        )));
+
+        let mut out = Vec::new();
+        let cfg = I18n {
+            strict: true,
+            ..I18n::default()
+        };
+        let err = write_locales(&mut out, &storage, &cfg).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("locale pt-br defines 'site.disclaimer' as a different type"));
+    }
+
+    #[test]
+    fn feature_gated_keys_are_cfg_guarded_in_struct_and_instances() {
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "site:\n  name: News\n  beta:\n    value: Try beta\n    feature: beta\n",
+            ),
+            ("nl", "site:\n  name: Nieuws\n  beta: Probeer beta\n"),
+            ("fr", "site:\n  name: Nouvelles\n"),
+        ])
+        .unwrap();
+        let default_pairs: Vec<StringValuePathPair> =
+            storage.get_default().unwrap().iter().collect();
+
+        let mut out = Vec::new();
+        generate_locales_strings_struct(
+            &mut out,
+            default_pairs.clone(),
+            &I18n::default(),
+            "Strings",
+            usize::MAX,
+        )
+        .unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains(
+            "    #[cfg(feature = \"beta\")]
+    pub beta: &'static str,
+    pub name: &'static str,"
+        ));
+
+        for locale in ["nl", "fr"] {
+            let pairs: Vec<StringValuePathPair> = LocaleStringWithDefaultIter::new(
+                storage.get(locale).unwrap().iter(),
+                default_pairs.clone().into_iter(),
+                Vec::new(),
+            )
+            .collect();
+            let mut out = Vec::new();
+            generate_locales_strings_instance(&mut out, "STRINGS", pairs.iter(), None).unwrap();
+            let code = String::from_utf8(out).unwrap();
+            assert!(code.contains(
+                "        #[cfg(feature = \"beta\")]
+        beta: "
+            ));
+            assert_eq!(code.matches("#[cfg(").count(), 1);
+        }
+    }
+
+    #[test]
+    fn diagnostics_list_every_locale_with_its_completeness() {
+        let mut cfg = I18n::default();
+        cfg.settings.insert(
+            "pt-br".to_owned(),
+            LocaleSettings {
+                direction: Some("rtl".to_owned()),
+                hidden: true,
+                ..Default::default()
+            },
+        );
+        let code = write_code(&fixture_storage(), &cfg);
+        for (locale, translated, hidden) in [("En", 2, false), ("Nl", 1, false), ("PtBr", 1, true)]
+        {
+            assert!(code.contains(&format!(
+                "    LocaleDiagnostics {{
+        locale: Locale::{},
+        translated_keys: {},
+        total_keys: 2,
+        hidden: {},
+    }},",
+                locale, translated, hidden
+            )));
+        }
+        // the direction is the one of the locale
+        assert!(code.contains(
+            "    pub fn direction(&self) -> Direction {
+        self.locale.direction()
+    }"
+        ));
+        assert!(code.contains("            Self::PtBr => Direction::Rtl,"));
+        assert!(code.contains(
+            "            self.direction(),
+            if self.hidden { \", hidden\" } else { \"\" }"
+        ));
+
+        // the mirror pseudo-locale is always hidden
+        let mut storage = fixture_storage();
+        storage.add_mirror_locale().unwrap();
+        let cfg = I18n {
+            mirror_locale: true,
+            ..I18n::default()
+        };
+        let code = write_code(&storage, &cfg);
+        assert!(code.contains(
+            "        locale: Locale::Mirror,
+        translated_keys: 2,
+        total_keys: 2,
+        hidden: true,"
+        ));
+    }
+
+    #[test]
+    fn strings_can_be_looked_up_by_a_path_of_keys() {
+        let storage = Storage::from_yaml_sources(&[(
+            "en",
+            "site:\n  name: News\n  logoText: Plabayo\nerrors:\n  not_found: not found\n",
+        )])
+        .unwrap();
+        let code = write_code(&storage, &I18n::default());
+        assert!(code.contains(
+            "    pub fn get_by_path(&self, path: &[&str]) -> Option<&'static str> {
+        match path {
+            [\"errors\", \"not_found\"] => Some(self.errors.not_found),
+            [\"site\", \"logoText\"] => Some(self.site.logo_text),
+            [\"site\", \"name\"] => Some(self.site.name),
+            _ => None,
+        }
+    }"
+        ));
+    }
+
+    #[test]
+    fn supports_excludes_fallback_strings() {
+        let code = write_code(&fixture_storage(), &I18n::default());
+        assert!(code.contains("            \"site.title\" => Some(self.site.title),"));
+        assert!(code.contains("            Self::En => self.effective(path).is_some(),"));
+        for locale in ["Nl", "PtBr"] {
+            assert!(code.contains(&format!(
+                "            Self::{} => {{
+                self.effective(path).is_some() && !matches!(path, \"site.title\")
+            }}",
+                locale
+            )));
+        }
+    }
+
+    #[test]
+    fn diff_compares_every_string_but_bytes() {
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "site:\n  name: News\n  beta:\n    value: Try beta\n    feature: beta\n  logo:\n    value: aGk=\n    format: base64\n",
+            ),
+            ("nl", "site:\n  name: Nieuws\n"),
+        ])
+        .unwrap();
+        let code = write_code(&storage, &I18n::default());
+        let diff = &code[code.find("    pub fn diff(").unwrap()..];
+        let diff = &diff[..diff.find("\n    }\n").unwrap()];
+        assert!(diff.contains(
+            "        #[cfg(feature = \"beta\")]
+        if self.site.beta != other.site.beta {
+            diff.push((\"site.beta\", self.site.beta, other.site.beta));
+        }
+        if self.site.name != other.site.name {
+            diff.push((\"site.name\", self.site.name, other.site.name));
+        }"
+        ));
+        assert!(!diff.contains("logo"));
+    }
+
+    #[test]
+    fn metrics_count_final_values_including_fallbacks() {
+        let code = write_code(&fixture_storage(), &I18n::default());
+        assert!(code.contains("pub const I18N_KEY_COUNT: usize = 2;"));
+        // en: News + Welcome, nl: Nieuws + Welcome, pt-br: Notícias + Welcome
+        let bytes = "NewsWelcome".len() + "NieuwsWelcome".len() + "NotíciasWelcome".len();
+        assert!(code.contains(&format!(
+            "pub const I18N_TOTAL_STRING_BYTES: usize = {};",
+            bytes
+        )));
+
+        // a string missing for pt-br is counted by the value of its pt fallback
+        let storage = Storage::from_yaml_sources(&[
+            ("en", "site:\n  name: News\n  title: Welcome\n"),
+            (
+                "pt",
+                "site:\n  name: Notícias\n  title: Bem-vindo ao site\n",
+            ),
+            ("pt-br", "site:\n  name: Notícias\n"),
+        ])
+        .unwrap();
+        let mut cfg = I18n::default();
+        cfg.settings.insert(
+            "pt-br".to_owned(),
+            LocaleSettings {
+                fallbacks: vec!["pt".to_owned()],
+                ..Default::default()
+            },
+        );
+        let code = write_code(&storage, &cfg);
+        // en: News + Welcome, pt: Notícias + Bem-vindo ao site, pt-br: Notícias + Bem-vindo ao site
+        let bytes = "NewsWelcome".len() + 2 * "NotíciasBem-vindo ao site".len();
+        assert!(code.contains(&format!(
+            "pub const I18N_TOTAL_STRING_BYTES: usize = {};",
+            bytes
+        )));
+    }
+
+    #[test]
+    fn is_supported_tag_only_accepts_known_tags() {
+        let code = generate_enum(&fixture_storage());
+        assert!(code.contains(
+            "    pub fn is_supported_tag(tag: &str) -> bool {
+        matches!(
+            tag.trim().to_lowercase().replace('_', \"-\").as_str(),
+            \"en\" | \"nl\" | \"pt-br\"
+        )
+    }"
+        ));
+        // region variants of supported locales are not supported tags themselves
+        assert!(!code.contains("\"en-us\""));
+        assert!(!code.contains("\"nl-be\""));
+    }
+
+    #[test]
+    fn all_locales_are_listed_default_first() {
+        let storage = Storage::from_yaml_sources(&[
+            ("nl", "site:\n  name: Nieuws\n"),
+            ("en", "site:\n  name: News\n"),
+            ("pt-br", "site:\n  name: Notícias\n"),
+        ])
+        .unwrap();
+        let code = generate_enum(&storage);
+        let all = code
+            .lines()
+            .find_map(|line| line.strip_prefix("    pub const ALL: &'static [Locale] = &["))
+            .and_then(|line| line.strip_suffix("];"))
+            .unwrap();
+        let variants: Vec<&str> = all.split(", ").collect();
+        assert_eq!(variants.len(), storage.all_locales().count());
+        assert_eq!(variants, vec!["Self::Nl", "Self::En", "Self::PtBr"]);
+        assert!(code.contains(
+            "    pub fn all() -> impl Iterator<Item = Locale> {
+        Self::ALL.iter().copied()
+    }"
+        ));
+    }
+
+    #[test]
+    fn display_writes_the_tag_even_for_a_single_locale() {
+        let storage = Storage::from_yaml_sources(&[("en", "site:\n  name: News\n")]).unwrap();
+        let code = generate_enum(&storage);
+        assert!(code.contains("    pub const ALL: &'static [Locale] = &[Self::En];"));
+        assert!(code.contains(
+            "impl std::fmt::Display for Locale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, \"{}\", self.as_str())
+    }
+}"
+        ));
+    }
+
+    #[test]
+    fn from_str_is_strict_and_lists_the_known_tags() {
+        let code = generate_enum(&fixture_storage());
+        assert!(code.contains("pub struct UnknownLocaleError(pub String);"));
+        assert!(code.contains(
+            "            \"unknown locale '{}', expected one of: en, nl, pt-br\",
+            self.0
+        )"
+        ));
+        assert!(code.contains(
+            "impl std::str::FromStr for Locale {
+    type Err = UnknownLocaleError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s).map_err(|_| UnknownLocaleError(s.to_owned()))
+    }
+}"
+        ));
+    }
+
+    #[test]
+    fn region_variants_share_their_language_subtag() {
+        let storage = Storage::from_yaml_sources(&[
+            ("en", "site:\n  name: News\n"),
+            ("pt", "site:\n  name: Notícias\n"),
+            ("pt-br", "site:\n  name: Notícias\n"),
+            ("pt-pt", "site:\n  name: Notícias\n"),
+        ])
+        .unwrap();
+        let code = generate_enum(&storage);
+        assert!(code.contains(
+            "    pub fn language_subtag(&self) -> &'static str {
+        match self {
+            Self::En => \"en\",
+            Self::Pt => \"pt\",
+            Self::PtBr => \"pt\",
+            Self::PtPt => \"pt\",
+        }
+    }"
+        ));
+        assert!(code.contains("    pub fn variants_for_language(lang: &str) -> Vec<Locale> {"));
+    }
+
+    #[test]
+    fn slugify_shares_transliteration_per_script() {
+        let mut cfg = I18n::default();
+        for locale in ["nl", "pt-br"] {
+            cfg.settings.insert(
+                locale.to_owned(),
+                LocaleSettings {
+                    script: Some("Cyrl".to_owned()),
+                    ..Default::default()
+                },
+            );
+        }
+        let mut out = Vec::new();
+        generate_locales_text_helpers(&mut out, &fixture_storage(), &cfg).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains("Self::En => transliterate_latn,"));
+        assert!(code.contains("Self::Nl => transliterate_cyrl,"));
+        assert!(code.contains("Self::PtBr => transliterate_cyrl,"));
+        assert_eq!(code.matches("fn transliterate_cyrl(").count(), 1);
+        assert_eq!(code.matches("fn transliterate_latn(").count(), 1);
+    }
+
+    #[test]
+    fn slugify_rejects_unknown_script() {
+        let mut cfg = I18n::default();
+        cfg.settings.insert(
+            "nl".to_owned(),
+            LocaleSettings {
+                script: Some("Klingon".to_owned()),
+                ..Default::default()
+            },
+        );
+        let mut out = Vec::new();
+        assert!(generate_locales_text_helpers(&mut out, &fixture_storage(), &cfg).is_err());
+    }
+
+    #[test]
+    fn flags_are_configured_or_derived_from_region_codes() {
+        let mut cfg = I18n::default();
+        for (locale, flag) in [("en", "🏴"), ("pt-br", "br")] {
+            cfg.settings.insert(
+                locale.to_owned(),
+                LocaleSettings {
+                    flag: Some(flag.to_owned()),
+                    ..Default::default()
+                },
+            );
+        }
+        let mut out = Vec::new();
+        generate_locales_text_helpers(&mut out, &fixture_storage(), &cfg).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains("            Self::En => Some(\"🏴\"),"));
+        assert!(code.contains("            Self::Nl => None,"));
+        assert!(code.contains("            Self::PtBr => Some(\"🇧🇷\"),"));
+        assert_eq!(flag_emoji("BE"), "\u{1F1E7}\u{1F1EA}");
+    }
+
+    #[test]
+    fn emoji_flag_sequences_handle_subdivisions_and_region_less_locales() {
+        let wales = "\u{1F3F4}\u{E0067}\u{E0062}\u{E0077}\u{E006C}\u{E0073}\u{E007F}";
+        assert_eq!(locale_tag_flag_emoji("en-gb-wls").unwrap(), wales);
+        assert_eq!(
+            locale_tag_flag_emoji("en-GB").unwrap(),
+            "\u{1F1EC}\u{1F1E7}"
+        );
+        assert_eq!(
+            locale_tag_flag_emoji("sr-latn-rs").unwrap(),
+            "\u{1F1F7}\u{1F1F8}"
+        );
+        assert_eq!(locale_tag_flag_emoji("en"), None);
+        assert_eq!(flag_emoji("GB-WLS"), wales);
+
+        let storage = Storage::from_yaml_sources(&[
+            ("en", "site:\n  name: News\n"),
+            ("en-gb-wls", "site:\n  name: News\n"),
+            ("nl", "site:\n  name: Nieuws\n"),
+            ("pt-br", "site:\n  name: Notícias\n"),
+        ])
+        .unwrap();
+        let mut cfg = I18n::default();
+        cfg.settings.insert(
+            "nl".to_owned(),
+            LocaleSettings {
+                flag: Some("BE".to_owned()),
+                ..Default::default()
+            },
+        );
+        let mut out = Vec::new();
+        generate_locales_text_helpers(&mut out, &storage, &cfg).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        let emoji_flag_sequence = &code[code.find("pub fn emoji_flag_sequence").unwrap()..];
+        assert!(emoji_flag_sequence.contains("            Self::En => Some(\"🌐\"),"));
+        assert!(emoji_flag_sequence
+            .contains(&format!("            Self::EnGbWls => Some({:?}),", wales)));
+        assert!(emoji_flag_sequence.contains("            Self::Nl => Some(\"🇧🇪\"),"));
+        assert!(emoji_flag_sequence.contains("            Self::PtBr => Some(\"🇧🇷\"),"));
+
+        // region-less locales can opt out of the globe
+        cfg.region_less_flag = Some(String::new());
+        let mut out = Vec::new();
+        generate_locales_text_helpers(&mut out, &storage, &cfg).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        let emoji_flag_sequence = &code[code.find("pub fn emoji_flag_sequence").unwrap()..];
+        assert!(emoji_flag_sequence.contains("            Self::En => None,"));
+    }
+
+    #[test]
+    fn direction_is_generated_as_enum() {
+        let mut cfg = I18n::default();
+        cfg.settings.insert(
+            "pt-br".to_owned(),
+            LocaleSettings {
+                direction: Some("rtl".to_owned()),
+                ..Default::default()
+            },
+        );
+        let mut out = Vec::new();
+        generate_locales_text_helpers(&mut out, &fixture_storage(), &cfg).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains("    pub fn direction(&self) -> Direction {"));
+        assert!(code.contains("            Self::En => Direction::Ltr,"));
+        assert!(code.contains("            Self::PtBr => Direction::Rtl,"));
+        assert!(code.contains("            Self::Rtl => \"rtl\","));
+        assert!(code.contains("            Self::PtBr => \"lang=\\\"pt-BR\\\" dir=\\\"rtl\\\"\","));
+        assert!(code.contains("            Self::En => \"lang=\\\"en\\\" dir=\\\"ltr\\\"\","));
+        assert_eq!(bcp47_tag("ZH-hant-tw"), "zh-Hant-TW");
+
+        // the mirror pseudo-locale is rtl unless configured otherwise
+        let mut storage = fixture_storage();
+        storage.add_mirror_locale().unwrap();
+        let mut out = Vec::new();
+        generate_locales_text_helpers(&mut out, &storage, &cfg).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains("            Self::Mirror => Direction::Rtl,"));
+    }
+
+    #[test]
+    fn too_many_missing_strings_fail_when_limited() {
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "site:\n  name: News\n  title: Welcome\n  tagline: Hacker news\n",
+            ),
+            ("fr", "site:\n  name: Nouvelles\n  title: Bienvenue\n"),
+            ("nl", "site:\n  name: Nieuws\n"),
+        ])
+        .unwrap();
+        let mut cfg = I18n::default();
+        write_locales(Vec::new(), &storage, &cfg).unwrap();
+
+        cfg.max_missing_strings = Some(1);
+        let err = write_locales(Vec::new(), &storage, &cfg).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "locale nl is missing 2 string(s), more than the maximum of 1: site.tagline, site.title"
+        );
+        // no missing strings at all, with only the first ones named
+        cfg.max_missing_strings = Some(0);
+        let err = write_locales(Vec::new(), &storage, &cfg).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "locale fr is missing 1 string(s), more than the maximum of 0: site.tagline
+locale nl is missing 2 string(s), more than the maximum of 0: site.tagline, site.title"
+        );
+        cfg.max_missing_strings = Some(2);
+        write_locales(Vec::new(), &storage, &cfg).unwrap();
+
+        let paths: Vec<String> = (0..12).map(|i| format!("key_{:02}", i)).collect();
+        let err = report_missing_strings(
+            &mut Warnings::new(false),
+            &[("nl".to_owned(), paths)],
+            Some(0),
+        )
+        .unwrap_err();
+        assert!(err.to_string().ends_with("key_08, key_09, ..."));
+
+        // missing strings are codegen warnings, and thus fail the build in strict mode
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                &format!(
+                    "site:\n  name: News\n  title: Welcome\n  tagline: Hacker news\n{}",
+                    A11Y_SOURCE
+                ),
+            ),
+            (
+                "fr",
+                &format!(
+                    "site:\n  name: Nouvelles\n  title: Bienvenue\n{}",
+                    A11Y_SOURCE
+                ),
+            ),
+            ("nl", &format!("site:\n  name: Nieuws\n{}", A11Y_SOURCE)),
+        ])
+        .unwrap();
+        let cfg = I18n {
+            strict: true,
+            ..I18n::default()
+        };
+        let err = write_locales(Vec::new(), &storage, &cfg).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "strict mode: 5 codegen warning(s) found:
+- locale fr is missing string 'site.tagline', please add it to fr.yml!
+- locale nl is missing string 'site.tagline', please add it to nl.yml!
+- locale nl is missing string 'site.title', please add it to nl.yml!
+- locale fr translation coverage is 85.7% (6/7 keys)
+- locale nl translation coverage is 71.4% (5/7 keys)"
+        );
+    }
+
+    #[test]
+    fn translation_coverage_excludes_verbatim_strings() {
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "site:\n  name: Plabayo News\n  title: Welcome\n  tagline: Hacker news\nbrand:\n  short: PN\n  long: Plabayo\n",
+            ),
+            ("fr", "site:\n  title: Bienvenue\n  tagline: Nouvelles\n"),
+            ("nl", "site:\n  title: Welkom\n"),
+        ])
+        .unwrap();
+        let mut cfg = I18n {
+            min_coverage: Some(30.0),
+            ..I18n::default()
+        };
+        // 2 of 5 keys are translated by fr, 1 of 5 by nl
+        let err = write_locales(Vec::new(), &storage, &cfg).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "locale nl has a translation coverage of 20.0%, below the minimum of 30.0%"
+        );
+
+        // nl translates 1 out of 2 keys, fr all of them
+        cfg.min_coverage = Some(50.0);
+        cfg.verbatim = vec!["site.name".to_owned(), "brand".to_owned()];
+        write_locales(Vec::new(), &storage, &cfg).unwrap();
+        let default_pairs: Vec<StringValuePathPair> =
+            storage.get_default().unwrap().iter().collect();
+        let missing = |paths: &[&str]| paths.iter().map(|path| (*path).to_owned()).collect();
+        cfg.min_coverage = Some(100.0);
+        let err = report_coverage(
+            &mut Warnings::new(false),
+            "en",
+            &default_pairs[..],
+            &[
+                ("en".to_owned(), missing(&[])),
+                (
+                    "fr".to_owned(),
+                    missing(&["brand.long", "brand.short", "site.name"]),
+                ),
+                ("nl".to_owned(), missing(&["brand.long", "site.tagline"])),
+            ],
+            &cfg,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "locale nl has a translation coverage of 50.0%, below the minimum of 100.0%"
+        );
+    }
+
+    #[test]
+    fn strict_mode_fails_on_incomplete_coverage_only() {
+        let storage = Storage::from_yaml_sources(&[
+            ("en", "site:\n  name: News\n  title: Welcome\n"),
+            ("fr", "site:\n  name: Nouvelles\n  title: Bienvenue\n"),
+            ("nl", "site:\n  name: Nieuws\n"),
+        ])
+        .unwrap();
+        let default_pairs: Vec<StringValuePathPair> =
+            storage.get_default().unwrap().iter().collect();
+        let cfg = I18n::default();
+        let mut warnings = Warnings::new(true);
+        report_coverage(
+            &mut warnings,
+            "en",
+            &default_pairs[..],
+            &[
+                ("en".to_owned(), vec![]),
+                ("fr".to_owned(), vec![]),
+                ("nl".to_owned(), vec!["site.title".to_owned()]),
+            ],
+            &cfg,
+        )
+        .unwrap();
+        assert_eq!(
+            warnings.finish().unwrap_err().to_string(),
+            "strict mode: 1 codegen warning(s) found:
+- locale nl translation coverage is 50.0% (1/2 keys)"
+        );
+
+        // complete locales don't warn
+        let mut warnings = Warnings::new(true);
+        report_coverage(
+            &mut warnings,
+            "en",
+            &default_pairs[..],
+            &[("en".to_owned(), vec![]), ("fr".to_owned(), vec![])],
+            &cfg,
+        )
+        .unwrap();
+        warnings.finish().unwrap();
+    }
+
+    #[test]
+    fn structure_conflicts_fail_generation() {
+        let storage = Storage::from_yaml_sources(&[
+            ("en", "site:\n  name: News\n  nav:\n    login: Log in\n"),
+            (
+                "nl",
+                "site:\n  name:\n    short: Nieuws\n  nav: Navigatie\n",
+            ),
+        ])
+        .unwrap();
+        let dir = std::env::temp_dir().join("plabayo_news_builder_structure_conflicts");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("locales.rs");
+        let err = generate_locales(&file_path, &storage, &I18n::default()).unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            format!("generate locales in {}: locale nl defines 'site.name' as an object (with 'site.name.short') while the default locale defines it as a string, please align nl.yml with the default locale!
+locale nl defines 'site.nav' as a string while the default locale defines it as an object (with 'site.nav.login'), please align nl.yml with the default locale!", file_path.display())
+        );
+        assert!(!file_path.exists());
+    }
+
+    #[test]
+    fn failing_generation_leaves_no_partial_file() {
+        let storage = Storage::from_yaml_sources(&[
+            ("en", "site:\n  name: News\n  title: Welcome\n"),
+            ("nl", "site:\n  name: Nieuws\n"),
+        ])
+        .unwrap();
+        let dir = std::env::temp_dir().join("plabayo_news_builder_partial_locales");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("locales.rs");
+        let cfg = I18n {
+            max_missing_strings: Some(0),
+            ..I18n::default()
+        };
+        assert!(generate_locales(&file_path, &storage, &cfg).is_err());
+        assert!(!file_path.exists());
+
+        generate_locales(&file_path, &storage, &I18n::default()).unwrap();
+        assert!(std::fs::read_to_string(&file_path)
+            .unwrap()
+            .contains("pub enum Locale {"));
+    }
+
+    #[test]
+    fn missing_strings_walk_the_fallback_chain() {
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "site:\n  name: News\n  title: Welcome\n  tagline: Hacker news\n",
+            ),
+            ("pt", "site:\n  name: Notícias\n  title: Bem-vindo\n"),
+            ("pt-br", "site:\n  name: Notícias\n"),
+            ("nl", "site:\n  name: Nieuws\n"),
+        ])
+        .unwrap();
+        let fallbacks = |locales: &[&str]| LocaleSettings {
+            fallbacks: locales.iter().map(|locale| (*locale).to_owned()).collect(),
+            ..Default::default()
+        };
+        let mut cfg = I18n::default();
+        cfg.settings.insert("pt-br".to_owned(), fallbacks(&["pt"]));
+        let code = write_code(&storage, &cfg);
+        let pt_br = &code[code.find("const STRINGS_PT_BR: Strings").unwrap()..];
+        assert!(pt_br.contains("        title: STRINGS_PT.site.title,"));
+        assert!(pt_br.contains("        tagline: STRINGS_DEFAULT.site.tagline,"));
+        let pt = &code[code.find("const STRINGS_PT: Strings").unwrap()..];
+        assert!(pt.contains("        tagline: STRINGS_DEFAULT.site.tagline,"));
+        let nl = &code[code.find("const STRINGS_NL: Strings").unwrap()..];
+        assert!(nl.contains("        title: STRINGS_DEFAULT.site.title,"));
+
+        // the chain continues with the fallbacks of the fallbacks
+        cfg.settings.insert("nl".to_owned(), fallbacks(&["pt-br"]));
+        let code = write_code(&storage, &cfg);
+        let nl = &code[code.find("const STRINGS_NL: Strings").unwrap()..];
+        assert!(nl.contains("        title: STRINGS_PT.site.title,"));
+
+        for (locale, locales, expected) in [
+            ("nl", &["nl"][..], "locale nl cannot be its own fallback"),
+            (
+                "nl",
+                &["en"][..],
+                "fallback en of locale nl is the default locale, which is always the last fallback",
+            ),
+            (
+                "nl",
+                &["fr"][..],
+                "fallback fr of locale nl is not a supported locale",
+            ),
+            (
+                "pt",
+                &["nl"][..],
+                "fallbacks of locale nl form a cycle: nl -> pt-br -> pt -> nl",
+            ),
+            (
+                "en",
+                &["nl"][..],
+                "default locale en cannot have fallbacks, as it defines all strings",
+            ),
+        ] {
+            let mut cfg = I18n::default();
+            cfg.settings.insert("pt-br".to_owned(), fallbacks(&["pt"]));
+            cfg.settings.insert("nl".to_owned(), fallbacks(&["pt-br"]));
+            cfg.settings.insert(locale.to_owned(), fallbacks(locales));
+            let err = write_locales(Vec::new(), &storage, &cfg).unwrap_err();
+            assert_eq!(err.to_string(), expected);
         }
     }
-}
 
-impl<T: Iterator<Item = StringValuePathPair>, U: Iterator<Item = StringValuePathPair>> Iterator
-    for LocaleStringWithDefaultIter<T, U>
-{
-    type Item = StringValuePathPair;
+    #[test]
+    fn hreflang_is_canonicalized_unless_configured() {
+        let storage = Storage::from_yaml_sources(&[
+            ("en", "site:\n  name: News\n"),
+            ("zh_hans", "site:\n  name: News\n"),
+            ("nl", "site:\n  name: Nieuws\n"),
+        ])
+        .unwrap();
+        let mut cfg = I18n::default();
+        cfg.settings.insert(
+            "nl".to_owned(),
+            LocaleSettings {
+                hreflang: Some("nl-BE".to_owned()),
+                ..Default::default()
+            },
+        );
+        let mut out = Vec::new();
+        generate_locales_text_helpers(&mut out, &storage, &cfg).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains(
+            "    pub fn hreflang(&self) -> &'static str {
+        match self {
+            Self::En => \"en\",
+            Self::Nl => \"nl-BE\",
+            Self::ZhHans => \"zh-Hans\",
+        }
+    }"
+        ));
+        assert!(
+            code.contains("            Self::ZhHans => \"lang=\\\"zh-Hans\\\" dir=\\\"ltr\\\"\",")
+        );
 
-    fn next(&mut self) -> Option<Self::Item> {
-        // load the next item to render
-        match std::mem::replace(&mut self.next_default_pair, None) {
-            // if there is no next default pair,
-            // than we can immediately stop as it means we're finished,
-            // with all possible properties,
-            // anything left in our main pairs iter are non-standard properties
-            None => None,
-            Some(next_default_pair) => {
-                loop {
-                    // get the last peeked pair if there was one,
-                    // or else get the next one, so we can start comparing
-                    let pair = if self.next_pair.is_some() {
-                        std::mem::replace(&mut self.next_pair, None)
-                    } else {
-                        self.pairs.next()
-                    };
-                    // if we didn't found a pair we'll need to start filling up all defaults
-                    let pair = match pair {
-                        Some(pair) => pair,
-                        None => {
-                            // missing keys, we'll fill up...
-                            self.next_default_pair = self.default_pairs.next();
-                            let value = format!(
-                                "STRINGS_DEFAULT.{}",
-                                next_default_pair
-                                    .path
-                                    .iter()
-                                    .map(|s| s.to_case(Case::Snake))
-                                    .join("."),
-                            );
-                            eprintln!(
-                                "plabayo-news_builder: missing string for locale {locale}, resolved by using fallback: {value}; Please add the translated string to {locale}.yml!",
-                                value=value, locale=self.locale);
-                            return Some(StringValuePathPair {
-                                path: next_default_pair.path,
-                                value,
-                            });
-                        }
-                    };
-                    // if we have a match we mean our pairs has the required property at the current
-                    // position and thus we render the correct value
-                    if pair == next_default_pair {
-                        self.next_default_pair = self.default_pairs.next();
-                        return Some(StringValuePathPair {
-                            path: pair.path,
-                            value: format!(
-                                r#################"r################"{}"################"#################,
-                                pair.value
-                            ),
-                        });
-                    }
-                    // in case we have not yet reached the current next default pair,
-                    // we want to skip the current pair, as it is a non-standard one
-                    if pair < next_default_pair {
-                        let value = format!(
-                            "STRINGS_{}.{}",
-                            self.locale.to_case(Case::ScreamingSnake),
-                            pair.path.iter().map(|s| s.to_case(Case::Snake)).join("."),
-                        );
-                        eprintln!(
-                            "plabayo-news_builder: skipping string '{value}' for locale {locale}: it is not found in default locale; Please remove this string from {locale}.yml!",
-                            value=value, locale=self.locale);
-                        continue;
-                    }
-                    // our next pair is already beyond the next desired property path,
-                    // so we need to fill up until we reach our pair's current path
-                    self.next_default_pair = self.default_pairs.next();
-                    // keep our fetched pair for next time
-                    self.next_pair = Some(pair);
-                    let value = format!(
-                        "STRINGS_DEFAULT.{}",
-                        next_default_pair
-                            .path
-                            .iter()
-                            .map(|s| s.to_case(Case::Snake))
-                            .join("."),
-                    );
-                    eprintln!(
-                        "plabayo-news_builder: missing string for locale {locale}, resolved by using fallback: {value}; Please add the translated string to {locale}.yml!",
-                        value=value, locale=self.locale);
-                    return Some(StringValuePathPair {
-                        path: next_default_pair.path,
-                        value,
-                    });
+        cfg.settings.get_mut("nl").unwrap().hreflang = Some("nl_BE".to_owned());
+        assert!(generate_locales_text_helpers(Vec::new(), &storage, &cfg).is_err());
+    }
+
+    #[test]
+    fn icu_language_identifiers_are_feature_gated_and_opt_in() {
+        let storage = Storage::from_yaml_sources(&[
+            ("en", "site:\n  name: News\n"),
+            ("zh_hans", "site:\n  name: News\n"),
+            ("pt-br", "site:\n  name: Notícias\n"),
+        ])
+        .unwrap();
+        let mut cfg = I18n::default();
+        assert!(!write_code(&storage, &cfg).contains("icu_locid"));
+
+        cfg.icu = true;
+        cfg.settings.insert(
+            "zh_hans".to_owned(),
+            LocaleSettings {
+                cldr_id: Some("zh-Hans-CN".to_owned()),
+                ..Default::default()
+            },
+        );
+        let code = write_code(&storage, &cfg);
+        assert!(code.contains(
+            "#[cfg(feature = \"icu\")]
+impl Locale {
+    /// the ICU language identifier of this locale
+    pub fn to_langid(&self) -> icu_locid::LanguageIdentifier {
+        match self {
+            Self::En => \"en\",
+            Self::PtBr => \"pt-BR\",
+            Self::ZhHans => \"zh-Hans-CN\",
+        }"
+        ));
+        assert!(code.contains(
+            "#[cfg(feature = \"icu\")]
+impl From<Locale> for icu_locid::LanguageIdentifier {"
+        ));
+
+        for (locale, cldr_id) in [
+            ("en", "e"),
+            ("en", "en-"),
+            ("en", "en-toolongsubtag"),
+            ("en", "abcd"),
+            ("en", "en-a"),
+            ("en", "en-US-Latn"),
+            ("en", "en-abc"),
+        ] {
+            cfg.settings.insert(
+                locale.to_owned(),
+                LocaleSettings {
+                    cldr_id: Some(cldr_id.to_owned()),
+                    ..Default::default()
+                },
+            );
+            assert!(locale_cldr_id(&cfg, locale).is_err(), "{}", cldr_id);
+        }
+        for cldr_id in [
+            "en",
+            "fil",
+            "zh-Hans",
+            "sr_Latn_RS",
+            "es-419",
+            "de-CH-1996",
+            "sl-rozaj-biske",
+        ] {
+            assert!(is_language_identifier(cldr_id), "{}", cldr_id);
+        }
+    }
+
+    #[test]
+    fn percent_formats_are_generated_per_locale() {
+        let mut cfg = I18n::default();
+        cfg.settings.insert(
+            "nl".to_owned(),
+            LocaleSettings {
+                decimal_separator: Some(",".to_owned()),
+                percent_pattern: Some("{}\u{a0}%".to_owned()),
+                ..Default::default()
+            },
+        );
+        let mut out = Vec::new();
+        generate_locales_text_helpers(&mut out, &fixture_storage(), &cfg).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains("            Self::En => format!(\"{}%\", number),"));
+        assert!(code.contains(
+            "            Self::Nl => format!(\"{}\\u{a0}%\", number.replace('.', \",\")),"
+        ));
+
+        cfg.settings.get_mut("nl").unwrap().percent_pattern = Some("{} {}%".to_owned());
+        assert!(generate_locales_text_helpers(Vec::new(), &fixture_storage(), &cfg).is_err());
+        cfg.settings.get_mut("nl").unwrap().percent_pattern = Some("{0}%".to_owned());
+        assert!(generate_locales_text_helpers(Vec::new(), &fixture_storage(), &cfg).is_err());
+    }
+
+    #[test]
+    fn datetime_patterns_are_generated_per_locale() {
+        let mut cfg = I18n::default();
+        cfg.settings.insert(
+            "nl".to_owned(),
+            LocaleSettings {
+                date_pattern: Some("%d-%m-%Y".to_owned()),
+                datetime_pattern: Some("%d-%m-%Y %H:%M".to_owned()),
+                ..Default::default()
+            },
+        );
+        let mut out = Vec::new();
+        generate_locales_text_helpers(&mut out, &fixture_storage(), &cfg).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains(
+            "    pub fn date_pattern(&self) -> &'static str {
+        match self {
+            Self::En => \"%Y-%m-%d\",
+            Self::Nl => \"%d-%m-%Y\",
+            Self::PtBr => \"%Y-%m-%d\",
+        }
+    }"
+        ));
+        assert!(code.contains(
+            "    pub fn time_pattern(&self) -> &'static str {
+        match self {
+            Self::En => \"%H:%M\",
+            Self::Nl => \"%H:%M\","
+        ));
+        assert!(code.contains(
+            "            Self::En => \"%Y-%m-%d %H:%M\",
+            Self::Nl => \"%d-%m-%Y %H:%M\","
+        ));
+    }
+
+    #[test]
+    fn unavailable_notice_names_requested_and_served_languages() {
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "site:\n  locales:\n    en: English\n    nl: Nederlands\n  unavailable: This page isn't available in {requested}; showing {served}\n",
+            ),
+            (
+                "nl",
+                "site:\n  unavailable: Deze pagina is niet beschikbaar in {requested}; we tonen {served}\n",
+            ),
+        ])
+        .unwrap();
+        let mut out = Vec::new();
+        generate_locales_unavailable_notice(&mut out, &storage, "site.unavailable").unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains(
+            "            Self::En => \"This page isn't available in {requested}; showing English\",
+            Self::Nl => \"Deze pagina is niet beschikbaar in {requested}; we tonen Nederlands\",
+        };
+        notice.replace(\"{requested}\", requested)"
+        ));
+
+        let mut cfg = I18n::default();
+        assert!(!write_code(&storage, &cfg).contains("unavailable_notice"));
+        cfg.unavailable_notice = Some("site.unavailable".to_owned());
+        assert!(write_code(&storage, &cfg).contains("pub fn unavailable_notice("));
+        cfg.unavailable_notice = Some("site.missing".to_owned());
+        assert!(write_locales(Vec::new(), &storage, &cfg).is_err());
+    }
+
+    #[test]
+    fn currency_formats_are_generated_per_locale() {
+        let mut cfg = I18n::default();
+        cfg.settings.insert(
+            "nl".to_owned(),
+            LocaleSettings {
+                decimal_separator: Some(",".to_owned()),
+                group_separator: Some(".".to_owned()),
+                currency_pattern: Some("{amount}\u{a0}{symbol}".to_owned()),
+                currency_symbols: vec![("usd".to_owned(), "US$".to_owned())]
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            },
+        );
+        let mut out = Vec::new();
+        generate_locales_text_helpers(&mut out, &fixture_storage(), &cfg).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains(
+            "            Self::En => {
+                let symbol = match currency.to_ascii_uppercase().as_str() {
+                    \"EUR\" => \"\u{20ac}\",
+                    \"GBP\" => \"\u{a3}\",
+                    \"JPY\" => \"\u{a5}\",
+                    \"USD\" => \"$\",
+                    _ => currency,
+                };
+                let amount = format!(
+                    \"{}{}{}\",
+                    Self::group_digits(integer, \",\"),
+                    \".\",
+                    fraction
+                );
+                format!(\"{sign}{symbol}{amount}\", sign = sign, amount = amount, symbol = symbol)
+            }"
+        ));
+        // the symbol is placed after the amount
+        assert!(code.contains("                    \"USD\" => \"US$\",\n"));
+        assert!(code.contains(
+            "                    Self::group_digits(integer, \".\"),\n                    \",\",\n"
+        ));
+        assert!(code.contains(
+            "format!(\"{sign}{amount}\\u{a0}{symbol}\", sign = sign, amount = amount, symbol = symbol)"
+        ));
+
+        cfg.settings.get_mut("nl").unwrap().currency_pattern = Some("{amount}".to_owned());
+        assert!(generate_locales_text_helpers(Vec::new(), &fixture_storage(), &cfg).is_err());
+        cfg.settings.get_mut("nl").unwrap().currency_pattern =
+            Some("{amount} {symbol} {code}".to_owned());
+        assert!(generate_locales_text_helpers(Vec::new(), &fixture_storage(), &cfg).is_err());
+    }
+
+    #[test]
+    fn isolate_only_wraps_values_when_bidi_isolation_is_enabled() {
+        let mut cfg = I18n::default();
+        cfg.settings.insert(
+            "nl".to_owned(),
+            LocaleSettings {
+                direction: Some("rtl".to_owned()),
+                ..Default::default()
+            },
+        );
+        let mut out = Vec::new();
+        generate_locales_text_helpers(&mut out, &fixture_storage(), &cfg).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains("            Self::Nl => Direction::Rtl,"));
+        assert!(code.contains("            Self::PtBr => Direction::Ltr,"));
+        assert!(code.contains("        value.to_owned()"));
+
+        cfg.bidi_isolation = true;
+        let mut out = Vec::new();
+        generate_locales_text_helpers(&mut out, &fixture_storage(), &cfg).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains(
+            "    pub fn isolate(&self, value: &str, direction: Option<Direction>) -> String {
+        let isolate = match direction {
+            Some(Direction::Ltr) => '\\u{2066}',
+            Some(Direction::Rtl) => '\\u{2067}',
+            None => '\\u{2068}',
+        };
+        format!(\"{}{}\\u{2069}\", isolate, value)"
+        ));
+
+        cfg.settings.get_mut("nl").unwrap().direction = Some("ttb".to_owned());
+        assert!(generate_locales_text_helpers(Vec::new(), &fixture_storage(), &cfg).is_err());
+    }
+
+    #[test]
+    fn from_shim_is_deprecated_when_enabled() {
+        let mut cfg = I18n::default();
+        let mut out = Vec::new();
+        generate_locales_from_shim(&mut out, &fixture_storage(), &cfg).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains("    pub fn from(s: &str) -> Locale {"));
+        assert!(!code.contains("#[deprecated"));
+        assert!(code.contains("    pub const fn from_tag_const(tag: &str) -> Locale {"));
+        assert!(code.contains(
+            "            if tag_eq_ignore_ascii_case(tag, start, end, b\"pt-br\") {
+                return Self::PtBr;
+            }"
+        ));
+        assert!(code.contains(
+            "            if end == start {
+                return Self::En;
+            }"
+        ));
+        assert!(code.contains("            Locale::from_tag_const(s)"));
+
+        cfg.deprecate_from = true;
+        let mut out = Vec::new();
+        generate_locales_from_shim(&mut out, &fixture_storage(), &cfg).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains(
+            "    #[deprecated(note = \"use `Locale::try_from`, which does not hide unsupported locales\")]
+    #[allow(clippy::should_implement_trait)]
+    pub fn from(s: &str) -> Locale {"
+        ));
+    }
+
+    #[test]
+    fn strings_are_split_by_domain_when_enabled() {
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "title: News\nnav:\n  submit: submit\n  user:\n    login: login\nerrors:\n  not_found: not found\n",
+            ),
+            ("nl", "title: Nieuws\nnav:\n  submit: deel\n"),
+        ])
+        .unwrap();
+        let mut cfg = I18n::default();
+        let code = write_code(&storage, &cfg);
+        assert!(!code.contains("STRINGS_NL_NAV"));
+
+        cfg.split_domains = true;
+        let mut out = Vec::new();
+        let report = write_locales(&mut out, &storage, &cfg).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains("mod strings_nav;\npub use strings_nav::*;\n"));
+        assert!(code.contains("    pub nav: StringsNav,\n"));
+        assert!(!code.contains("pub struct StringsNav {"));
+        assert!(!code.contains("const STRINGS_NL_NAV"));
+        assert!(code.contains(
+            "
+const STRINGS_NL: Strings = Strings {
+    errors: STRINGS_NL_ERRORS,
+    nav: STRINGS_NL_NAV,
+    title: r\"Nieuws\",
+};
+"
+        ));
+
+        // every domain is a module of its own, with its structs and consts
+        let modules: Vec<&str> = report
+            .domain_modules
+            .iter()
+            .map(|(module, _)| module.as_str())
+            .collect();
+        assert_eq!(modules, vec!["strings_errors", "strings_nav"]);
+        let nav = std::str::from_utf8(&report.domain_modules[1].1).unwrap();
+        assert!(nav.contains("#[allow(unused_imports)]\nuse super::*;\n"));
+        assert!(nav.contains("pub struct StringsNav {\n    pub submit: &'static str,\n    pub user: StringsNavUser,\n}"));
+        assert!(nav.contains("pub struct StringsNavUser {\n    pub login: &'static str,\n}"));
+        assert!(nav.contains(
+            "
+pub(super) const STRINGS_NL_NAV: StringsNav = StringsNav {
+    submit: r\"deel\",
+    user: StringsNavUser {
+        login: STRINGS_DEFAULT.nav.user.login,
+    },
+};
+"
+        ));
+        assert!(nav.contains("pub(super) const STRINGS_DEFAULT_NAV: StringsNav = StringsNav {"));
+        assert!(!nav.contains("StringsErrors"));
+    }
+
+    #[test]
+    fn accessors_are_generated_with_flattened_names_when_enabled() {
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "title: News\nhome:\n  title: Home\n  logo:\n    value: aGk=\n    format: base64\n",
+            ),
+            ("nl", "home:\n  title: Start\n"),
+        ])
+        .unwrap();
+        let mut cfg = I18n::default();
+        let code = write_code(&storage, &cfg);
+        assert!(code.contains("    pub home: StringsHome,"));
+        assert!(!code.contains("pub fn home_title"));
+
+        cfg.accessors = true;
+        let code = write_code(&storage, &cfg);
+        assert!(code.contains("    pub(crate) home: StringsHome,"));
+        assert!(code.contains("    pub(crate) title: &'static str,"));
+        assert!(code.contains(
+            "    pub fn home_title(&self) -> &'static str {
+        self.home.title
+    }"
+        ));
+        assert!(code.contains(
+            "    pub fn home_logo(&self) -> &'static [u8] {
+        self.home.logo
+    }"
+        ));
+        assert!(code.contains("    pub fn title(&self) -> &'static str {"));
+
+        let storage = Storage::from_yaml_sources(&[(
+            "en",
+            "home:\n  page_title: Home\nhome_page:\n  title: Home\n",
+        )])
+        .unwrap();
+        let mut out = Vec::new();
+        let err = write_locales(&mut out, &storage, &cfg).unwrap_err();
+        assert!(format!("{:#}", err).contains(
+            "accessor 'home_page_title' of string 'home_page.title' collides with the accessor of string 'home.page_title'"
+        ));
+        let storage = Storage::from_yaml_sources(&[("en", "get: Get\n")]).unwrap();
+        assert!(write_locales(Vec::new(), &storage, &cfg).is_err());
+    }
+
+    #[test]
+    fn plural_strings_select_their_form_by_the_rules_of_the_locale() {
+        let mut storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "site:\n  name: News\ncomments:\n  one: comment\n  other: comments\n",
+            ),
+            (
+                "ru",
+                "comments:\n  one: komentariy\n  few: komentariya\n  many: komentariev\n  other: komentariya\n",
+            ),
+            ("ja", "comments:\n  other: komento\n"),
+        ])
+        .unwrap();
+        let mut cfg = I18n::default();
+        assert!(!write_code(&storage, &cfg).contains("PluralCategory"));
+
+        cfg.plurals = true;
+        storage.add_plural_forms().unwrap();
+        let code = write_code(&storage, &cfg);
+        assert!(code.contains("    pub few: &'static str,"));
+        assert!(code.contains(
+            "            Self::Ja => PluralCategory::Other,
+            Self::Ru => {
+                if n % 10 == 1 && n % 100 != 11 {
+                    PluralCategory::One
+                } else if (2..=4).contains(&(n % 10)) && !(12..=14).contains(&(n % 100)) {
+                    PluralCategory::Few
+                } else {
+                    PluralCategory::Many
                 }
             }
+"
+        ));
+        assert!(code.contains(
+            "    pub fn comments(&self, n: u64) -> &'static str {
+        let forms = &self.strings().comments;
+        match self.plural_category(n) {
+            PluralCategory::Zero => forms.zero,
+            PluralCategory::One => forms.one,
+            PluralCategory::Two => forms.two,
+            PluralCategory::Few => forms.few,
+            PluralCategory::Many => forms.many,
+            PluralCategory::Other => forms.other,
+        }
+    }
+"
+        ));
+        assert!(!code.contains("pub fn site("));
+        // the forms are part of the strings, the ones not translated using the `other` form
+        assert!(code.contains("        few: r\"komentariya\",\n"));
+        assert!(code.contains("        few: r\"komento\",\n"));
+        assert!(code.contains("        few: r\"comments\",\n"));
+    }
+
+    #[test]
+    fn interpolation_methods_are_generated_for_strings_with_placeholders() {
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "site:\n  name: News\n  greeting: Welcome back, {name}\n  points: \"{name} has {count} points, {name}!\"\n  ago: \"{} ago\"\n",
+            ),
+            ("nl", "site:\n  greeting: Welkom terug, {name}\n"),
+        ])
+        .unwrap();
+        let mut cfg = I18n::default();
+        let code = write_code(&storage, &cfg);
+        assert!(!code.contains("impl StringsSite {"));
+        assert!(!code.contains("fn interpolate("));
+
+        cfg.interpolation = true;
+        let code = write_code(&storage, &cfg);
+        assert!(code.contains("    pub greeting: &'static str,"));
+        assert!(code.contains(
+            "impl StringsSite {
+    pub fn greeting(&self, name: &str) -> String {
+        interpolate(self.greeting, &[(\"name\", name)])
+    }
+
+    pub fn points(&self, name: &str, count: &str) -> String {
+        interpolate(self.points, &[(\"name\", name), (\"count\", count)])
+    }
+
+}"
+        ));
+        assert!(!code.contains("pub fn name("));
+        assert!(!code.contains("pub fn ago("));
+        assert!(code.contains("    rendered.push_str(value);\n}"));
+
+        // arguments are isolated as their direction is unknown
+        cfg.bidi_isolation = true;
+        let code = write_code(&storage, &cfg);
+        assert!(code.contains(
+            "fn push_arg(rendered: &mut String, value: &str) {
+    rendered.push('\\u{2068}');
+    rendered.push_str(value);
+    rendered.push('\\u{2069}');
+}"
+        ));
+        cfg.bidi_isolation = false;
+
+        let storage = Storage::from_yaml_sources(&[
+            ("en", "site:\n  greeting: Welcome back, {name}\n"),
+            ("nl", "site:\n  greeting: Welkom terug, {naam}\n"),
+        ])
+        .unwrap();
+        let err = write_locales(Vec::new(), &storage, &cfg).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "placeholder '{name}' of 'site.greeting' is only found in the default locale but not in locale nl, please align nl.yml with the default locale!
+placeholder '{naam}' of 'site.greeting' is only found in locale nl, please align nl.yml with the default locale!"
+        );
+
+        let storage = Storage::from_yaml_sources(&[("en", "get: Get {item}\n")]).unwrap();
+        let err = write_locales(Vec::new(), &storage, &cfg).unwrap_err();
+        assert!(format!("{:#}", err).contains(
+            "interpolation method of string 'get' collides with the Strings::get method"
+        ));
+        let storage = Storage::from_yaml_sources(&[("en", "site:\n  kind: A {type}\n")]).unwrap();
+        let err = write_locales(Vec::new(), &storage, &cfg).unwrap_err();
+        assert!(format!("{:#}", err).contains(
+            "placeholder '{type}' of string 'site.kind' cannot be used as an argument name"
+        ));
+    }
+
+    #[test]
+    fn repr_c_is_emitted_on_all_strings_structs_when_enabled() {
+        let storage = fixture_storage();
+        let default_pairs: Vec<StringValuePathPair> =
+            storage.get_default().unwrap().iter().collect();
+
+        let cfg = I18n {
+            repr_c: true,
+            ..I18n::default()
+        };
+        let mut out = Vec::new();
+        generate_locales_strings_struct(
+            &mut out,
+            default_pairs.clone(),
+            &cfg,
+            "Strings",
+            usize::MAX,
+        )
+        .unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains("#[repr(C)]\npub struct Strings {\n"));
+        assert!(code.contains("#[repr(C)]\npub struct StringsSite {\n"));
+        assert_eq!(
+            code.matches("#[repr(C)]").count(),
+            code.matches("pub struct ").count()
+        );
+
+        let mut out = Vec::new();
+        generate_locales_strings_struct(
+            &mut out,
+            default_pairs,
+            &I18n::default(),
+            "Strings",
+            usize::MAX,
+        )
+        .unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(!code.contains("#[repr(C)]"));
+    }
+
+    #[test]
+    fn reload_mode_is_feature_gated_and_opt_in() {
+        let code = write_code(&fixture_storage(), &I18n::default());
+        assert!(!code.contains("reload"));
+
+        let cfg = I18n {
+            reload: true,
+            ..I18n::default()
+        };
+        let code = write_code(&fixture_storage(), &cfg);
+        assert!(code.contains(
+            "    pub fn strings(&self) -> &'static Strings {
+        match self {"
+        ));
+        assert!(!code.contains("Box::leak"));
+        assert!(code.contains("#[cfg(feature = \"reload\")]\nimpl Locale {"));
+        assert!(code.contains(
+            "    pub fn current(&self, path: &str) -> Option<std::borrow::Cow<'static, str>> {"
+        ));
+        assert!(code.contains(
+            "        Locale::En => &[],
+        Locale::Nl => &[Locale::En],
+        Locale::PtBr => &[Locale::En],"
+        ));
+        assert!(code.contains("#[cfg(feature = \"reload\")]\npub fn reload_from_json(json: &str)"));
+    }
+
+    #[test]
+    fn render_json_is_feature_gated_and_opt_in() {
+        let code = write_code(&fixture_storage(), &I18n::default());
+        assert!(!code.contains("render_json"));
+
+        let cfg = I18n {
+            render_json: true,
+            ..I18n::default()
+        };
+        let code = write_code(&fixture_storage(), &cfg);
+        assert!(code.contains(
+            "#[cfg(feature = \"serde_json\")]
+impl Strings {
+    /// render the string of the given (dotted) path, replacing its `{name}` placeholders"
+        ));
+        assert!(code.contains(
+            "    pub fn render_json(&self, key: &str, context: &serde_json::Value) -> anyhow::Result<String> {"
+        ));
+    }
+
+    #[test]
+    fn gendered_accessors_select_variants() {
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "greeting:\n  male: Welcome sir\n  neutral: Welcome\nsite:\n  name: News\n",
+            ),
+            (
+                "nl",
+                "greeting:\n  male: Welkom meneer\n  neutral: Welkom\nsite:\n  name: Nieuws\n",
+            ),
+            ("pt-br", "site:\n  name: Notícias\n"),
+        ])
+        .unwrap();
+        let mut out = Vec::new();
+        generate_locales_gendered_accessors(&mut out, &storage, &["greeting".to_owned()]).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains(
+            "    pub fn greeting(&self, gender: Gender) -> &'static str {
+        let strings = self.strings();
+        match gender {
+            Gender::Male => strings.greeting.male,
+            Gender::Female => strings.greeting.neutral,
+            Gender::Neutral => strings.greeting.neutral,
         }
+    }"
+        ));
+
+        // no accessors (nor gender) without gendered strings
+        let mut out = Vec::new();
+        generate_locales_gendered_accessors(&mut out, &storage, &[]).unwrap();
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn plural_and_gendered_accessors_may_not_collide() {
+        let storage = Storage::from_yaml_sources(&[(
+            "en",
+            "direction:\n  male: His way\n  neutral: The way\ncomment:\n  count:\n    one: comment\n    other: comments\ncomment_count:\n  male: His comments\n  neutral: Comments\n",
+        )])
+        .unwrap();
+        let mut cfg = I18n {
+            gendered: vec!["direction".to_owned()],
+            ..I18n::default()
+        };
+        assert_eq!(
+            check_locale_accessor_names(&storage, &cfg)
+                .unwrap_err()
+                .to_string(),
+            "accessor of string 'direction' collides with the Locale::direction method"
+        );
+
+        cfg.gendered = vec!["comment_count".to_owned()];
+        check_locale_accessor_names(&storage, &cfg).unwrap();
+        cfg.plurals = true;
+        assert_eq!(
+            check_locale_accessor_names(&storage, &cfg)
+                .unwrap_err()
+                .to_string(),
+            "accessor 'comment_count' of string 'comment.count' collides with the accessor of string 'comment_count'"
+        );
+        assert!(write_locales(Vec::new(), &storage, &cfg).is_err());
+    }
+
+    #[test]
+    fn inconsistent_gender_variants_are_rejected() {
+        let storage = Storage::from_yaml_sources(&[
+            ("en", "greeting:\n  male: Welcome sir\n  neutral: Welcome\n"),
+            (
+                "nl",
+                "greeting:\n  female: Welkom mevrouw\n  neutral: Welkom\n",
+            ),
+        ])
+        .unwrap();
+        let err =
+            generate_locales_gendered_accessors(Vec::new(), &storage, &["greeting".to_owned()])
+                .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "gendered string 'greeting' of locale nl defines variants [female, neutral] while the default locale defines [male, neutral]"
+        );
+
+        let storage =
+            Storage::from_yaml_sources(&[("en", "greeting:\n  male: Welcome sir\n")]).unwrap();
+        let err =
+            generate_locales_gendered_accessors(Vec::new(), &storage, &["greeting".to_owned()])
+                .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "gendered string 'greeting' of default locale en has no neutral variant"
+        );
+
+        let storage =
+            Storage::from_yaml_sources(&[("en", "greeting:\n  other: Hi\n  neutral: Hello\n")])
+                .unwrap();
+        let err =
+            generate_locales_gendered_accessors(Vec::new(), &storage, &["greeting".to_owned()])
+                .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("unknown variant 'other' of gendered string 'greeting' in locale en"));
+    }
+
+    #[test]
+    fn duplicate_values_are_reported_per_locale() {
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "actions:\n  ok: OK\n  confirm: OK\n  cancel: Cancel\nform:\n  submit: OK\n  close: Cancel\n  back: Back\n",
+            ),
+            (
+                "nl",
+                "actions:\n  cancel: Annuleren\n  ok: OK\nform:\n  close: Annuleren\n",
+            ),
+        ])
+        .unwrap();
+        let mut out = Vec::new();
+        let report = write_locales(&mut out, &storage, &I18n::default()).unwrap();
+        let report = duplicates_report_json(&report.duplicates[..]).unwrap();
+        assert!(report.ends_with("}\n"));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&report).unwrap(),
+            serde_json::json!({
+                "en": [
+                    {"value": "OK", "count": 3, "saved_bytes": 4, "keys": ["actions.confirm", "actions.ok", "form.submit"]},
+                    {"value": "Cancel", "count": 2, "saved_bytes": 6, "keys": ["actions.cancel", "form.close"]}
+                ],
+                "nl": [
+                    {"value": "Annuleren", "count": 2, "saved_bytes": 9, "keys": ["actions.cancel", "form.close"]}
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn coverage_is_reported_per_locale() {
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "site:\n  name: News\n  title: Welcome\nnav:\n  login: login\n",
+            ),
+            ("nl", "site:\n  name: Nieuws\n"),
+            (
+                "de",
+                "site:\n  name: Nachrichten\n  title: Willkommen\nnav:\n  login: anmelden\n",
+            ),
+        ])
+        .unwrap();
+        let report = write_locales(Vec::new(), &storage, &I18n::default()).unwrap();
+        let report =
+            coverage_report_json(&report.diagnostics[..], &report.missing_paths[..]).unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&report).unwrap(),
+            serde_json::json!({
+                "de": {"translated_keys": 3, "total_keys": 3, "missing": []},
+                "en": {"translated_keys": 3, "total_keys": 3, "missing": []},
+                "nl": {"translated_keys": 1, "total_keys": 3, "missing": ["nav.login", "site.title"]}
+            })
+        );
+    }
+
+    #[test]
+    fn a11y_labels_are_generated_per_locale() {
+        let storage = Storage::from_yaml_sources(&[
+            ("en", A11Y_SOURCE),
+            ("nl", "a11y:\n  skip_to_content: Ga naar inhoud\n"),
+        ])
+        .unwrap();
+        let mut cfg = I18n::default();
+        let code = write_code(&storage, &cfg);
+        assert!(code.contains(
+            "            Self::Nl => A11yLabels {
+                skip_to_content: \"Ga naar inhoud\",
+                main_nav: \"Main navigation\",
+"
+        ));
+
+        // the default locale is required to define all of them
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "a11y:\n  skip_to_content: Skip\n  main_nav: Main\n  user_nav: User\n",
+            ),
+            (
+                "nl",
+                "a11y:\n  skip_to_content: Naar inhoud\n  main_nav: Hoofdmenu\n  user_nav: Gebruiker\n  footer_nav: Voettekst\n",
+            ),
+        ])
+        .unwrap();
+        cfg.strict = true;
+        let err = write_locales(Vec::new(), &storage, &cfg).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "strict mode: 1 codegen warning(s) found:
+- default locale en is missing accessibility string 'a11y.footer_nav', using \"Footer navigation\" for all locales; please add it to en.yml!"
+        );
+        cfg.strict = false;
+        let code = write_code(&storage, &cfg);
+        assert!(code.contains("                footer_nav: \"Voettekst\",\n"));
+        assert!(code.contains("                footer_nav: \"Footer navigation\",\n"));
+    }
+
+    #[test]
+    fn strict_mode_fails_on_warnings() {
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                &format!(
+                    "site:\n  name: News\n  nav:\n    login: Log in\n{}",
+                    A11Y_SOURCE
+                ),
+            ),
+            (
+                "nl",
+                &format!(
+                    "site:\n  name: Nieuws\n  nav:\n    login: Log in als {{user}}\n{}",
+                    A11Y_SOURCE
+                ),
+            ),
+        ])
+        .unwrap();
+        let mut cfg = I18n {
+            check_placeholders: true,
+            ..I18n::default()
+        };
+        write_locales(Vec::new(), &storage, &cfg).unwrap();
+
+        cfg.strict = true;
+        let err = write_locales(Vec::new(), &storage, &cfg).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "strict mode: 1 codegen warning(s) found:
+- placeholder '{user}' of 'site.nav.login' is only found in locale nl, please align nl.yml with the default locale!"
+        );
+
+        let storage =
+            Storage::from_yaml_sources(&[("en", A11Y_SOURCE), ("nl", A11Y_SOURCE)]).unwrap();
+        write_locales(Vec::new(), &storage, &cfg).unwrap();
+    }
+
+    #[test]
+    fn relative_time_is_generated_for_relative_time_keys() {
+        let relative_time = "relative_time:
+  past: \"{} ago\"
+  future: \"in {}\"
+  seconds: { one: \"{} second\", other: \"{} seconds\" }
+  minutes: { one: \"{} minute\", other: \"{} minutes\" }
+  hours: { one: \"{} hour\", other: \"{} hours\" }
+";
+        let storage = Storage::from_yaml_sources(&[
+            ("en", relative_time),
+            ("fr", "site:\n  name: Actualités\n"),
+        ])
+        .unwrap();
+        let err = generate_locales_relative_time(Vec::new(), &storage).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "default locale en misses relative time keys: relative_time.days.other"
+        );
+
+        let relative_time = format!(
+            "{}  days: {{ one: \"{{}} day\", other: \"{{}} days\" }}\n",
+            relative_time
+        );
+        let storage = Storage::from_yaml_sources(&[
+            ("en", relative_time.as_str()),
+            ("fr", "site:\n  name: Actualités\n"),
+        ])
+        .unwrap();
+        let mut out = Vec::new();
+        generate_locales_relative_time(&mut out, &storage).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains("    pub fn relative_time(&self, seconds_ago: i64) -> String {"));
+        assert!(code.contains(
+            "        } else if seconds < 24 * 60 * 60 {
+            let count = seconds / (60 * 60);
+            let unit = match self.plural_category(count) {
+                PluralCategory::Zero => strings.hours.other,
+                PluralCategory::One => strings.hours.one,
+                PluralCategory::Two => strings.hours.other,
+                PluralCategory::Few => strings.hours.other,
+                PluralCategory::Many => strings.hours.other,
+                PluralCategory::Other => strings.hours.other,
+            };
+            (count, unit)
+        } else {"
+        ));
+        assert!(!code.contains("is_plural_one"));
+
+        // every form defined by the default locale is selected by its plural category
+        let mut ru_storage = Storage::from_yaml_sources(&[
+            ("en", relative_time.as_str()),
+            (
+                "ru",
+                "relative_time:\n  hours: { one: \"{} час\", few: \"{} часа\", many: \"{} часов\", other: \"{} часа\" }\n",
+            ),
+        ])
+        .unwrap();
+        ru_storage.add_plural_forms().unwrap();
+        let mut out = Vec::new();
+        generate_locales_relative_time(&mut out, &ru_storage).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains("                PluralCategory::Few => strings.hours.few,\n"));
+        assert!(code.contains("                PluralCategory::Many => strings.hours.many,\n"));
+
+        // the plural category is generated for relative time, without plural accessors
+        let code = write_code(&storage, &I18n::default());
+        assert!(code.contains("    pub fn plural_category(&self, n: u64) -> PluralCategory {"));
+        assert!(code.contains("            Self::Fr => {\n                if n <= 1 {"));
+        assert!(!code.contains("    pub fn relative_time_seconds(&self, n: u64)"));
+
+        // nothing is generated without relative time keys
+        let mut out = Vec::new();
+        generate_locales_relative_time(&mut out, &fixture_storage()).unwrap();
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn raw_strings_use_the_fewest_hashes_needed() {
+        assert_eq!(raw_string_literal("News"), "r\"News\"");
+        assert_eq!(raw_string_literal("say \"hi\""), "r#\"say \"hi\"\"#");
+        assert_eq!(
+            raw_string_literal("let s = r##\"a\"##; \"#"),
+            "r###\"let s = r##\"a\"##; \"#\"###"
+        );
+
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "title: News\nhelp:\n  quote: 'He said \"hi\"'\n  code: 'r#\"x\"# and \"###'\n",
+            ),
+            ("nl", "title: '\"Nieuws\"#'\n"),
+        ])
+        .unwrap();
+        let code = write_code(&storage, &I18n::default());
+        assert!(code.contains("        quote: r#\"He said \"hi\"\"#,\n"));
+        assert!(code.contains("        code: r####\"r#\"x\"# and \"###\"####,\n"));
+        assert!(code.contains("    title: r\"News\",\n"));
+        assert!(code.contains("    title: r##\"\"Nieuws\"#\"##,\n"));
     }
 }
@@ -0,0 +1,157 @@
+// Plabayo News
+// Copyright (C) 2021  Glen Henri J. De Cauwsemaecker
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{Context, Result};
+use convert_case::{Case, Casing};
+use serde::Serialize;
+
+use crate::i18n::config::Pages;
+use crate::i18n::locales::Storage;
+
+/// a symbol of a generated module, as listed in the symbols manifest
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Symbol {
+    module: &'static str,
+    name: String,
+    kind: &'static str,
+}
+
+impl Symbol {
+    fn new(module: &'static str, name: String, kind: &'static str) -> Symbol {
+        Symbol { module, name, kind }
+    }
+}
+
+/// the `Locale` variants and `STRINGS_*` constants of the generated locales module
+pub fn locale_symbols(storage: &Storage) -> Vec<Symbol> {
+    let mut symbols: Vec<Symbol> = storage
+        .all_locales()
+        .map(|locale| {
+            Symbol::new(
+                "locales",
+                format!("Locale::{}", locale.to_case(Case::Pascal)),
+                "variant",
+            )
+        })
+        .collect();
+    symbols.push(Symbol::new(
+        "locales",
+        "STRINGS_DEFAULT".to_owned(),
+        "const",
+    ));
+    for locale in storage
+        .all_locales()
+        .filter(|locale| locale != &storage.default_locale())
+    {
+        symbols.push(Symbol::new(
+            "locales",
+            format!("STRINGS_{}", locale.to_case(Case::ScreamingSnake)),
+            "const",
+        ));
+    }
+    symbols
+}
+
+/// the `StaticPage` variants, `PAGE_*` constants and page functions
+/// of the generated pages module for the given templates
+pub fn page_symbols(cfg: &Pages, templates: &[String]) -> Vec<Symbol> {
+    let mut templates = templates.to_vec();
+    templates.sort();
+    let (static_pages, dynamic_pages): (Vec<String>, Vec<String>) = templates
+        .into_iter()
+        .partition(|page| cfg.static_pages.contains(page));
+
+    let mut symbols = Vec::new();
+    for page in static_pages.iter().filter(|page| **page != cfg.not_found) {
+        symbols.push(Symbol::new(
+            "pages",
+            format!("StaticPage::{}", page.to_case(Case::Pascal)),
+            "variant",
+        ));
+    }
+    for page in static_pages.iter() {
+        if *page != cfg.not_found {
+            symbols.push(Symbol::new(
+                "pages",
+                format!("PAGE_{}_ENDPOINT", page.to_case(Case::ScreamingSnake)),
+                "const",
+            ));
+        }
+        symbols.push(Symbol::new(
+            "pages",
+            format!("Page{}::new", page.to_case(Case::Pascal)),
+            "fn",
+        ));
+    }
+    for page in dynamic_pages.iter() {
+        symbols.push(Symbol::new(
+            "pages",
+            format!("Page{}::new_response", page.to_case(Case::Pascal)),
+            "fn",
+        ));
+    }
+    symbols
+}
+
+/// machine-readable JSON manifest of the given symbols, one object per symbol
+pub fn manifest_json(symbols: &[Symbol]) -> Result<String> {
+    Ok(serde_json::to_string_pretty(symbols).context("serialize symbols manifest")? + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_lists_generated_symbols() {
+        let storage = Storage::from_yaml_sources(&[
+            ("en", "site:\n  name: Plabayo News\n"),
+            ("nl", "site:\n  name: Plabayo Nieuws\n"),
+            ("pt-br", "site:\n  name: Notícias Plabayo\n"),
+        ])
+        .unwrap();
+        let cfg = Pages {
+            not_found: "unknown".to_owned(),
+            static_pages: vec!["about".to_owned(), "unknown".to_owned()],
+            ..Pages::default()
+        };
+        let templates = ["unknown", "items", "about"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+
+        let mut symbols = locale_symbols(&storage);
+        symbols.extend(page_symbols(&cfg, &templates[..]));
+        let manifest = manifest_json(&symbols[..]).unwrap();
+        assert!(manifest.ends_with("]\n"));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&manifest).unwrap(),
+            serde_json::json!([
+                {"module": "locales", "name": "Locale::En", "kind": "variant"},
+                {"module": "locales", "name": "Locale::Nl", "kind": "variant"},
+                {"module": "locales", "name": "Locale::PtBr", "kind": "variant"},
+                {"module": "locales", "name": "STRINGS_DEFAULT", "kind": "const"},
+                {"module": "locales", "name": "STRINGS_NL", "kind": "const"},
+                {"module": "locales", "name": "STRINGS_PT_BR", "kind": "const"},
+                {"module": "pages", "name": "StaticPage::About", "kind": "variant"},
+                {"module": "pages", "name": "PAGE_ABOUT_ENDPOINT", "kind": "const"},
+                {"module": "pages", "name": "PageAbout::new", "kind": "fn"},
+                {"module": "pages", "name": "PageUnknown::new", "kind": "fn"},
+                {"module": "pages", "name": "PageItems::new_response", "kind": "fn"}
+            ])
+        );
+    }
+}
@@ -17,21 +17,47 @@
 use std::fs;
 use std::path::Path;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 
-use crate::i18n::config::Pages;
+use crate::i18n::config::I18n;
 use crate::i18n::locales::Storage;
 
+mod call_sites;
 mod common;
 mod locales;
+mod manifest;
 mod pages;
+mod transliteration;
+mod typescript;
 
-pub fn generate_all(dir: &str, storage: &Storage, pages_cfg: &Pages) -> Result<()> {
+pub fn generate_all(cfg: &I18n, storage: &Storage) -> Result<()> {
+    let dir = cfg.out.as_str();
     fs::create_dir_all(dir)?;
 
-    locales::generate_locales(&Path::new(dir).join("locales.rs"), storage)?;
+    locales::generate_locales(&Path::new(dir).join("locales.rs"), storage, cfg)?;
+
+    // the pages directory is a sub directory of the templates (e.g. next to the layouts)
+    if let Some(templates_path) = Path::new(&cfg.pages.path).parent() {
+        call_sites::verify_template_call_sites(
+            templates_path,
+            &call_sites::accessors(cfg, storage)[..],
+        )
+        .context("verify string accessor calls of templates")?;
+    }
 
     let pages_dir = Path::new(dir).join("pages");
     fs::create_dir_all(&pages_dir)?;
-    pages::generate_pages(&pages_dir.join("generated.rs"), pages_cfg)
+    let page_symbols = pages::generate_pages(&pages_dir.join("generated.rs"), &cfg.pages, storage)?;
+
+    if let Some(manifest_path) = cfg.symbols_manifest.as_deref() {
+        let mut symbols = manifest::locale_symbols(storage);
+        symbols.extend(page_symbols);
+        fs::write(manifest_path, manifest::manifest_json(&symbols[..])?)
+            .with_context(|| format!("write symbols manifest at {}", manifest_path))?;
+    }
+    if let Some(definitions_path) = cfg.typescript_definitions.as_deref() {
+        fs::write(definitions_path, typescript::strings_definitions(storage))
+            .with_context(|| format!("write typescript definitions at {}", definitions_path))?;
+    }
+    Ok(())
 }
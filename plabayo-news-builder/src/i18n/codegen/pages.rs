@@ -15,37 +15,50 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use std::fs::{self, File};
+use std::io::BufWriter;
 use std::path::Path;
 
 use anyhow::{anyhow, Context, Result};
 use convert_case::{Case, Casing};
 use itertools::Itertools;
 
-use crate::i18n::codegen::common::generate_copyright_file_header;
-use crate::i18n::config::Pages;
+use crate::i18n::codegen::common::{generate_copyright_file_header, json_string};
+use crate::i18n::codegen::manifest::{page_symbols, Symbol};
+use crate::i18n::config::{JsonLdValue, Pages};
+use crate::i18n::encoding::read_text_file;
+use crate::i18n::locales::Storage;
 
-pub fn generate_pages(file_path: &Path, cfg: &Pages) -> Result<()> {
+/// generate the pages module, returning the symbols it defines
+pub fn generate_pages(file_path: &Path, cfg: &Pages, storage: &Storage) -> Result<Vec<Symbol>> {
     println!("cargo:rerun-if-changed={}", cfg.path);
+    for svg_path in cfg.svg.values().flat_map(|svg_paths| svg_paths.values()) {
+        println!("cargo:rerun-if-changed={}", svg_path);
+    }
 
     let file = File::create(file_path)
         .with_context(|| format!("create locales rust file at {}", file_path.display()))?;
 
-    let (not_found_template, templates) = get_templates(&cfg.path, &cfg.not_found)
+    let (_, templates) = get_templates(&cfg.path, &cfg.not_found)
         .with_context(|| format!("get templates for result at {}", file_path.display()))?;
 
-    generate_copyright_file_header(&file).with_context(|| {
-        format!(
-            "generate locales module copyright (header) in {}",
-            file_path.display()
-        )
-    })?;
+    verify_template_files(cfg, &templates[..])?;
 
-    generate_pages_mod_docs(&file).with_context(|| {
-        format!(
-            "generate pages module docs (header) in {}",
-            file_path.display()
-        )
-    })?;
+    let symbols = page_symbols(cfg, &templates[..]);
+    write_pages(BufWriter::new(file), cfg, templates, storage)
+        .with_context(|| format!("generate pages in {}", file_path.display()))?;
+    Ok(symbols)
+}
+
+/// write the generated pages module for the given templates to the given writer
+pub fn write_pages(
+    mut w: impl std::io::Write,
+    cfg: &Pages,
+    templates: Vec<String>,
+    storage: &Storage,
+) -> Result<()> {
+    generate_copyright_file_header(&mut w).context("generate pages module copyright (header)")?;
+
+    generate_pages_mod_docs(&mut w).context("generate pages module docs (header)")?;
 
     let (mut static_pages, mut dynamic_pages) =
         templates
@@ -61,27 +74,98 @@ pub fn generate_pages(file_path: &Path, cfg: &Pages) -> Result<()> {
     static_pages.sort();
     dynamic_pages.sort();
 
-    generate_pages_imports(&file, &dynamic_pages[..])
-        .with_context(|| format!("generate pages imports in {}", file_path.display()))?;
+    generate_pages_imports(&mut w, &dynamic_pages[..]).context("generate pages imports")?;
 
-    generate_static_pages(
-        &file,
-        cfg.templates_dir.as_str(),
-        &static_pages[..],
-        not_found_template.as_str(),
-    )?;
+    generate_static_pages(&mut w, cfg, &static_pages[..], storage)?;
+
+    generate_locale_extractor(&mut w).context("generate locale extractor")?;
 
-    generate_dynamic_pages(&file, cfg.templates_dir.as_str(), &dynamic_pages[..])?;
+    generate_dynamic_pages(&mut w, cfg.templates_dir.as_str(), &dynamic_pages[..])?;
 
+    w.flush()?;
     Ok(())
 }
 
 fn generate_static_pages(
     mut w: impl std::io::Write,
-    templates_dir: &str,
+    cfg: &Pages,
     pages: &[String],
-    not_found: &str,
+    storage: &Storage,
 ) -> Result<()> {
+    let templates_dir = cfg.templates_dir.as_str();
+    let not_found = cfg.not_found.as_str();
+
+    for template in cfg.status.keys() {
+        if !pages.contains(template) {
+            return Err(anyhow!(
+                "status configured for unknown static page template '{}'",
+                template
+            ));
+        }
+    }
+    for page in pages.iter().filter(|page| *page != not_found) {
+        let endpoint = page.to_case(Case::Snake);
+        if endpoint.eq_ignore_ascii_case(cfg.assets_root.trim_matches('/')) {
+            return Err(anyhow!(
+                "endpoint '{}' of static page template '{}' collides with the assets root '{}', please rename the template",
+                endpoint,
+                page,
+                cfg.assets_root
+            ));
+        }
+    }
+    for (template, kind) in cfg.kinds.iter() {
+        if !pages.contains(template) || template == not_found {
+            return Err(anyhow!(
+                "kind configured for unknown static page template '{}'",
+                template
+            ));
+        }
+        if !kind
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            || !kind.starts_with(|c: char| c.is_ascii_alphabetic())
+        {
+            return Err(anyhow!(
+                "invalid kind '{}' for static page template '{}'",
+                kind,
+                template
+            ));
+        }
+    }
+    for template in cfg.svg.keys() {
+        if !pages.contains(template) {
+            return Err(anyhow!(
+                "svg configured for unknown static page template '{}'",
+                template
+            ));
+        }
+    }
+    for template in cfg.jsonld.keys() {
+        if !pages.contains(template) {
+            return Err(anyhow!(
+                "JSON-LD configured for unknown static page template '{}'",
+                template
+            ));
+        }
+    }
+    for (template, content_type) in cfg.content_types.iter() {
+        if !pages.contains(template) {
+            return Err(anyhow!(
+                "content type configured for unknown static page template '{}'",
+                template
+            ));
+        }
+        if !is_valid_mime_type(content_type) {
+            return Err(anyhow!(
+                "invalid content type '{}' for static page template '{}', expected type/subtype with optional parameters",
+                content_type,
+                template
+            ));
+        }
+    }
+    validate_required_keys(cfg, pages, storage)?;
+
     w.write_all(
         b"//-------------------------------------
 //------- STATIC PAGES
@@ -90,9 +174,348 @@ fn generate_static_pages(
 ",
     )?;
 
+    // 1. generate the static page enum, the only type used to refer to static pages,
+    //    with &str endpoints only accepted at the HTTP boundary
+
+    w.write_all(
+        b"#[derive(Debug, PartialEq, Eq, Clone, Copy, std::hash::Hash)]
+pub enum StaticPage {
+",
+    )?;
+    for page in pages.iter().filter(|page| *page != not_found) {
+        w.write_all(
+            format!(
+                "    {},
+",
+                page.to_case(Case::Pascal)
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"}
+
+impl StaticPage {
+    pub fn from_endpoint(endpoint: &str) -> Option<StaticPage> {
+        match endpoint {
+",
+    )?;
+    for page in pages.iter().filter(|page| *page != not_found) {
+        w.write_all(
+            format!(
+                "            PAGE_{}_ENDPOINT => Some(Self::{}),
+",
+                page.to_case(Case::ScreamingSnake),
+                page.to_case(Case::Pascal)
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"            _ => None,
+        }
+    }
+
+    pub fn endpoint(&self) -> &'static str {
+        match self {
+",
+    )?;
+    for page in pages.iter().filter(|page| *page != not_found) {
+        w.write_all(
+            format!(
+                "            Self::{} => PAGE_{}_ENDPOINT,
+",
+                page.to_case(Case::Pascal),
+                page.to_case(Case::ScreamingSnake)
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"        }
+    }
+
+    pub fn path(&self) -> String {
+        format!(\"/{}\", self.endpoint())
+    }
+
+    pub fn kind(&self) -> PageKind {
+        match self {
+",
+    )?;
+    for page in pages.iter().filter(|page| *page != not_found) {
+        w.write_all(
+            format!(
+                "            Self::{} => PageKind::{},
+",
+                page.to_case(Case::Pascal),
+                cfg.kinds
+                    .get(page)
+                    .map(|kind| kind.to_case(Case::Pascal))
+                    .unwrap_or_else(|| "Generic".to_owned())
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"        }
+    }
+}
+
+",
+    )?;
+
+    // 2. generate the page kind enum, such that middleware can treat kinds of pages differently
+
+    let kinds: Vec<String> = std::iter::once("Generic".to_owned())
+        .chain(
+            cfg.kinds
+                .values()
+                .map(|kind| kind.to_case(Case::Pascal))
+                .filter(|kind| kind != "Generic")
+                .sorted()
+                .dedup(),
+        )
+        .collect();
+    w.write_all(
+        format!(
+            "/// root path of the static assets, which no page endpoint equals
+pub const ASSETS_ROOT: &str = {:?};
+
+",
+            cfg.assets_root.trim_matches('/')
+        )
+        .as_bytes(),
+    )?;
+    w.write_all(
+        b"#[derive(Debug, PartialEq, Eq, Clone, Copy, std::hash::Hash)]
+pub enum PageKind {
+",
+    )?;
+    for kind in kinds {
+        w.write_all(
+            format!(
+                "    {},
+",
+                kind
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"}
+
+pub fn endpoint_kind(endpoint: &str) -> PageKind {
+    StaticPage::from_endpoint(endpoint).map_or(PageKind::Generic, |page| page.kind())
+}
+
+",
+    )?;
+
+    // 3. generate the localized endpoints of the static pages, and their inverse
+
+    let localized_slugs = static_page_localized_slugs(cfg, pages, storage)?;
+    let localized_arms: Vec<String> = localized_slugs
+        .iter()
+        .flat_map(|(locale, slugs)| {
+            slugs
+                .iter()
+                .filter(|(page, slug)| page.to_case(Case::Snake) != *slug)
+                .map(move |(page, slug)| {
+                    format!(
+                        "            (Self::{}, Locale::{}) => {:?},
+",
+                        page.to_case(Case::Pascal),
+                        locale.to_case(Case::Pascal),
+                        slug
+                    )
+                })
+        })
+        .collect();
     w.write_all(
-        b"pub fn static_response(endpoint: &str, page: PageState) -> Result<HttpResponse> {
-    let (mut response, render_result) = match endpoint {
+        b"impl StaticPage {
+    /// endpoint of the page localized for the given locale,
+    /// the canonical endpoint in case no slug is configured for the locale
+",
+    )?;
+    if localized_arms.is_empty() {
+        w.write_all(
+            b"    pub fn localized_endpoint(&self, _locale: Locale) -> &'static str {
+        self.endpoint()
+    }
+}
+
+",
+        )?;
+    } else {
+        w.write_all(
+            b"    pub fn localized_endpoint(&self, locale: Locale) -> &'static str {
+        match (self, locale) {
+",
+        )?;
+        for arm in localized_arms {
+            w.write_all(arm.as_bytes())?;
+        }
+        w.write_all(
+            b"            _ => self.endpoint(),
+        }
+    }
+}
+
+",
+        )?;
+    }
+    w.write_all(
+        b"/// canonical endpoint of the static page with the given localized endpoint (slug) for the given locale,
+/// the inverse of `StaticPage::localized_endpoint`, `None` if the slug is not recognized
+pub fn resolve_localized_endpoint(slug: &str, locale: Locale) -> Option<&'static str> {
+    match (locale, slug) {
+",
+    )?;
+    for (locale, slugs) in localized_slugs.iter() {
+        for (page, slug) in slugs {
+            w.write_all(
+                format!(
+                    "        (Locale::{}, {:?}) => Some(PAGE_{}_ENDPOINT),
+",
+                    locale.to_case(Case::Pascal),
+                    slug,
+                    page.to_case(Case::ScreamingSnake)
+                )
+                .as_bytes(),
+            )?;
+        }
+    }
+    w.write_all(
+        b"        _ => None,
+    }
+}
+
+",
+    )?;
+    for (locale, slugs) in localized_slugs.iter() {
+        for (page, slug) in slugs {
+            w.write_all(
+                format!(
+                    "pub const PAGE_{}_{}_PATH: &str = \"/{}\";
+",
+                    page.to_case(Case::ScreamingSnake),
+                    locale.to_case(Case::ScreamingSnake),
+                    slug
+                )
+                .as_bytes(),
+            )?;
+        }
+    }
+    w.write_all(
+        b"
+impl StaticPage {
+    /// path of the page localized for the given locale (e.g. `/over-ons`),
+    /// resolved back to the page and locale by `resolve_localized_path` if localized
+    pub fn localized_path(&self, locale: Locale) -> &'static str {
+",
+    )?;
+    if localized_slugs.iter().all(|(_, slugs)| slugs.is_empty()) {
+        w.write_all(
+            b"        match *self {}
+",
+        )?;
+    } else {
+        w.write_all(
+            b"        match (self, locale) {
+",
+        )?;
+        for (locale, slugs) in localized_slugs.iter() {
+            for (page, _) in slugs {
+                w.write_all(
+                    format!(
+                        "            (Self::{}, Locale::{}) => PAGE_{}_{}_PATH,
+",
+                        page.to_case(Case::Pascal),
+                        locale.to_case(Case::Pascal),
+                        page.to_case(Case::ScreamingSnake),
+                        locale.to_case(Case::ScreamingSnake),
+                    )
+                    .as_bytes(),
+                )?;
+            }
+        }
+        w.write_all(
+            b"        }
+",
+        )?;
+    }
+    w.write_all(
+        b"    }
+}
+",
+    )?;
+    let mut resolved_slugs: Vec<&str> = Vec::new();
+    let mut path_arms = Vec::new();
+    for (locale, slugs) in localized_slugs.iter() {
+        for (page, slug) in slugs {
+            if page.to_case(Case::Snake) == *slug || resolved_slugs.contains(&slug.as_str()) {
+                continue;
+            }
+            resolved_slugs.push(slug);
+            path_arms.push(format!(
+                "        {:?} => Some((StaticPage::{}, Locale::{})),
+",
+                slug,
+                page.to_case(Case::Pascal),
+                locale.to_case(Case::Pascal)
+            ));
+        }
+    }
+    w.write_all(
+        b"
+/// static page and locale of the given localized path (e.g. `/over-ons`), independent of
+/// the locale of the request, `None` for canonical endpoints as their locale is up to negotiation,
+/// a slug shared by multiple locales resolves to the first of those locales
+",
+    )?;
+    if path_arms.is_empty() {
+        w.write_all(
+            b"pub fn resolve_localized_path(_path: &str) -> Option<(StaticPage, Locale)> {
+    None
+}
+
+",
+        )?;
+    } else {
+        w.write_all(
+            b"pub fn resolve_localized_path(path: &str) -> Option<(StaticPage, Locale)> {
+    match path.trim_matches('/') {
+",
+        )?;
+        for arm in path_arms {
+            w.write_all(arm.as_bytes())?;
+        }
+        w.write_all(
+            b"        _ => None,
+    }
+}
+
+",
+        )?;
+    }
+
+    // 4. generate the (actix responder) response of all static pages,
+    //    resolving to the not found page in case no static page is given
+
+    w.write_all(
+        b"/// a rendered static page, which can be returned as is by (actix) handlers
+pub struct StaticPageResponse {
+    pub status: StatusCode,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl StaticPageResponse {
+    /// render the given static page, or the not found page in case no static page is given
+    pub fn render(page: Option<StaticPage>, state: PageState) -> Result<StaticPageResponse> {
+        let locale = state.locale;
+        let (status, content_type, render_result) = match page {
 ",
     )?;
     for page in pages {
@@ -101,32 +524,122 @@ fn generate_static_pages(
         }
         w.write_all(
             format!(
-                "        PAGE_{}_ENDPOINT => (HttpResponse::Ok(), Page{}::new(page).render()),
+                "            Some(StaticPage::{page}) => (
+                StatusCode::{status},
+                {content_type:?},
+                Page{page}::new(state).render(),
+            ),
 ",
-                page.to_case(Case::ScreamingSnake),
-                page.to_case(Case::Pascal)
+                page = page.to_case(Case::Pascal),
+                status = static_page_status(cfg, page)?,
+                content_type = static_page_content_type(cfg, page),
             )
             .as_bytes(),
         )?;
     }
     w.write_all(
         format!(
-            "        _ => (HttpResponse::NotFound(), Page{}::new(page).render()),
+            "            None => (
+                StatusCode::{status},
+                {content_type:?},
+                Page{page}::new(state).render(),
+            ),
 ",
-            not_found.to_case(Case::Pascal)
+            page = not_found.to_case(Case::Pascal),
+            status = static_page_status(cfg, not_found)?,
+            content_type = static_page_content_type(cfg, not_found),
         )
         .as_bytes(),
     )?;
 
     w.write_all(
-        b"    };
-    let s = render_result.map_err(ErrorInternalServerError)?;
-    Ok(response.content_type(\"text/html\").body(s))
+        format!(
+            "        }};
+        // name the page and locale, as a broken template only fails once rendered
+        let body = render_result.map_err(|err| {{
+            ErrorInternalServerError(format!(
+                \"render static page '{{}}' for locale {{}}: {{}}\",
+                page.map_or({not_found:?}, |page| page.endpoint()),
+                locale,
+                err
+            ))
+        }})?;
+        Ok(StaticPageResponse {{
+            status,
+            content_type,
+            body,
+        }})
+    }}
+}}
+
+",
+            not_found = not_found,
+        )
+        .as_bytes(),
+    )?;
+    w.write_all(
+        b"impl From<StaticPageResponse> for HttpResponse {
+    fn from(page: StaticPageResponse) -> HttpResponse {
+        HttpResponse::build(page.status)
+            .content_type(page.content_type)
+            .body(page.body)
+    }
 }
 
+impl Responder for StaticPageResponse {
+    type Error = Error;
+    type Future = Ready<Result<HttpResponse, Error>>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        ready(Ok(self.into()))
+    }
+}
+
+pub fn static_response(page: Option<StaticPage>, state: PageState) -> Result<HttpResponse> {
+    StaticPageResponse::render(page, state).map(HttpResponse::from)
+}
+
+",
+    )?;
+
+    // 5. generate the HEAD response function for all static pages,
+    //    answering with the status and content type of the page without rendering it
+
+    w.write_all(
+        b"/// response to a HEAD request of a static page, with the status and content type
+/// of its GET response, but without rendering the page (body), and thus without
+/// a content length, as that is only known once the page is rendered
+pub fn static_response_head(page: Option<StaticPage>) -> HttpResponse {
+    let (mut response, content_type) = match page {
 ",
     )?;
+    for page in pages.iter().filter(|page| *page != not_found) {
+        w.write_all(
+            format!(
+                "        Some(StaticPage::{page}) => (HttpResponse::build(StatusCode::{status}), {content_type:?}),
+",
+                page = page.to_case(Case::Pascal),
+                status = static_page_status(cfg, page)?,
+                content_type = static_page_content_type(cfg, page),
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        format!(
+            "        None => (HttpResponse::build(StatusCode::{status}), {content_type:?}),
+    }};
+    response.content_type(content_type).body(Body::None)
+}}
 
+",
+            status = static_page_status(cfg, not_found)?,
+            content_type = static_page_content_type(cfg, not_found),
+        )
+        .as_bytes(),
+    )?;
+
+    let mut uses_site_info = false;
     for page in pages {
         if page != not_found {
             w.write_all(
@@ -140,6 +653,27 @@ fn generate_static_pages(
                 .as_bytes(),
             )?;
         }
+        // localized fields, as (name, type, (locale, expression) per locale)
+        let mut fields = vec![];
+        let localized_svgs = static_page_localized_svgs(cfg, page, storage)?;
+        if !localized_svgs.is_empty() {
+            fields.push((
+                "localized_svg",
+                "&'static str",
+                localized_svgs
+                    .into_iter()
+                    .map(|(locale, svg)| (locale, format!("{:?}", svg)))
+                    .collect::<Vec<_>>(),
+            ));
+        }
+        let jsonld = static_page_jsonld(cfg, page, storage)?;
+        if !jsonld.is_empty() {
+            uses_site_info |= jsonld
+                .iter()
+                .any(|(_, expr)| expr.contains("json_ld_string("));
+            fields.push(("jsonld", "String", jsonld));
+        }
+
         w.write_all(
             format!(
                 "#[derive(Template)]
@@ -147,26 +681,522 @@ fn generate_static_pages(
 struct Page{page}<'a> {{
     site_info: &'a SiteInfo,
     page: PageState,
-}}
+    a11y: A11yLabels,
+",
+                dir = templates_dir,
+                page_orig = &page,
+                page = page.to_case(Case::Pascal)
+            )
+            .as_bytes(),
+        )?;
+        for (name, ty, _) in fields.iter() {
+            w.write_all(
+                format!(
+                    "    {}: {},
+",
+                    name, ty
+                )
+                .as_bytes(),
+            )?;
+        }
+        w.write_all(
+            format!(
+                "}}
 
 impl<'a> Page{page}<'a> {{
     pub fn new(page: PageState) -> Page{page}<'a> {{
-        Page{page} {{
+",
+                page = page.to_case(Case::Pascal)
+            )
+            .as_bytes(),
+        )?;
+        for (name, _, values) in fields.iter() {
+            w.write_all(
+                format!(
+                    "        let {} = match page.locale {{
+",
+                    name
+                )
+                .as_bytes(),
+            )?;
+            for (locale, expr) in values {
+                w.write_all(
+                    format!(
+                        "            crate::site::l18n::locales::Locale::{} => {},
+",
+                        locale.to_case(Case::Pascal),
+                        expr
+                    )
+                    .as_bytes(),
+                )?;
+            }
+            w.write_all(
+                b"        };
+",
+            )?;
+        }
+        w.write_all(
+            format!(
+                "        Page{page} {{
             site_info: &SITE_INFO,
+            a11y: page.locale.a11y(),
             page,
-        }}
-    }}
-}}
-
 ",
-                dir = templates_dir,
-                page_orig = &page,
                 page = page.to_case(Case::Pascal)
             )
             .as_bytes(),
         )?;
+        for (name, _, _) in fields.iter() {
+            w.write_all(
+                format!(
+                    "            {},
+",
+                    name
+                )
+                .as_bytes(),
+            )?;
+        }
+        w.write_all(
+            b"        }
+    }
+}
+
+",
+        )?;
+    }
+
+    if uses_site_info {
+        w.write_all(
+            b"/// encode the given value as a JSON string, safe to embed in a script element
+fn json_ld_string(value: &dyn std::fmt::Display) -> String {
+    let value = value.to_string();
+    let mut s = String::with_capacity(value.len() + 2);
+    s.push('\"');
+    for c in value.chars() {
+        match c {
+            '\"' => s.push_str(\"\\\\\\\"\"),
+            '\\\\' => s.push_str(\"\\\\\\\\\"),
+            '<' => s.push_str(\"\\\\u003c\"),
+            c if (c as u32) < 0x20 => s.push_str(&format!(\"\\\\u{:04x}\", c as u32)),
+            c => s.push(c),
+        }
+    }
+    s.push('\"');
+    s
+}
+
+",
+        )?;
+    }
+
+    Ok(())
+}
+
+/// ensure the default locale defines all keys required by the static page templates
+fn validate_required_keys(cfg: &Pages, pages: &[String], storage: &Storage) -> Result<()> {
+    let default_paths: Vec<String> = storage
+        .get_default()
+        .map(|locales| locales.iter().map(|pair| pair.path.join(".")).collect())
+        .unwrap_or_default();
+    for (template, keys) in cfg.required_keys.iter().sorted_by_key(|(t, _)| *t) {
+        if !pages.contains(template) {
+            return Err(anyhow!(
+                "required keys configured for unknown static page template '{}'",
+                template
+            ));
+        }
+        let missing: Vec<&str> = keys
+            .iter()
+            .map(|key| key.as_str())
+            .filter(|key| {
+                !default_paths.iter().any(|path| {
+                    path == key || (path.starts_with(key) && path[key.len()..].starts_with('.'))
+                })
+            })
+            .collect();
+        if !missing.is_empty() {
+            return Err(anyhow!(
+                "static page template '{}' misses required keys in default locale '{}': {}",
+                template,
+                storage.default_locale(),
+                missing.join(", ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// (static page template, endpoint slug) pairs of a locale
+type LocaleSlugs<'a> = (&'a str, Vec<(&'a str, String)>);
+
+/// the endpoint slug of every (non not found) static page per locale, sorted by locale,
+/// ensuring that the slugs of a locale are valid and unique
+fn static_page_localized_slugs<'a>(
+    cfg: &Pages,
+    pages: &'a [String],
+    storage: &'a Storage,
+) -> Result<Vec<LocaleSlugs<'a>>> {
+    let locales: Vec<&str> = storage.all_locales().collect();
+    for (template, slugs) in cfg.slugs.iter() {
+        if !pages.contains(template) || template == &cfg.not_found {
+            return Err(anyhow!(
+                "slugs configured for unknown static page template '{}'",
+                template
+            ));
+        }
+        for (locale, slug) in slugs {
+            if !locales.contains(&locale.as_str()) {
+                return Err(anyhow!(
+                    "slug configured for unknown locale '{}' of static page template '{}'",
+                    locale,
+                    template
+                ));
+            }
+            if slug.is_empty()
+                || !slug
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+                || slug.eq_ignore_ascii_case(cfg.assets_root.trim_matches('/'))
+            {
+                return Err(anyhow!(
+                    "invalid slug '{}' for locale '{}' of static page template '{}'",
+                    slug,
+                    locale,
+                    template
+                ));
+            }
+        }
+    }
+    let mut localized_slugs = Vec::with_capacity(locales.len());
+    for locale in locales {
+        let mut slugs: Vec<(&str, String)> = vec![];
+        for page in pages.iter().filter(|page| **page != cfg.not_found) {
+            let slug = cfg
+                .slugs
+                .get(page)
+                .and_then(|slugs| slugs.get(locale))
+                .cloned()
+                .unwrap_or_else(|| page.to_case(Case::Snake));
+            if let Some((other, _)) = slugs.iter().find(|(_, other_slug)| *other_slug == slug) {
+                return Err(anyhow!(
+                    "slug '{}' of locale '{}' is used by both static page templates '{}' and '{}'",
+                    slug,
+                    locale,
+                    other,
+                    page
+                ));
+            }
+            slugs.push((page.as_str(), slug));
+        }
+        localized_slugs.push((locale, slugs));
+    }
+    Ok(localized_slugs)
+}
+
+/// read the svg of every locale for the given static page,
+/// empty in case no svg is configured for that page
+fn static_page_localized_svgs<'a>(
+    cfg: &Pages,
+    page: &str,
+    storage: &'a Storage,
+) -> Result<Vec<(&'a str, String)>> {
+    let svg_paths = match cfg.svg.get(page) {
+        Some(svg_paths) => svg_paths,
+        None => return Ok(vec![]),
+    };
+    let locales: Vec<&str> = storage.all_locales().collect();
+    for locale in svg_paths.keys() {
+        if !locales.contains(&locale.as_str()) {
+            return Err(anyhow!(
+                "svg configured for unknown locale '{}' of static page template '{}'",
+                locale,
+                page
+            ));
+        }
+    }
+    let default_locale = storage.default_locale();
+    let mut svgs = Vec::with_capacity(locales.len());
+    for locale in locales {
+        let svg_path = svg_paths
+            .get(locale)
+            .or_else(|| svg_paths.get(default_locale))
+            .ok_or_else(|| {
+                anyhow!(
+                    "no svg configured for locale '{}' nor default locale '{}' of static page template '{}'",
+                    locale,
+                    default_locale,
+                    page
+                )
+            })?;
+        let svg = read_text_file(Path::new(svg_path)).with_context(|| {
+            format!(
+                "read svg {} for locale '{}' of static page template '{}'",
+                svg_path, locale, page
+            )
+        })?;
+        svgs.push((locale, svg.trim().to_owned()));
+    }
+    Ok(svgs)
+}
+
+/// render the JSON-LD of every locale for the given static page as a rust `String` expression,
+/// empty in case no JSON-LD is configured for that page
+fn static_page_jsonld<'a>(
+    cfg: &Pages,
+    page: &str,
+    storage: &'a Storage,
+) -> Result<Vec<(&'a str, String)>> {
+    let properties = match cfg.jsonld.get(page) {
+        Some(properties) => properties,
+        None => return Ok(vec![]),
+    };
+    let mut exprs = vec![];
+    for locale in storage.all_locales() {
+        let mut parts = vec![];
+        let mut json = String::from("{");
+        if !properties.contains_key("@context") {
+            json.push_str("\"@context\":\"https://schema.org\"");
+        }
+        write_jsonld_properties(&mut parts, &mut json, properties, storage, locale).with_context(
+            || {
+                format!(
+                    "render JSON-LD for locale '{}' of static page template '{}'",
+                    locale, page
+                )
+            },
+        )?;
+        json.push('}');
+        let expr = if parts.is_empty() {
+            format!("{:?}.to_owned()", json)
+        } else {
+            parts.push(format!("{:?}", json));
+            format!("[{}].concat()", parts.join(", "))
+        };
+        exprs.push((locale, expr));
     }
+    Ok(exprs)
+}
 
+/// write the given JSON-LD properties (without braces) to the given json,
+/// which is moved to the expression parts for every value resolved at runtime
+fn write_jsonld_properties(
+    parts: &mut Vec<String>,
+    json: &mut String,
+    properties: &std::collections::BTreeMap<String, JsonLdValue>,
+    storage: &Storage,
+    locale: &str,
+) -> Result<()> {
+    for (property, value) in properties {
+        if !json.ends_with('{') {
+            json.push(',');
+        }
+        json.push_str(&json_string(property));
+        json.push(':');
+        match value {
+            JsonLdValue::Text(text) => json.push_str(&json_string(text)),
+            JsonLdValue::Key(key) => {
+                let value = storage
+                    .value_or_default(locale, &key.key)
+                    .ok_or_else(|| anyhow!("unknown translation key '{}'", key.key))?;
+                json.push_str(&json_string(&value));
+            }
+            JsonLdValue::SiteInfo(field) => {
+                if field.site_info.is_empty()
+                    || !field
+                        .site_info
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+                {
+                    return Err(anyhow!("invalid site info field '{}'", field.site_info));
+                }
+                parts.push(format!("{:?}", json));
+                json.clear();
+                parts.push(format!("&json_ld_string(&SITE_INFO.{})", field.site_info));
+            }
+            JsonLdValue::Object(object) => {
+                json.push('{');
+                write_jsonld_properties(parts, json, object, storage, locale)?;
+                json.push('}');
+            }
+        }
+    }
+    Ok(())
+}
+
+fn static_page_content_type<'a>(cfg: &'a Pages, page: &str) -> &'a str {
+    cfg.content_types
+        .get(page)
+        .map(|content_type| content_type.trim())
+        .unwrap_or("text/html")
+}
+
+/// basic MIME syntax check: `type/subtype` followed by optional `; name=value` parameters,
+/// with tokens as defined by RFC 7230 and values being a token or quoted string
+fn is_valid_mime_type(value: &str) -> bool {
+    fn is_token(s: &str) -> bool {
+        !s.is_empty()
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+    }
+    fn is_quoted_string(s: &str) -> bool {
+        s.len() >= 2
+            && s.starts_with('"')
+            && s.ends_with('"')
+            && !s[1..s.len() - 1].contains(|c: char| c == '"' || c.is_ascii_control())
+    }
+
+    let mut parts = value.split(';');
+    let media_type = parts.next().unwrap_or("").trim();
+    let valid_media_type = match media_type.split_once('/') {
+        Some((type_, subtype)) => is_token(type_) && is_token(subtype),
+        None => false,
+    };
+    valid_media_type
+        && parts.all(|param| match param.trim().split_once('=') {
+            Some((name, value)) => is_token(name) && (is_token(value) || is_quoted_string(value)),
+            None => false,
+        })
+}
+
+fn static_page_status<'a>(cfg: &'a Pages, page: &str) -> Result<&'a str> {
+    let status = match cfg.status.get(page) {
+        Some(status) => status.as_str(),
+        None if page == cfg.not_found => "NOT_FOUND",
+        None => "OK",
+    };
+    if !STATUS_CODE_NAMES.contains(&status) {
+        return Err(anyhow!(
+            "invalid status '{}' for static page template '{}', expected one of: {}",
+            status,
+            page,
+            STATUS_CODE_NAMES.join(", ")
+        ));
+    }
+    Ok(status)
+}
+
+/// all associated status code constants known by `actix_web::http::StatusCode`
+const STATUS_CODE_NAMES: &[&str] = &[
+    "CONTINUE",
+    "SWITCHING_PROTOCOLS",
+    "PROCESSING",
+    "OK",
+    "CREATED",
+    "ACCEPTED",
+    "NON_AUTHORITATIVE_INFORMATION",
+    "NO_CONTENT",
+    "RESET_CONTENT",
+    "PARTIAL_CONTENT",
+    "MULTI_STATUS",
+    "ALREADY_REPORTED",
+    "IM_USED",
+    "MULTIPLE_CHOICES",
+    "MOVED_PERMANENTLY",
+    "FOUND",
+    "SEE_OTHER",
+    "NOT_MODIFIED",
+    "USE_PROXY",
+    "TEMPORARY_REDIRECT",
+    "PERMANENT_REDIRECT",
+    "BAD_REQUEST",
+    "UNAUTHORIZED",
+    "PAYMENT_REQUIRED",
+    "FORBIDDEN",
+    "NOT_FOUND",
+    "METHOD_NOT_ALLOWED",
+    "NOT_ACCEPTABLE",
+    "PROXY_AUTHENTICATION_REQUIRED",
+    "REQUEST_TIMEOUT",
+    "CONFLICT",
+    "GONE",
+    "LENGTH_REQUIRED",
+    "PRECONDITION_FAILED",
+    "PAYLOAD_TOO_LARGE",
+    "URI_TOO_LONG",
+    "UNSUPPORTED_MEDIA_TYPE",
+    "RANGE_NOT_SATISFIABLE",
+    "EXPECTATION_FAILED",
+    "IM_A_TEAPOT",
+    "MISDIRECTED_REQUEST",
+    "UNPROCESSABLE_ENTITY",
+    "LOCKED",
+    "FAILED_DEPENDENCY",
+    "UPGRADE_REQUIRED",
+    "PRECONDITION_REQUIRED",
+    "TOO_MANY_REQUESTS",
+    "REQUEST_HEADER_FIELDS_TOO_LARGE",
+    "UNAVAILABLE_FOR_LEGAL_REASONS",
+    "INTERNAL_SERVER_ERROR",
+    "NOT_IMPLEMENTED",
+    "BAD_GATEWAY",
+    "SERVICE_UNAVAILABLE",
+    "GATEWAY_TIMEOUT",
+    "HTTP_VERSION_NOT_SUPPORTED",
+    "VARIANT_ALSO_NEGOTIATES",
+    "INSUFFICIENT_STORAGE",
+    "LOOP_DETECTED",
+    "NOT_EXTENDED",
+    "NETWORK_AUTHENTICATION_REQUIRED",
+];
+
+fn generate_locale_extractor(mut w: impl std::io::Write) -> Result<()> {
+    w.write_all(
+        b"//-------------------------------------
+//------- LOCALE
+//-------------------------------------
+
+/// negotiate the locale of the request, using (in order of precedence)
+/// the locale query parameter, the locale cookie (same name) and the Accept-Language header
+pub fn negotiate_from_request(req: &HttpRequest) -> Locale {
+    negotiate_with_cookie_persistence(req).0
+}
+
+/// negotiate the locale of the request (see `negotiate_from_request`), as well as
+/// whether or not to persist it in the locale cookie, which is only the case for a locale
+/// derived from the Accept-Language header, as an explicit choice (query or cookie) is kept as is
+pub fn negotiate_with_cookie_persistence(req: &HttpRequest) -> (Locale, bool) {
+    negotiate_with_preference(req, None)
+}
+
+/// negotiate the locale of the request (see `negotiate_with_cookie_persistence`),
+/// where a preferred locale (e.g. the one of the signed in user) takes precedence
+/// over the locale cookie and Accept-Language header, but not over the locale query parameter
+pub fn negotiate_with_preference(req: &HttpRequest, preferred: Option<Locale>) -> (Locale, bool) {
+    let explicit = Locale::parse_query(req.query_string(), LOCALE_QUERY_KEY)
+        .or(preferred)
+        .or_else(|| {
+            req.cookie(LOCALE_QUERY_KEY)
+                .and_then(|cookie| Locale::try_from(cookie.value()).ok())
+        });
+    if let Some(locale) = explicit {
+        return (locale, false);
+    }
+    match req
+        .headers()
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|hv| hv.to_str().ok())
+    {
+        Some(header) => (Locale::from_accept_language(header), true),
+        None => (Locale::default(), false),
+    }
+}
+
+/// extractor of the locale negotiated for the request
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ResolvedLocale(pub Locale);
+
+impl FromRequest for ResolvedLocale {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(ResolvedLocale(negotiate_from_request(req))))
+    }
+}
+
+",
+    )?;
     Ok(())
 }
 
@@ -191,6 +1221,7 @@ fn generate_dynamic_pages(
 pub struct Page{page}<'a> {{
     site_info: &'a SiteInfo,
     page: PageState,
+    a11y: A11yLabels,
     content: Content{page},
 }}
 
@@ -198,10 +1229,17 @@ impl<'a> Page{page}<'a> {{
     pub fn new_response(page: PageState, content: Content{page}) -> Result<HttpResponse> {{
         let page = Page{page} {{
             site_info: &SITE_INFO,
+            a11y: page.locale.a11y(),
             page,
             content,
         }};
-        let s = page.render().map_err(ErrorInternalServerError)?;
+        let s = page.render().map_err(|err| {{
+            ErrorInternalServerError(format!(
+                \"render page '{page_orig}' for locale {{}}: {{}}\",
+                page.page.locale,
+                err
+            ))
+        }})?;
         Ok(HttpResponse::Ok().content_type(\"text/html\").body(s))
     }}
 }}
@@ -217,6 +1255,27 @@ impl<'a> Page{page}<'a> {{
     Ok(())
 }
 
+/// ensure that the template file referenced by the `#[template(path = ...)]` attribute of every page
+/// exists, with the (askama) templates directory being the parent of the pages directory
+fn verify_template_files(cfg: &Pages, templates: &[String]) -> Result<()> {
+    let templates_root = Path::new(&cfg.path)
+        .parent()
+        .ok_or_else(|| anyhow!("get templates directory of {}", cfg.path))?;
+    for template in templates {
+        let template_path = templates_root
+            .join(&cfg.templates_dir)
+            .join(format!("{}.html", template));
+        if !template_path.is_file() {
+            return Err(anyhow!(
+                "template file of page '{}' not found at {}, check the pages path and templates_dir",
+                template,
+                template_path.display()
+            ));
+        }
+    }
+    Ok(())
+}
+
 fn get_templates(templates_path: &str, not_found: &str) -> Result<(String, Vec<String>)> {
     let paths = fs::read_dir(templates_path)
         .with_context(|| format!("list all static page templates in {}", templates_path))?;
@@ -245,6 +1304,9 @@ fn get_templates(templates_path: &str, not_found: &str) -> Result<(String, Vec<S
             templates.push(name.to_owned());
         }
     }
+    // read_dir order depends on the filesystem, sorted for reproducible codegen,
+    // with the not found template kept first
+    templates[1..].sort();
     Ok((not_found_template, templates))
 }
 
@@ -261,10 +1323,15 @@ fn generate_pages_mod_docs(mut w: impl std::io::Write) -> Result<()> {
 
 fn generate_pages_imports(mut w: impl std::io::Write, dynamic_pages: &[String]) -> Result<()> {
     w.write_all(
-        b"use actix_web::error::ErrorInternalServerError;
-use actix_web::{HttpResponse, Result};
+        b"use actix_web::dev::{Body, Payload};
+use actix_web::error::ErrorInternalServerError;
+use actix_web::http::header::ACCEPT_LANGUAGE;
+use actix_web::http::StatusCode;
+use actix_web::{Error, FromRequest, HttpMessage, HttpRequest, HttpResponse, Responder, Result};
 use askama::Template;
+use futures::future::{ready, Ready};
 
+use crate::site::l18n::locales::{A11yLabels, Locale, LOCALE_QUERY_KEY};
 use crate::site::pages::PageState;
 use crate::site::{SiteInfo, SITE_INFO};
 
@@ -284,3 +1351,452 @@ use super::models::{",
     )?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::i18n::codegen::common::assert_snapshot;
+
+    fn fixture_storage() -> Storage {
+        Storage::from_yaml_sources(&[
+            (
+                "en",
+                "site:\n  name: Plabayo News\n  description: \"Conscious \\\"news\\\"\"\n",
+            ),
+            ("nl", "site:\n  name: Plabayo Nieuws\n"),
+            ("pt-br", "site:\n  name: Notícias Plabayo\n"),
+        ])
+        .unwrap()
+    }
+
+    fn fixture_pages(status: &[(&str, &str)]) -> (Pages, Vec<String>) {
+        let cfg = Pages {
+            not_found: "unknown".to_owned(),
+            templates_dir: "pages".to_owned(),
+            status: status
+                .iter()
+                .map(|(page, status)| (page.to_string(), status.to_string()))
+                .collect(),
+            ..Pages::default()
+        };
+        let pages = ["about", "legacy", "teapot", "unknown"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        (cfg, pages)
+    }
+
+    #[test]
+    fn generated_pages_match_snapshot() {
+        let (mut cfg, pages) = fixture_pages(&[("legacy", "GONE")]);
+        cfg.static_pages = pages.clone();
+        let mut templates = pages;
+        templates.push("item".to_owned());
+        templates.push("items".to_owned());
+        let mut out = Vec::new();
+        write_pages(&mut out, &cfg, templates, &fixture_storage()).unwrap();
+        assert_snapshot("pages.rs.snap", &String::from_utf8(out).unwrap());
+    }
+
+    #[test]
+    fn static_pages_use_configured_status_codes() {
+        let (cfg, pages) = fixture_pages(&[
+            ("legacy", "GONE"),
+            ("teapot", "IM_A_TEAPOT"),
+            ("unknown", "UNAVAILABLE_FOR_LEGAL_REASONS"),
+        ]);
+        let mut out = Vec::new();
+        generate_static_pages(&mut out, &cfg, &pages[..], &fixture_storage()).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        for (page, status) in [
+            ("Some(StaticPage::About)", "OK"),
+            ("Some(StaticPage::Legacy)", "GONE"),
+            ("Some(StaticPage::Teapot)", "IM_A_TEAPOT"),
+            ("None", "UNAVAILABLE_FOR_LEGAL_REASONS"),
+        ] {
+            assert!(code.contains(&format!(
+                "            {} => (
+                StatusCode::{},",
+                page, status
+            )));
+        }
+    }
+
+    #[test]
+    fn endpoints_map_to_their_configured_kinds() {
+        let (mut cfg, pages) = fixture_pages(&[]);
+        cfg.kinds = [
+            ("about", "marketing"),
+            ("legacy", "legal"),
+            ("teapot", "legal"),
+        ]
+        .iter()
+        .map(|(page, kind)| (page.to_string(), kind.to_string()))
+        .collect();
+        let mut out = Vec::new();
+        generate_static_pages(&mut out, &cfg, &pages[..], &fixture_storage()).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains(
+            "pub enum PageKind {
+    Generic,
+    Legal,
+    Marketing,
+}"
+        ));
+        for (page, kind) in [
+            ("About", "Marketing"),
+            ("Legacy", "Legal"),
+            ("Teapot", "Legal"),
+        ] {
+            assert!(code.contains(&format!(
+                "            Self::{} => PageKind::{},",
+                page, kind
+            )));
+        }
+
+        // uncategorized pages are generic
+        cfg.kinds.remove("teapot");
+        let mut out = Vec::new();
+        generate_static_pages(&mut out, &cfg, &pages[..], &fixture_storage()).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains("            Self::Teapot => PageKind::Generic,"));
+    }
+
+    #[test]
+    fn not_found_page_defaults_to_not_found_status() {
+        let (cfg, pages) = fixture_pages(&[]);
+        let mut out = Vec::new();
+        generate_static_pages(&mut out, &cfg, &pages[..], &fixture_storage()).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains(
+            "            None => (
+                StatusCode::NOT_FOUND,"
+        ));
+    }
+
+    #[test]
+    fn assets_root_is_generated_from_the_config() {
+        let (mut cfg, pages) = fixture_pages(&[]);
+        cfg.assets_root = "/static/".to_owned();
+        let mut out = Vec::new();
+        generate_static_pages(&mut out, &cfg, &pages[..], &fixture_storage()).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains("pub const ASSETS_ROOT: &str = \"static\";\n"));
+    }
+
+    #[test]
+    fn endpoints_colliding_with_assets_root_are_rejected() {
+        let (mut cfg, mut pages) = fixture_pages(&[]);
+        cfg.assets_root = "assets".to_owned();
+        pages.push("Assets".to_owned());
+        let err =
+            generate_static_pages(Vec::new(), &cfg, &pages[..], &fixture_storage()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "endpoint 'assets' of static page template 'Assets' collides with the assets root 'assets', please rename the template"
+        );
+    }
+
+    #[test]
+    fn invalid_status_names_are_rejected() {
+        let (cfg, pages) = fixture_pages(&[("legacy", "GONNE")]);
+        let err =
+            generate_static_pages(Vec::new(), &cfg, &pages[..], &fixture_storage()).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("invalid status 'GONNE' for static page template 'legacy'"));
+    }
+
+    #[test]
+    fn missing_template_files_are_reported_with_their_path() {
+        let dir = std::env::temp_dir().join("plabayo_news_builder_template_files");
+        let pages_dir = dir.join("pages");
+        fs::create_dir_all(&pages_dir).unwrap();
+        fs::write(pages_dir.join("about.html"), "").unwrap();
+        let cfg = Pages {
+            path: pages_dir.to_str().unwrap().to_owned(),
+            templates_dir: "pages".to_owned(),
+            ..Pages::default()
+        };
+        verify_template_files(&cfg, &["about".to_owned()]).unwrap();
+
+        let cfg = Pages {
+            templates_dir: "views".to_owned(),
+            ..cfg
+        };
+        let err = verify_template_files(&cfg, &["about".to_owned()]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "template file of page 'about' not found at {}, check the pages path and templates_dir",
+                dir.join("views").join("about.html").display()
+            )
+        );
+    }
+
+    #[test]
+    fn localized_svgs_are_inlined_per_locale() {
+        let dir = std::env::temp_dir().join("plabayo_news_builder_localized_svg");
+        fs::create_dir_all(&dir).unwrap();
+        let svg_path = |name: &str| {
+            let path = dir.join(format!("{}.svg", name));
+            fs::write(&path, format!("<svg><title>{}</title></svg>\n", name)).unwrap();
+            path.to_str().unwrap().to_owned()
+        };
+        let (mut cfg, pages) = fixture_pages(&[]);
+        cfg.svg.insert(
+            "about".to_owned(),
+            [
+                ("en".to_owned(), svg_path("flag-en")),
+                ("nl".to_owned(), svg_path("flag-nl")),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let mut out = Vec::new();
+        generate_static_pages(&mut out, &cfg, &pages[..], &fixture_storage()).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        for (locale, svg) in [
+            ("En", "flag-en"),
+            ("Nl", "flag-nl"),
+            // no svg for pt-br, falls back to the default locale
+            ("PtBr", "flag-en"),
+        ] {
+            assert!(code.contains(&format!(
+                "            crate::site::l18n::locales::Locale::{} => \"<svg><title>{}</title></svg>\",",
+                locale, svg
+            )));
+        }
+        // only pages with a configured svg get the localized_svg field
+        assert_eq!(code.matches("    localized_svg: &'static str,").count(), 1);
+    }
+
+    fn jsonld_fixture(properties: &str) -> std::collections::BTreeMap<String, JsonLdValue> {
+        toml::from_str(properties).unwrap()
+    }
+
+    #[test]
+    fn jsonld_is_localized_per_locale() {
+        let properties = jsonld_fixture(
+            r#"
+"@type" = "WebPage"
+name = { key = "site.name" }
+description = { key = "site.description" }
+publisher = { "@type" = "Organization", name = { key = "site.name" } }
+"#,
+        );
+        let storage = fixture_storage();
+        for (locale, name) in [
+            ("en", "Plabayo News"),
+            ("nl", "Plabayo Nieuws"),
+            ("pt-br", "Notícias Plabayo"),
+        ] {
+            let mut parts = vec![];
+            let mut json = String::from("{");
+            write_jsonld_properties(&mut parts, &mut json, &properties, &storage, locale).unwrap();
+            json.push('}');
+            assert!(parts.is_empty());
+            let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+            assert_eq!(value["@type"], "WebPage");
+            assert_eq!(value["name"], name);
+            assert_eq!(value["publisher"]["@type"], "Organization");
+            assert_eq!(value["publisher"]["name"], name);
+            // missing translations fall back to the default locale
+            assert_eq!(value["description"], "Conscious \"news\"");
+        }
+    }
+
+    #[test]
+    fn jsonld_is_generated_as_template_field() {
+        let (mut cfg, pages) = fixture_pages(&[]);
+        cfg.jsonld.insert(
+            "about".to_owned(),
+            jsonld_fixture(
+                r#"
+"@type" = "WebPage"
+name = { key = "site.name" }
+publisher = { "@type" = "Organization", url = { site_info = "repository" } }
+"#,
+            ),
+        );
+        let mut out = Vec::new();
+        generate_static_pages(&mut out, &cfg, &pages[..], &fixture_storage()).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert_eq!(code.matches("    jsonld: String,").count(), 1);
+        assert!(code.contains(
+            r#"            crate::site::l18n::locales::Locale::Nl => ["{\"@context\":\"https://schema.org\",\"@type\":\"WebPage\",\"name\":\"Plabayo Nieuws\",\"publisher\":{\"@type\":\"Organization\",\"url\":", &json_ld_string(&SITE_INFO.repository), "}}"].concat(),"#
+        ));
+        assert!(code.contains("fn json_ld_string(value: &dyn std::fmt::Display) -> String {"));
+
+        cfg.jsonld.insert(
+            "legacy".to_owned(),
+            jsonld_fixture(r#"name = { key = "site.title" }"#),
+        );
+        let err =
+            generate_static_pages(Vec::new(), &cfg, &pages[..], &fixture_storage()).unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "render JSON-LD for locale 'en' of static page template 'legacy': unknown translation key 'site.title'"
+        );
+    }
+
+    #[test]
+    fn missing_required_keys_are_rejected() {
+        let (mut cfg, pages) = fixture_pages(&[]);
+        cfg.required_keys.insert(
+            "about".to_owned(),
+            vec![
+                "site".to_owned(),
+                "site.name".to_owned(),
+                "site.title".to_owned(),
+                "page.about.intro".to_owned(),
+            ],
+        );
+        let err =
+            generate_static_pages(Vec::new(), &cfg, &pages[..], &fixture_storage()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "static page template 'about' misses required keys in default locale 'en': site.title, page.about.intro"
+        );
+
+        // keys only have to be defined by the default locale
+        cfg.required_keys
+            .insert("about".to_owned(), vec!["site.description".to_owned()]);
+        generate_static_pages(Vec::new(), &cfg, &pages[..], &fixture_storage()).unwrap();
+    }
+
+    #[test]
+    fn localized_endpoints_resolve_to_canonical_endpoints() {
+        let (mut cfg, pages) = fixture_pages(&[]);
+        cfg.slugs.insert(
+            "about".to_owned(),
+            [("nl".to_owned(), "over-ons".to_owned())]
+                .into_iter()
+                .collect(),
+        );
+        let mut out = Vec::new();
+        generate_static_pages(&mut out, &cfg, &pages[..], &fixture_storage()).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains(
+            "        match (self, locale) {
+            (Self::About, Locale::Nl) => \"over-ons\",
+            _ => self.endpoint(),
+        }"
+        ));
+        for (locale, slug, page) in [
+            ("En", "about", "ABOUT"),
+            ("Nl", "over-ons", "ABOUT"),
+            ("Nl", "legacy", "LEGACY"),
+            ("PtBr", "about", "ABOUT"),
+        ] {
+            assert!(code.contains(&format!(
+                "        (Locale::{}, \"{}\") => Some(PAGE_{}_ENDPOINT),",
+                locale, slug, page
+            )));
+        }
+        // the canonical endpoint is not a slug of a locale with a localized slug
+        assert!(!code.contains("        (Locale::Nl, \"about\")"));
+
+        // localized paths route to the page in their own locale
+        assert!(code.contains("            (Self::About, Locale::Nl) => PAGE_ABOUT_NL_PATH,\n"));
+        assert!(code.contains("pub const PAGE_ABOUT_NL_PATH: &str = \"/over-ons\";\n"));
+        assert!(code.contains(
+            "    match path.trim_matches('/') {
+        \"over-ons\" => Some((StaticPage::About, Locale::Nl)),
+        _ => None,
+    }"
+        ));
+
+        cfg.slugs
+            .get_mut("about")
+            .unwrap()
+            .insert("en".to_owned(), "teapot".to_owned());
+        let err =
+            generate_static_pages(Vec::new(), &cfg, &pages[..], &fixture_storage()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "slug 'teapot' of locale 'en' is used by both static page templates 'about' and 'teapot'"
+        );
+    }
+
+    #[test]
+    fn content_types_are_validated() {
+        for content_type in [
+            "text/html",
+            "text/plain; charset=utf-8",
+            "application/ld+json",
+            "text/html;charset=\"utf-8\"; q=0.9",
+        ] {
+            assert!(is_valid_mime_type(content_type), "{}", content_type);
+        }
+        for content_type in [
+            "text",
+            "text/",
+            "/html",
+            "text/html;",
+            "text/html; charset",
+            "text /html",
+        ] {
+            assert!(!is_valid_mime_type(content_type), "{}", content_type);
+        }
+
+        let (mut cfg, pages) = fixture_pages(&[]);
+        cfg.content_types
+            .insert("teapot".to_owned(), "text/plain; charset=utf-8".to_owned());
+        let mut out = Vec::new();
+        generate_static_pages(&mut out, &cfg, &pages[..], &fixture_storage()).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        assert!(code.contains(
+            "                StatusCode::OK,
+                \"text/plain; charset=utf-8\",
+                PageTeapot::new(state).render(),"
+        ));
+
+        cfg.content_types
+            .insert("legacy".to_owned(), "text/htlm/x".to_owned());
+        let err =
+            generate_static_pages(Vec::new(), &cfg, &pages[..], &fixture_storage()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid content type 'text/htlm/x' for static page template 'legacy', expected type/subtype with optional parameters"
+        );
+    }
+
+    #[test]
+    fn head_responses_use_the_status_and_content_type_of_the_page() {
+        let (mut cfg, pages) = fixture_pages(&[("legacy", "GONE")]);
+        cfg.content_types
+            .insert("teapot".to_owned(), "text/plain".to_owned());
+        let mut out = Vec::new();
+        generate_static_pages(&mut out, &cfg, &pages[..], &fixture_storage()).unwrap();
+        let code = String::from_utf8(out).unwrap();
+        let head = &code[code.find("pub fn static_response_head").unwrap()..];
+        let head = &head[..head.find("\n}\n").unwrap()];
+        for arm in [
+            "Some(StaticPage::Legacy) => (HttpResponse::build(StatusCode::GONE), \"text/html\")",
+            "Some(StaticPage::Teapot) => (HttpResponse::build(StatusCode::OK), \"text/plain\")",
+            "None => (HttpResponse::build(StatusCode::NOT_FOUND), \"text/html\")",
+        ] {
+            assert!(head.contains(arm), "{}", arm);
+        }
+        assert!(!head.contains("render()"));
+        assert!(head.contains("response.content_type(content_type).body(Body::None)"));
+    }
+
+    #[test]
+    fn templates_are_sorted_after_the_not_found_template() {
+        let dir = std::env::temp_dir().join("plabayo_news_builder_sorted_templates");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["faq", "about", "not_found", "security", "guidelines", "api"] {
+            fs::write(dir.join(format!("{}.html", name)), "").unwrap();
+        }
+        let (not_found, templates) = get_templates(dir.to_str().unwrap(), "not_found").unwrap();
+        assert_eq!(not_found, "not_found");
+        assert_eq!(
+            templates,
+            vec!["not_found", "about", "api", "faq", "guidelines", "security"]
+        );
+    }
+}
@@ -0,0 +1,172 @@
+// Plabayo News
+// Copyright (C) 2021  Glen Henri J. De Cauwsemaecker
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{anyhow, Result};
+use convert_case::{Case, Casing};
+use itertools::Itertools;
+
+/// the script used when none is configured for a locale,
+/// its rules are also applied to any script as a final fallback
+pub const DEFAULT_SCRIPT: &str = "Latn";
+
+/// (lowercase characters, ascii transliteration) pairs per ISO 15924 script code
+const SCRIPTS: &[(&str, &[(&str, &str)])] = &[
+    (
+        "Latn",
+        &[
+            ("àáâãäåāăą", "a"),
+            ("æ", "ae"),
+            ("çćĉċč", "c"),
+            ("ďđð", "d"),
+            ("èéêëēĕėęě", "e"),
+            ("ĝğġģ", "g"),
+            ("ĥħ", "h"),
+            ("ìíîïĩīĭįı", "i"),
+            ("ĳ", "ij"),
+            ("ĵ", "j"),
+            ("ķ", "k"),
+            ("ĺļľŀł", "l"),
+            ("ñńņňŉ", "n"),
+            ("òóôõöøōŏő", "o"),
+            ("œ", "oe"),
+            ("ŕŗř", "r"),
+            ("śŝşš", "s"),
+            ("ß", "ss"),
+            ("ţťŧ", "t"),
+            ("þ", "th"),
+            ("ùúûüũūŭůűų", "u"),
+            ("ŵ", "w"),
+            ("ýÿŷ", "y"),
+            ("źżž", "z"),
+        ],
+    ),
+    (
+        "Cyrl",
+        &[
+            ("а", "a"),
+            ("б", "b"),
+            ("в", "v"),
+            ("гґ", "g"),
+            ("д", "d"),
+            ("её", "e"),
+            ("є", "ye"),
+            ("ж", "zh"),
+            ("з", "z"),
+            ("иі", "i"),
+            ("ї", "yi"),
+            ("й", "y"),
+            ("к", "k"),
+            ("л", "l"),
+            ("м", "m"),
+            ("н", "n"),
+            ("о", "o"),
+            ("п", "p"),
+            ("р", "r"),
+            ("с", "s"),
+            ("т", "t"),
+            ("у", "u"),
+            ("ф", "f"),
+            ("х", "kh"),
+            ("ц", "ts"),
+            ("ч", "ch"),
+            ("ш", "sh"),
+            ("щ", "shch"),
+            ("ъь", ""),
+            ("ы", "y"),
+            ("э", "e"),
+            ("ю", "yu"),
+            ("я", "ya"),
+        ],
+    ),
+    (
+        "Grek",
+        &[
+            ("αά", "a"),
+            ("β", "v"),
+            ("γ", "g"),
+            ("δ", "d"),
+            ("εέ", "e"),
+            ("ζ", "z"),
+            ("ηήιίϊΐ", "i"),
+            ("θ", "th"),
+            ("κ", "k"),
+            ("λ", "l"),
+            ("μ", "m"),
+            ("ν", "n"),
+            ("ξ", "x"),
+            ("οό", "o"),
+            ("π", "p"),
+            ("ρ", "r"),
+            ("σς", "s"),
+            ("τ", "t"),
+            ("υύϋΰ", "y"),
+            ("φ", "f"),
+            ("χ", "ch"),
+            ("ψ", "ps"),
+            ("ωώ", "o"),
+        ],
+    ),
+];
+
+/// name of the generated transliteration function for the given script
+pub fn transliteration_fn_name(script: &str) -> String {
+    format!("transliterate_{}", script.to_case(Case::Snake))
+}
+
+/// generate a `fn(char) -> Option<&'static str>` transliterating
+/// the lowercase characters of the given script to ascii
+pub fn generate_transliteration_fn(mut w: impl std::io::Write, script: &str) -> Result<()> {
+    let table = SCRIPTS
+        .iter()
+        .find(|(name, _)| *name == script)
+        .map(|(_, table)| *table)
+        .ok_or_else(|| {
+            anyhow!(
+                "unsupported script '{}' for transliteration, expected one of: {}",
+                script,
+                SCRIPTS.iter().map(|(name, _)| *name).join(", ")
+            )
+        })?;
+
+    w.write_all(
+        format!(
+            "
+fn {}(c: char) -> Option<&'static str> {{
+    match c {{
+",
+            transliteration_fn_name(script)
+        )
+        .as_bytes(),
+    )?;
+    for (chars, ascii) in table {
+        w.write_all(
+            format!(
+                "        {} => Some({:?}),
+",
+                chars.chars().map(|c| format!("{:?}", c)).join(" | "),
+                ascii
+            )
+            .as_bytes(),
+        )?;
+    }
+    w.write_all(
+        b"        _ => None,
+    }
+}
+",
+    )?;
+    Ok(())
+}
@@ -0,0 +1,183 @@
+// Plabayo News
+// Copyright (C) 2021  Glen Henri J. De Cauwsemaecker
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use crate::i18n::codegen::common::json_string;
+use crate::i18n::locales::{Storage, PLURAL_CATEGORIES};
+use crate::i18n::validation::named_placeholders;
+
+enum Node {
+    Text(String),
+    Object(BTreeMap<String, Node>),
+}
+
+/// TypeScript definition (`.d.ts`) of the strings of the default locale, as a nested `Strings`
+/// interface, with strings having `{name}` placeholders typed as functions of those parameters
+/// and plural strings (e.g. `one` and `other`) as functions of the count
+pub fn strings_definitions(storage: &Storage) -> String {
+    let mut root = BTreeMap::new();
+    // bytes are not strings, and thus not part of the definitions
+    let pairs: Vec<_> = storage
+        .get_default()
+        .map(|locales| locales.iter().filter(|pair| pair.bytes.is_none()).collect())
+        .unwrap_or_default();
+    for pair in pairs {
+        insert(&mut root, &pair.path[..], pair.value);
+    }
+
+    let mut definitions = format!(
+        "// strings.d.ts, generated from the {}.yml keys, do not edit\n\nexport interface Strings ",
+        storage.default_locale()
+    );
+    write_object(&mut definitions, &root, 0);
+    definitions.push('\n');
+    definitions
+}
+
+fn insert(object: &mut BTreeMap<String, Node>, path: &[String], value: String) {
+    match path {
+        [] => (),
+        [key] => {
+            object.insert(key.clone(), Node::Text(value));
+        }
+        [parent, rest @ ..] => {
+            if let Node::Object(children) = object
+                .entry(parent.clone())
+                .or_insert_with(|| Node::Object(BTreeMap::new()))
+            {
+                insert(children, rest, value);
+            }
+        }
+    }
+}
+
+fn write_object(ts: &mut String, object: &BTreeMap<String, Node>, depth: usize) {
+    ts.push_str("{\n");
+    for (key, node) in object {
+        ts.push_str(&format!(
+            "{}{}: ",
+            "  ".repeat(depth + 1),
+            property_name(key)
+        ));
+        match node {
+            Node::Text(value) => ts.push_str(&function_type(&named_placeholders(value), None)),
+            Node::Object(children) => match plural_forms(children) {
+                Some(forms) => {
+                    let mut params: Vec<String> = forms
+                        .iter()
+                        .flat_map(|form| named_placeholders(form))
+                        .filter(|name| name != "count")
+                        .collect();
+                    params.sort();
+                    params.dedup();
+                    ts.push_str(&function_type(&params, Some("count: number")));
+                }
+                None => write_object(ts, children, depth + 1),
+            },
+        }
+        ts.push_str(";\n");
+    }
+    ts.push_str(&"  ".repeat(depth));
+    ts.push('}');
+}
+
+/// the values of an object of plural forms, `None` if the object isn't a plural string
+fn plural_forms(object: &BTreeMap<String, Node>) -> Option<Vec<&str>> {
+    if !object.contains_key("other") {
+        return None;
+    }
+    object
+        .iter()
+        .map(|(key, node)| match node {
+            Node::Text(value) if PLURAL_CATEGORIES.contains(&key.as_str()) => Some(value.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn function_type(params: &[String], count: Option<&str>) -> String {
+    let mut args: Vec<String> = count.into_iter().map(|s| s.to_owned()).collect();
+    if !params.is_empty() {
+        args.push(format!(
+            "params: {{ {} }}",
+            params
+                .iter()
+                .map(|name| format!("{}: string | number", property_name(name)))
+                .collect::<Vec<_>>()
+                .join("; ")
+        ));
+    }
+    if args.is_empty() {
+        "string".to_owned()
+    } else {
+        format!("({}) => string", args.join(", "))
+    }
+}
+
+fn property_name(key: &str) -> String {
+    let is_identifier = key
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_identifier {
+        key.to_owned()
+    } else {
+        json_string(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn definitions_type_plural_and_interpolated_strings_as_functions() {
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "site:\n  name: News\n  greeting: Hi {user}, you have {count} {user} points\n  logo:\n    value: aGk=\n    format: base64\n  pt-br: Português\nrelative_time:\n  seconds:\n    one: \"{} second ago\"\n    other: \"{} seconds ago\"\ncomments:\n  one: one comment on {item}\n  other: \"{count} comments on {item}\"\nother:\n  one: one\n  two: two\n  three: three\n",
+            ),
+            ("nl", "site:\n  name: Nieuws\n"),
+        ])
+        .unwrap();
+        assert_eq!(
+            strings_definitions(&storage),
+            r#"// strings.d.ts, generated from the en.yml keys, do not edit
+
+export interface Strings {
+  comments: (count: number, params: { item: string | number }) => string;
+  other: {
+    one: string;
+    three: string;
+    two: string;
+  };
+  relative_time: {
+    seconds: (count: number) => string;
+  };
+  site: {
+    greeting: (params: { user: string | number; count: string | number }) => string;
+    name: string;
+    "pt-br": string;
+  };
+}
+"#
+        );
+    }
+}
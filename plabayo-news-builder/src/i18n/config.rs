@@ -14,6 +14,8 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use std::collections::{BTreeMap, HashMap};
+
 use anyhow::Result;
 use serde::Deserialize;
 use toml;
@@ -33,21 +35,253 @@ struct MetaDataConfig {
     i18n: I18n,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct I18n {
     pub locales: Vec<String>,
     pub path: String,
     pub out: String,
     pub pages: Pages,
+    #[serde(default)]
+    pub settings: HashMap<String, LocaleSettings>,
+    /// Emit `#[repr(C)]` on the generated `Strings` (and nested) structs,
+    /// fixing the field order and padding to the declaration order.
+    ///
+    /// Note that this does not make the fields themselves FFI-safe:
+    /// a `&'static str` is a (pointer, length) pair whose layout Rust does not guarantee,
+    /// so foreign consumers should only rely on the struct layout,
+    /// and access the strings themselves through Rust (e.g. `as_ptr` and `len`).
+    #[serde(default)]
+    pub repr_c: bool,
+    /// Emit the strings of every top level key (domain, e.g. `nav` or `errors`) as a module
+    /// of its own (e.g. `locales/strings_nav.rs` next to the generated `locales.rs`), holding
+    /// the structs of the domain and its const for every locale, composed into the locale's
+    /// `Strings`. Access (e.g. `strings().nav.submit`) remains unchanged.
+    #[serde(default)]
+    pub split_domains: bool,
+    /// Generate a `Strings` method per string with a flattened name (e.g. `home_title()`
+    /// for `home.title`), restricting the (nested) fields to the crate, such that the locale files
+    /// can be restructured without breaking downstream code. Colliding names fail the build.
+    #[serde(default)]
+    pub accessors: bool,
+    /// Dotted key paths (e.g. `email.subject`) of strings used in plain text contexts,
+    /// which are not allowed to contain html in any locale, including all nested paths.
+    #[serde(default)]
+    pub plain_text: Vec<String>,
+    /// Warn about `{name}` placeholders which are suspiciously similar across the keys of the
+    /// default locale (e.g. `{username}` and `{user_name}`), as well as about placeholders
+    /// found in only the default or only the translated version of a key.
+    #[serde(default)]
+    pub check_placeholders: bool,
+    /// Generate a method per string with `{name}` placeholders, named after the string
+    /// (e.g. `greeting(name)` for `greeting`), substituting its `&str` arguments. Fails the build
+    /// when a locale's version of such a string doesn't use the same placeholders.
+    #[serde(default)]
+    pub interpolation: bool,
+    /// Wrap values passed to the generated `Locale::isolate` in unicode isolate characters
+    /// (a left-to-right or right-to-left isolate when their direction is given), as well as
+    /// the arguments of the `interpolation` methods, such that (user) content interpolated
+    /// in text of another direction renders correctly.
+    /// Opt-in as it adds invisible characters to the interpolated content.
+    #[serde(default)]
+    pub bidi_isolation: bool,
+    /// Mark the generated (lossy) `Locale::from` shim as deprecated in favour of `TryFrom`.
+    #[serde(default)]
+    pub deprecate_from: bool,
+    /// Generate a runtime reload mode, gated behind the consuming crate's `reload` feature
+    /// (which requires a `serde_json` dependency), where strings replaced at runtime using
+    /// `reload_from_json` are looked up with `Locale::current`, resolving fallbacks at lookup.
+    /// Without the feature strings remain fully static.
+    #[serde(default)]
+    pub reload: bool,
+    /// Generate the `mirror` pseudo-locale from the default locale, with its text strings reversed
+    /// (keeping `{placeholder}` tokens and markup intact) and flagged rtl, to test rtl layouts.
+    /// The default locale has to name the locale itself (e.g. `site.locales.mirror`) where needed.
+    #[serde(default)]
+    pub mirror_locale: bool,
+    /// Generate `Strings::render_json`, gated behind the consuming crate's `serde_json` feature
+    /// (e.g. an optional `serde_json` dependency), rendering `{name}` placeholders of a string
+    /// using the fields of a json object.
+    #[serde(default)]
+    pub render_json: bool,
+    /// Generate `Locale::to_langid` and `From<Locale> for icu_locid::LanguageIdentifier`,
+    /// gated behind the consuming crate's `icu` feature (which requires an `icu_locid`
+    /// dependency), to pass locales straight into ICU formatters.
+    #[serde(default)]
+    pub icu: bool,
+    /// Dotted key paths (e.g. `greeting`) of strings with gendered variants (`male`, `female`, `neutral`),
+    /// for which a `Locale` accessor selecting the variant by `Gender` is generated.
+    #[serde(default)]
+    pub gendered: Vec<String>,
+    /// Generate a `Locale` method per plural string, an object of CLDR plural category strings
+    /// (e.g. `one` and `other`), selecting the form for a count (e.g. `comments(n)` for `comments`)
+    /// by the plural rules of the locale. Categories a locale doesn't define use its `other` form.
+    #[serde(default)]
+    pub plurals: bool,
+    /// Flag emoji returned by `Locale::emoji_flag_sequence` for locales without a region subtag,
+    /// a globe by default, an empty string returns no flag for those locales instead.
+    pub region_less_flag: Option<String>,
+    /// Dotted key path (e.g. `site.unavailable`) of a string with `{requested}` and `{served}`
+    /// placeholders, used to generate `Locale::unavailable_notice` for requests of unsupported
+    /// locales. Not generated by default.
+    pub unavailable_notice: Option<String>,
+    /// Path of an advisory JSON report listing, per locale, the values shared by multiple keys,
+    /// most duplicated first, as candidates for shared fragments. Not written by default.
+    pub duplicates_report: Option<String>,
+    /// Write `coverage.json` next to the generated locales module, listing per locale
+    /// the number of translated keys and the keys filled in by a fallback locale. Not written by default.
+    #[serde(default)]
+    pub coverage_report: bool,
+    /// Path of a JSON manifest listing the generated symbols (`Locale` variants,
+    /// `STRINGS_*` and `PAGE_*` constants, page functions) and their kind, for tooling.
+    /// Not written by default.
+    pub symbols_manifest: Option<String>,
+    /// Path of a TypeScript definition (e.g. `strings.d.ts`) of the strings of the default locale,
+    /// typing strings with `{name}` placeholders and plural strings as functions, for frontends.
+    /// Not written by default.
+    pub typescript_definitions: Option<String>,
+    /// Fail the build when a locale defines strings which the default locale doesn't
+    /// (e.g. a mistyped `site.titel`), listing all of them at once,
+    /// instead of skipping them with a codegen warning.
+    #[serde(default)]
+    pub deny_unknown_keys: bool,
+    /// Fail the build when a locale misses more than this number of strings, which are otherwise
+    /// only listed as cargo warnings (and filled in by the fallback locales). Unlimited by default.
+    pub max_missing_strings: Option<usize>,
+    /// Dotted key paths (e.g. `site.name`) of strings kept verbatim in every locale (e.g. brand names),
+    /// including all nested paths, which are not counted for the translation coverage.
+    #[serde(default)]
+    pub verbatim: Vec<String>,
+    /// Fail the build when the translation coverage (percentage of translated keys) of a locale
+    /// is below this percentage (e.g. `90.0`), the coverage of incomplete locales being listed
+    /// as codegen warnings regardless.
+    pub min_coverage: Option<f64>,
+    /// Fail the build on any codegen warning, listing all of them in a single error.
+    #[serde(default)]
+    pub strict: bool,
+    /// Query parameter used to select a locale in (alternate) links, `lang` by default.
+    pub locale_query_key: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+impl I18n {
+    pub fn locale_settings(&self, locale: &str) -> Option<&LocaleSettings> {
+        self.settings.get(locale)
+    }
+}
+
+/// Optional per-locale settings, used to generate the locale specific helpers.
+#[derive(Debug, Default, Deserialize)]
+pub struct LocaleSettings {
+    pub ellipsis: Option<String>,
+    /// ISO 15924 script code (e.g. `Latn`, `Cyrl`, `Grek`) used to transliterate slugs
+    pub script: Option<String>,
+    /// css font stack (e.g. `"Noto Sans JP", sans-serif`) used as `--font-body`
+    pub font_stack: Option<String>,
+    /// flag emoji, or a two letter region code (e.g. `BE`) to derive the flag emoji from
+    pub flag: Option<String>,
+    /// tag used for `hreflang` and `lang` attributes (e.g. `zh-Hans`),
+    /// the locale tag canonicalized to BCP 47 casing by default
+    pub hreflang: Option<String>,
+    /// CLDR (unicode) language identifier (e.g. `zh-Hans-CN`) of the ICU language identifier
+    /// of the locale, the locale tag canonicalized to BCP 47 casing by default
+    pub cldr_id: Option<String>,
+    /// text direction of the locale, `ltr` (default) or `rtl`
+    pub direction: Option<String>,
+    /// decimal separator used by `Locale::format_percent`, `.` by default
+    pub decimal_separator: Option<String>,
+    /// pattern used by `Locale::format_percent`, with `{}` as the number placeholder,
+    /// `{}%` by default (e.g. `{}\u00A0%` for a non-breaking space before the sign)
+    pub percent_pattern: Option<String>,
+    /// strftime-like pattern (e.g. `%d/%m/%Y`) returned by `Locale::date_pattern`, `%Y-%m-%d` by default
+    pub date_pattern: Option<String>,
+    /// strftime-like pattern (e.g. `%I:%M %p`) returned by `Locale::time_pattern`, `%H:%M` by default
+    pub time_pattern: Option<String>,
+    /// strftime-like pattern returned by `Locale::datetime_pattern`, `%Y-%m-%d %H:%M` by default
+    pub datetime_pattern: Option<String>,
+    /// digit group separator used by `Locale::format_currency`, `,` by default
+    pub group_separator: Option<String>,
+    /// pattern used by `Locale::format_currency`, with `{amount}` and `{symbol}` placeholders,
+    /// `{symbol}{amount}` by default (e.g. `{amount}\u00A0{symbol}` for a symbol after the amount)
+    pub currency_pattern: Option<String>,
+    /// currency symbol per (ISO 4217) currency code (e.g. `USD = "US$"`), on top of the
+    /// default symbols, currencies without symbol are shown by their code
+    #[serde(default)]
+    pub currency_symbols: HashMap<String, String>,
+    /// locales (e.g. `pt` for `pt-br`) to take the strings missing for this locale from, in order,
+    /// followed by their own fallbacks, before falling back to the default locale
+    #[serde(default)]
+    pub fallbacks: Vec<String>,
+    /// whether the locale is hidden (e.g. not yet offered to users while being translated),
+    /// as listed by the locale diagnostics, the `mirror` pseudo-locale always being hidden
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
 pub struct Pages {
     pub path: String,
     pub not_found: String,
     pub templates_dir: String,
     #[serde(rename = "static")]
     pub static_pages: Vec<String>,
+    /// status code name (e.g. `GONE`) per static page template,
+    /// defaulting to `OK`, or `NOT_FOUND` for the not found template
+    #[serde(default)]
+    pub status: HashMap<String, String>,
+    /// svg file path per locale per static page template, inlined at build time
+    /// as the `localized_svg` template field, missing locales use the default locale's svg
+    #[serde(default)]
+    pub svg: HashMap<String, HashMap<String, String>>,
+    /// kind (e.g. `legal`) per static page template, used to generate the `PageKind` enum,
+    /// defaulting to the `Generic` kind
+    #[serde(default)]
+    pub kinds: HashMap<String, String>,
+    /// root path of the static assets (e.g. `assets::ROOT`), which no page endpoint may equal
+    #[serde(default = "default_assets_root")]
+    pub assets_root: String,
+    /// JSON-LD structured data (e.g. a `WebPage` published by an `Organization`) per static page template,
+    /// rendered per locale at build time as the `jsonld` template field
+    #[serde(default)]
+    pub jsonld: HashMap<String, BTreeMap<String, JsonLdValue>>,
+    /// content type (e.g. `text/plain; charset=utf-8`) per static page template,
+    /// defaulting to `text/html`
+    #[serde(default)]
+    pub content_types: HashMap<String, String>,
+    /// localized endpoint slug per locale per static page template,
+    /// missing locales use the canonical endpoint of the page
+    #[serde(default)]
+    pub slugs: HashMap<String, HashMap<String, String>>,
+    /// dotted key paths (e.g. `page.faq.intro`) per static page template,
+    /// which the default locale is required to define (directly or nested)
+    #[serde(default)]
+    pub required_keys: HashMap<String, Vec<String>>,
+}
+
+/// value of a JSON-LD property
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum JsonLdValue {
+    /// translation key (e.g. `{ key = "site.name" }`), resolved per locale
+    Key(JsonLdKey),
+    /// `SITE_INFO` field (e.g. `{ site_info = "repository" }`), resolved at runtime
+    SiteInfo(JsonLdSiteInfo),
+    Text(String),
+    Object(BTreeMap<String, JsonLdValue>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JsonLdKey {
+    pub key: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JsonLdSiteInfo {
+    pub site_info: String,
+}
+
+fn default_assets_root() -> String {
+    "assets".to_owned()
 }
 
 /// Load the i18n config from the package's Cargo.toml metadata.
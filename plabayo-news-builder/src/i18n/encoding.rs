@@ -0,0 +1,116 @@
+// Plabayo News
+// Copyright (C) 2021  Glen Henri J. De Cauwsemaecker
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16BE_BOM: &[u8] = &[0xFE, 0xFF];
+const UTF32LE_BOM: &[u8] = &[0xFF, 0xFE, 0x00, 0x00];
+const UTF32BE_BOM: &[u8] = &[0x00, 0x00, 0xFE, 0xFF];
+
+/// read a (locale, template, ...) text file as UTF-8, stripping its byte order mark if any,
+/// and transcoding it to UTF-8 in case it is UTF-16 with a byte order mark (e.g. as saved
+/// by some Windows editors)
+pub fn read_text_file(path: &Path) -> Result<String> {
+    let bytes = fs::read(path).with_context(|| format!("read file {}", path.display()))?;
+    decode_text(&bytes).with_context(|| format!("decode file {}", path.display()))
+}
+
+fn decode_text(bytes: &[u8]) -> Result<String> {
+    // a UTF-32LE byte order mark starts with the UTF-16LE one
+    if bytes.starts_with(UTF32LE_BOM) || bytes.starts_with(UTF32BE_BOM) {
+        return Err(anyhow!(
+            "unsupported encoding UTF-32, expected UTF-8 or UTF-16 (with byte order mark)"
+        ));
+    }
+    if let Some(bytes) = bytes.strip_prefix(UTF16LE_BOM) {
+        return decode_utf16(bytes, u16::from_le_bytes);
+    }
+    if let Some(bytes) = bytes.strip_prefix(UTF16BE_BOM) {
+        return decode_utf16(bytes, u16::from_be_bytes);
+    }
+    let bytes = bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes);
+    String::from_utf8(bytes.to_vec()).map_err(|err| {
+        anyhow!(
+            "unsupported encoding, expected UTF-8 or UTF-16 (with byte order mark): {}",
+            err
+        )
+    })
+}
+
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> Result<String> {
+    let units = bytes.chunks_exact(2);
+    if !units.remainder().is_empty() {
+        return Err(anyhow!("invalid UTF-16: odd number of bytes"));
+    }
+    char::decode_utf16(units.map(|unit| from_bytes([unit[0], unit[1]])))
+        .collect::<Result<String, _>>()
+        .map_err(|err| anyhow!("invalid UTF-16: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(s: &str, bom: &[u8], to_bytes: fn(u16) -> [u8; 2]) -> Vec<u8> {
+        let mut bytes = bom.to_vec();
+        bytes.extend(s.encode_utf16().flat_map(to_bytes));
+        bytes
+    }
+
+    #[test]
+    fn byte_order_marks_are_stripped_and_utf16_is_transcoded() {
+        let text = "site:\n  name: Nouvelles – ça marche\n";
+        assert_eq!(decode_text(text.as_bytes()).unwrap(), text);
+        assert_eq!(
+            decode_text(&[UTF8_BOM, text.as_bytes()].concat()).unwrap(),
+            text
+        );
+        assert_eq!(
+            decode_text(&utf16(text, UTF16LE_BOM, u16::to_le_bytes)).unwrap(),
+            text
+        );
+        assert_eq!(
+            decode_text(&utf16(text, UTF16BE_BOM, u16::to_be_bytes)).unwrap(),
+            text
+        );
+    }
+
+    #[test]
+    fn unsupported_encodings_are_rejected() {
+        assert_eq!(
+            decode_text(&[UTF32LE_BOM, b"s\0\0\0"].concat())
+                .unwrap_err()
+                .to_string(),
+            "unsupported encoding UTF-32, expected UTF-8 or UTF-16 (with byte order mark)"
+        );
+        // latin-1
+        assert!(decode_text(b"caf\xe9")
+            .unwrap_err()
+            .to_string()
+            .starts_with("unsupported encoding, expected UTF-8 or UTF-16 (with byte order mark)"));
+        assert_eq!(
+            decode_text(&[UTF16LE_BOM, b"s"].concat())
+                .unwrap_err()
+                .to_string(),
+            "invalid UTF-16: odd number of bytes"
+        );
+    }
+}
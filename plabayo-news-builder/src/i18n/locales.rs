@@ -17,36 +17,72 @@
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::convert::TryFrom;
-use std::fs::File;
-use std::path::Path;
+use std::fmt;
+use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Error, Result};
+use anyhow::{anyhow, Context, Error, Result};
 use itertools::Itertools;
 use pulldown_cmark::{html, Options, Parser};
+use serde::de::{DeserializeOwned, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
 use serde::Deserialize;
-use serde_yaml::{from_reader, from_value, Value};
+use serde_yaml::{from_str, from_value, Mapping, Value};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::i18n::encoding::read_text_file;
+use crate::i18n::rename::rename_yaml_key;
 
 #[derive(Debug)]
 pub struct Storage {
     default_locale: String,
     locale_to_values_map: HashMap<String, Locales>,
+    source_paths: Vec<PathBuf>,
 }
 
 impl Storage {
     pub fn load<T: AsRef<str>>(path: &str, supported_locales: &[T]) -> Result<Storage> {
-        println!("cargo:rerun-if-changed={}", path);
-
         let mut locale_to_values_map = HashMap::new();
+        let mut source_paths = Vec::new();
         for supported_locale in supported_locales.iter().map(|r| r.as_ref()) {
-            let locales = Locales::load(path, supported_locale)?;
+            let locale_path = Locales::source_path(path, supported_locale)?;
+            let locales = Locales::load_file(&locale_path)?;
             locale_to_values_map.insert(supported_locale.to_owned(), locales);
+            source_paths.push(locale_path);
         }
         Ok(Storage {
             default_locale: supported_locales[0].as_ref().to_owned(),
             locale_to_values_map,
+            source_paths,
         })
     }
 
+    #[cfg(test)]
+    pub fn from_yaml_sources(sources: &[(&str, &str)]) -> Result<Storage> {
+        let mut locale_to_values_map = HashMap::new();
+        for (locale, source) in sources {
+            let values: HashMap<String, Value> = serde_yaml::from_str(source)
+                .with_context(|| format!("load locale source for {}", locale))?;
+            locale_to_values_map.insert((*locale).to_owned(), Locales { values });
+        }
+        Ok(Storage {
+            default_locale: sources[0].0.to_owned(),
+            locale_to_values_map,
+            source_paths: Vec::new(),
+        })
+    }
+
+    /// paths of all locale files parsed, including those that define no keys
+    pub fn source_paths(&self) -> &[PathBuf] {
+        &self.source_paths[..]
+    }
+
+    /// path of the locale file the strings of the given locale were parsed from, if any
+    pub fn source_path(&self, locale: &str) -> Option<&Path> {
+        self.source_paths
+            .iter()
+            .map(|path| path.as_path())
+            .find(|path| path.file_stem() == Some(locale.as_ref()))
+    }
+
     pub fn default_locale(&self) -> &str {
         self.default_locale.as_str()
     }
@@ -65,6 +101,372 @@ impl Storage {
     pub fn get(&self, locale: &str) -> Option<&Locales> {
         self.locale_to_values_map.get(locale)
     }
+
+    /// (formatted) value of the given dotted path for the given locale,
+    /// falling back to the value of the default locale
+    pub fn value_or_default(&self, locale: &str, path: &str) -> Option<String> {
+        let find = |locales: &Locales| {
+            locales
+                .iter()
+                .find(|pair| pair.path.join(".") == path)
+                .map(|pair| pair.value)
+        };
+        self.get(locale)
+            .and_then(find)
+            .or_else(|| self.get_default().and_then(find))
+    }
+
+    /// dotted paths of the plural strings of the default locale,
+    /// objects of only plural category strings, `other` included
+    pub fn plural_paths(&self) -> Vec<String> {
+        let pairs: Vec<StringValuePathPair> = self
+            .get_default()
+            .map(|locales| locales.iter().collect())
+            .unwrap_or_default();
+        pairs
+            .iter()
+            .filter(|pair| pair.path.len() > 1 && pair.path[pair.path.len() - 1] == "other")
+            .map(|pair| &pair.path[..pair.path.len() - 1])
+            .filter(|parent| {
+                pairs
+                    .iter()
+                    .filter(|pair| pair.path.len() > parent.len() && pair.path.starts_with(parent))
+                    .all(|pair| {
+                        pair.path.len() == parent.len() + 1
+                            && pair.bytes.is_none()
+                            && PLURAL_CATEGORIES.contains(&pair.path[parent.len()].as_str())
+                    })
+            })
+            .map(|parent| parent.join("."))
+            .collect()
+    }
+
+    /// define all plural categories of the plural strings of every locale,
+    /// using the `other` form of the locale for the categories it doesn't define
+    pub fn add_plural_forms(&mut self) -> Result<()> {
+        for path in self.plural_paths() {
+            let path: Vec<&str> = path.split('.').collect();
+            for (locale, locales) in self.locale_to_values_map.iter_mut() {
+                let forms = match locales.lookup_mut(&path[..]) {
+                    Some(Value::Mapping(forms)) => forms,
+                    // not translated, or reported by the validation of the locale
+                    _ => continue,
+                };
+                let other = forms
+                    .get(&Value::String("other".to_owned()))
+                    .cloned()
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "plural string '{}' of locale {} has no 'other' form",
+                            path.join("."),
+                            locale
+                        )
+                    })?;
+                for category in PLURAL_CATEGORIES {
+                    let key = Value::String((*category).to_owned());
+                    if !forms.contains_key(&key) {
+                        forms.insert(key, other.clone());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// add the `mirror` pseudo-locale, generated from the default locale
+    /// with the graphemes of its (text) strings reversed, placeholders and markup kept intact
+    pub fn add_mirror_locale(&mut self) -> Result<()> {
+        if self.locale_to_values_map.contains_key(MIRROR_LOCALE) {
+            return Err(anyhow!(
+                "locale {} is reserved for the generated mirror pseudo-locale",
+                MIRROR_LOCALE
+            ));
+        }
+        let values = self
+            .get_default()
+            .map(|locales| {
+                locales
+                    .values
+                    .iter()
+                    .map(|(k, v)| (k.clone(), mirror_value(v)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.locale_to_values_map
+            .insert(MIRROR_LOCALE.to_owned(), Locales { values });
+        Ok(())
+    }
+}
+
+impl Storage {
+    /// move the string (or object of strings) at the given dotted path to the new dotted path,
+    /// in all locales defining it, failing without any change in case the default locale
+    /// doesn't define it or any locale already defines (a string at) the new path,
+    /// returning the locales of which the key was moved
+    pub fn rename_key(&mut self, old_path: &str, new_path: &str) -> Result<Vec<String>> {
+        let old: Vec<&str> = old_path.split('.').collect();
+        let new: Vec<&str> = new_path.split('.').collect();
+        if old.iter().chain(new.iter()).any(|key| key.is_empty()) {
+            return Err(anyhow!(
+                "invalid key rename from '{}' to '{}', expected dotted paths of non-empty keys",
+                old_path,
+                new_path
+            ));
+        }
+        if new.starts_with(&old[..]) {
+            return Err(anyhow!(
+                "cannot move key '{}' into itself as '{}'",
+                old_path,
+                new_path
+            ));
+        }
+        if self
+            .get_default()
+            .and_then(|locales| locales.lookup(&old[..]))
+            .is_none()
+        {
+            return Err(anyhow!(
+                "key '{}' not found in default locale {}",
+                old_path,
+                self.default_locale
+            ));
+        }
+        for locale in self.all_locales() {
+            let locales = &self.locale_to_values_map[locale];
+            if locales.lookup(&old[..]).is_some() && !locales.can_insert(&new[..]) {
+                return Err(anyhow!(
+                    "cannot rename key '{}' to '{}' as it conflicts with a key already defined by locale {}",
+                    old_path,
+                    new_path,
+                    locale
+                ));
+            }
+        }
+        let mut renamed = Vec::new();
+        for (locale, locales) in self.locale_to_values_map.iter_mut() {
+            if let Some(value) = locales.take(&old[..]) {
+                locales.insert(&new[..], value);
+                renamed.push(locale.clone());
+            }
+        }
+        renamed.sort();
+        Ok(renamed)
+    }
+}
+
+/// CLDR plural categories, an object of (only) these strings is a plural string
+pub const PLURAL_CATEGORIES: &[&str] = &["few", "many", "one", "other", "two", "zero"];
+
+/// tag of the (opt-in) pseudo-locale used to test rtl layouts
+pub const MIRROR_LOCALE: &str = "mirror";
+
+fn mirror_value(value: &Value) -> Value {
+    match value {
+        Value::String(s) => Value::String(mirror_text(s)),
+        Value::Mapping(m) => match TypedValue::try_from(value) {
+            // markdown would no longer parse and bytes are no text, both are kept as-is
+            Ok(TypedValue {
+                format: None | Some(ValueFormat::Text),
+                ..
+            }) => {
+                let mut m = m.clone();
+                let key = Value::String("value".to_owned());
+                if let Some(Value::String(s)) = m.get(&key) {
+                    let mirrored = mirror_text(s);
+                    m.insert(key, Value::String(mirrored));
+                }
+                Value::Mapping(m)
+            }
+            Ok(_) => value.clone(),
+            Err(_) => Value::Mapping(
+                m.iter()
+                    .map(|(k, v)| (k.clone(), mirror_value(v)))
+                    .collect(),
+            ),
+        },
+        _ => value.clone(),
+    }
+}
+
+/// reverse the graphemes of the given text, keeping `{placeholder}` tokens,
+/// html tags and entities intact and in place, such that the text remains renderable
+fn mirror_text(s: &str) -> String {
+    let mut mirrored = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some((start, end)) = next_mirror_token(rest) {
+        mirrored.extend(rest[..start].graphemes(true).rev());
+        mirrored.push_str(&rest[start..end]);
+        rest = &rest[end..];
+    }
+    mirrored.extend(rest.graphemes(true).rev());
+    mirrored
+}
+
+/// byte range of the first placeholder, html tag or html entity in the given text
+fn next_mirror_token(s: &str) -> Option<(usize, usize)> {
+    s.char_indices().find_map(|(start, c)| {
+        let end = match c {
+            '{' => s[start..].find('}'),
+            '<' => s[start..].find('>'),
+            '&' => s[start + 1..]
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '#'))
+                .filter(|&len| len > 0 && s[start + 1 + len..].starts_with(';'))
+                .map(|len| len + 1),
+            _ => None,
+        }?;
+        Some((start, start + end + 1))
+    })
+}
+
+/// extensions of the supported locale files, yaml first
+const LOCALE_FILE_EXTENSIONS: &[&str] = &["yml", "yaml", "json", "toml"];
+
+fn has_extension(path: &Path, expected: &str) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case(expected))
+}
+
+/// parse the given source of a locale file in the format of its extension
+fn parse_locale_source<T: DeserializeOwned>(locale_path: &Path, source: &str) -> Result<T> {
+    let result = if has_extension(locale_path, "json") {
+        serde_json::from_str(source).map_err(Error::from)
+    } else if has_extension(locale_path, "toml") {
+        toml::from_str(source).map_err(Error::from)
+    } else {
+        from_str(source).map_err(Error::from)
+    };
+    result.with_context(|| format!("load locale file {}", locale_path.display()))
+}
+
+/// the keys of a locale file as written, including the keys defined more than once
+enum KeyTree {
+    Leaf,
+    Object(Vec<(String, KeyTree)>),
+}
+
+impl<'de> Deserialize<'de> for KeyTree {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<KeyTree, D::Error> {
+        deserializer.deserialize_any(KeyTreeVisitor)
+    }
+}
+
+struct KeyTreeVisitor;
+
+impl<'de> Visitor<'de> for KeyTreeVisitor {
+    type Value = KeyTree;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a locale value")
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<KeyTree, E> {
+        Ok(KeyTree::Leaf)
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<KeyTree, E> {
+        Ok(KeyTree::Leaf)
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<KeyTree, E> {
+        Ok(KeyTree::Leaf)
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<KeyTree, E> {
+        Ok(KeyTree::Leaf)
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<KeyTree, E> {
+        Ok(KeyTree::Leaf)
+    }
+
+    fn visit_unit<E>(self) -> Result<KeyTree, E> {
+        Ok(KeyTree::Leaf)
+    }
+
+    fn visit_none<E>(self) -> Result<KeyTree, E> {
+        Ok(KeyTree::Leaf)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<KeyTree, D::Error> {
+        KeyTree::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<KeyTree, A::Error> {
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(KeyTree::Leaf)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<KeyTree, A::Error> {
+        let mut keys = Vec::new();
+        while let Some(key) = map.next_key::<Value>()? {
+            let tree = map.next_value()?;
+            // as for the values, keys other than strings are ignored
+            if let Value::String(key) = key {
+                keys.push((key, tree));
+            }
+        }
+        Ok(KeyTree::Object(keys))
+    }
+}
+
+/// dotted paths defined more than once, by repeating a key in an object
+/// or by a dotted key (e.g. `site.name`) repeating a nested path, not listing their nested paths
+fn duplicate_paths(keys: &[(String, KeyTree)]) -> Vec<String> {
+    fn collect_paths(keys: &[(String, KeyTree)], prefix: &str, paths: &mut Vec<String>) {
+        for (key, tree) in keys {
+            let path = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+            if let KeyTree::Object(children) = tree {
+                collect_paths(&children[..], &path, paths);
+            }
+            paths.push(path);
+        }
+    }
+    let mut paths = Vec::new();
+    collect_paths(keys, "", &mut paths);
+    let duplicates: Vec<String> = paths
+        .into_iter()
+        .sorted()
+        .tuple_windows()
+        .filter(|(a, b)| a == b)
+        .map(|(path, _)| path)
+        .dedup()
+        .collect();
+    duplicates
+        .iter()
+        .filter(|path| {
+            !duplicates
+                .iter()
+                .any(|parent| path.starts_with(&format!("{}.", parent)))
+        })
+        .cloned()
+        .collect()
+}
+
+/// the yaml value of a toml locale value, only tables and strings are accepted
+/// as locale strings are text (base64 bytes included)
+fn value_from_toml(value: toml::Value, path: &mut Vec<String>) -> Result<Value> {
+    match value {
+        toml::Value::String(s) => Ok(Value::String(s)),
+        toml::Value::Table(table) => {
+            let mut mapping = Mapping::new();
+            for (key, value) in table {
+                path.push(key);
+                let value = value_from_toml(value, path)?;
+                let key = path.pop().unwrap_or_default();
+                mapping.insert(Value::String(key), value);
+            }
+            Ok(Value::Mapping(mapping))
+        }
+        value => Err(anyhow!(
+            "key '{}' is of type {}, only strings and tables are supported",
+            path.join("."),
+            value.type_str()
+        )),
+    }
 }
 
 #[derive(Debug)]
@@ -73,18 +475,222 @@ pub struct Locales {
 }
 
 impl Locales {
-    pub fn load(path: &str, locale: &str) -> Result<Locales> {
-        let locale_path = Path::new(path).join(format!("{}.yml", locale));
-        let locales_file = File::open(locale_path)
-            .with_context(|| format!("open locale file {}/{}.yml", path, locale))?;
-        let values: HashMap<String, Value> = from_reader(locales_file)
-            .with_context(|| format!("load locale file {}/{}.yml", path, locale))?;
-        Ok(Locales { values })
+    /// load a yaml (`.yml` or `.yaml`), json (`.json`) or toml (`.toml`) locale file
+    fn load_file(locale_path: &Path) -> Result<Locales> {
+        let source = read_text_file(locale_path)
+            .with_context(|| format!("open locale file {}", locale_path.display()))?;
+        // the parsers keep the last value of a key defined more than once, so check the keys first
+        if let KeyTree::Object(keys) = parse_locale_source(locale_path, &source)? {
+            let duplicates = duplicate_paths(&keys[..]);
+            if !duplicates.is_empty() {
+                return Err(anyhow!(duplicates
+                    .iter()
+                    .map(|path| format!(
+                        "key '{}' is defined more than once in locale file {}",
+                        path,
+                        locale_path.display()
+                    ))
+                    .join("\n")));
+            }
+        }
+        let values: HashMap<String, Value> = if has_extension(locale_path, "toml") {
+            let table: HashMap<String, toml::Value> = parse_locale_source(locale_path, &source)?;
+            table
+                .into_iter()
+                .map(|(key, value)| Ok((key.clone(), value_from_toml(value, &mut vec![key])?)))
+                .collect::<Result<_>>()
+                .with_context(|| format!("load locale file {}", locale_path.display()))?
+        } else {
+            parse_locale_source(locale_path, &source)?
+        };
+        let locales = Locales { values };
+        // the feature of a string is generated as-is into a `#[cfg(feature = "..")]` attribute
+        let invalid_features: Vec<String> = locales
+            .iter()
+            .filter_map(|pair| match pair.feature {
+                Some(feature) if !is_feature_name(&feature) => Some(format!(
+                    "key '{}' is gated behind invalid feature name {:?} in locale file {}, expected only alphanumeric characters, '-' and '_'",
+                    pair.path.join("."),
+                    feature,
+                    locale_path.display()
+                )),
+                _ => None,
+            })
+            .collect();
+        if !invalid_features.is_empty() {
+            return Err(anyhow!(invalid_features.join("\n")));
+        }
+        Ok(locales)
+    }
+
+    /// the one locale file of the given locale in the given directory, of any supported format
+    fn source_path(path: &str, locale: &str) -> Result<PathBuf> {
+        let mut candidates: Vec<PathBuf> = LOCALE_FILE_EXTENSIONS
+            .iter()
+            .map(|extension| Path::new(path).join(format!("{}.{}", locale, extension)))
+            .filter(|locale_path| locale_path.is_file())
+            .collect();
+        match candidates.len() {
+            0 => Err(anyhow!(
+                "no locale file found for locale {} in {}, expected one of: {}",
+                locale,
+                path,
+                LOCALE_FILE_EXTENSIONS
+                    .iter()
+                    .map(|extension| format!("{}.{}", locale, extension))
+                    .join(", ")
+            )),
+            1 => Ok(candidates.remove(0)),
+            _ => Err(anyhow!(
+                "multiple locale files found for locale {} in {}: {}",
+                locale,
+                path,
+                candidates
+                    .iter()
+                    .map(|locale_path| locale_path.display().to_string())
+                    .join(", ")
+            )),
+        }
     }
 
     pub fn iter(&self) -> impl Iterator<Item = StringValuePathPair> + '_ {
         ValueIter::new(&self.values).sorted()
     }
+
+    /// the given source of the locale file at the given path, edited to define these values
+    /// after the key at the old path was moved to the new path (see `Storage::rename_key`),
+    /// keeping the comments and the order of the other keys of the source
+    pub fn rename_in_source(
+        &self,
+        locale_path: &Path,
+        source: &str,
+        old: &[&str],
+        new: &[&str],
+    ) -> Result<String> {
+        let edited = if has_extension(locale_path, "json") {
+            // json has no comments, the document is parsed as is to keep the order of its keys
+            let mut mapping: Mapping = parse_locale_source(locale_path, source)?;
+            if let Some(value) = take_from_mapping(&mut mapping, old) {
+                insert_into_mapping(&mut mapping, new, value);
+            }
+            serde_json::to_string_pretty(&mapping).context("serialize locale values")? + "\n"
+        } else if has_extension(locale_path, "toml") {
+            return Err(anyhow!(
+                "cannot rename keys in toml locale file {} without losing its comments and key order, please move the key by hand",
+                locale_path.display()
+            ));
+        } else {
+            rename_yaml_key(source, old, new)
+                .with_context(|| format!("move key in locale file {}", locale_path.display()))?
+        };
+        // the text is edited, so make sure it defines exactly the moved values
+        let values: HashMap<String, Value> = parse_locale_source(locale_path, &edited)?;
+        if values != self.values {
+            return Err(anyhow!(
+                "failed to move key '{}' to '{}' in locale file {}, please move it by hand",
+                old.join("."),
+                new.join("."),
+                locale_path.display()
+            ));
+        }
+        Ok(edited)
+    }
+
+    fn lookup(&self, path: &[&str]) -> Option<&Value> {
+        let (first, rest) = path.split_first()?;
+        rest.iter()
+            .try_fold(self.values.get(*first)?, |value, key| value.get(*key))
+    }
+
+    fn lookup_mut(&mut self, path: &[&str]) -> Option<&mut Value> {
+        let (first, rest) = path.split_first()?;
+        rest.iter()
+            .try_fold(self.values.get_mut(*first)?, |value, key| {
+                value.get_mut(*key)
+            })
+    }
+
+    /// true if the path isn't defined yet, nor conflicts with a string defined at a parent path
+    fn can_insert(&self, path: &[&str]) -> bool {
+        (1..path.len()).all(|len| match self.lookup(&path[..len]) {
+            None => true,
+            Some(value) => value.is_mapping() && TypedValue::try_from(value).is_err(),
+        }) && self.lookup(path).is_none()
+    }
+
+    /// remove the value at the given path, as well as the objects it leaves empty
+    fn take(&mut self, path: &[&str]) -> Option<Value> {
+        match path {
+            [] => None,
+            [key] => self.values.remove(*key),
+            [key, rest @ ..] => {
+                let mapping = self.values.get_mut(*key)?.as_mapping_mut()?;
+                let value = take_from_mapping(mapping, rest)?;
+                if mapping.is_empty() {
+                    self.values.remove(*key);
+                }
+                Some(value)
+            }
+        }
+    }
+
+    /// insert the value at the given path, creating the (missing) objects on its path,
+    /// which is expected to be checked with `can_insert`
+    fn insert(&mut self, path: &[&str], value: Value) {
+        match path {
+            [] => (),
+            [key] => {
+                self.values.insert((*key).to_owned(), value);
+            }
+            [key, rest @ ..] => {
+                let parent = self
+                    .values
+                    .entry((*key).to_owned())
+                    .or_insert_with(|| Value::Mapping(Mapping::new()));
+                if let Value::Mapping(mapping) = parent {
+                    insert_into_mapping(mapping, rest, value);
+                }
+            }
+        }
+    }
+}
+
+/// true if the given name is a valid cargo feature name
+fn is_feature_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn take_from_mapping(mapping: &mut Mapping, path: &[&str]) -> Option<Value> {
+    let (first, rest) = path.split_first()?;
+    let key = Value::String((*first).to_owned());
+    if rest.is_empty() {
+        return mapping.remove(&key);
+    }
+    let child = mapping.get_mut(&key)?.as_mapping_mut()?;
+    let value = take_from_mapping(child, rest)?;
+    if child.is_empty() {
+        mapping.remove(&key);
+    }
+    Some(value)
+}
+
+fn insert_into_mapping(mapping: &mut Mapping, path: &[&str], value: Value) {
+    if let Some((first, rest)) = path.split_first() {
+        let key = Value::String((*first).to_owned());
+        if rest.is_empty() {
+            mapping.insert(key, value);
+            return;
+        }
+        if !mapping.contains_key(&key) {
+            mapping.insert(key.clone(), Value::Mapping(Mapping::new()));
+        }
+        if let Some(Value::Mapping(child)) = mapping.get_mut(&key) {
+            insert_into_mapping(child, rest, value);
+        }
+    }
 }
 
 pub struct ValueIter<'a> {
@@ -100,34 +706,21 @@ struct ValuePathPairRef<'a> {
 pub struct StringValuePathPair {
     pub value: String,
     pub path: Vec<String>,
+    pub feature: Option<String>,
+    /// decoded bytes of a value tagged as base64, generated as `&'static [u8]`
+    pub bytes: Option<Vec<u8>>,
 }
 
 impl PartialEq for StringValuePathPair {
     fn eq(&self, other: &StringValuePathPair) -> bool {
-        if self.path.len() != other.path.len() {
-            return false;
-        }
-        for i in 0..self.path.len() {
-            if self.path[i] != other.path[i] {
-                return false;
-            }
-        }
-        true
+        self.path == other.path
     }
 }
 
 impl Ord for StringValuePathPair {
     fn cmp(&self, other: &StringValuePathPair) -> Ordering {
-        for i in 0..self.path.len() {
-            let c = self.path[i].cmp(&other.path[i]);
-            if i >= other.path.len() {
-                return Ordering::Less;
-            }
-            if c != Ordering::Equal {
-                return c;
-            }
-        }
-        Ordering::Equal
+        // a path which is the prefix of another path comes first
+        self.path.cmp(&other.path)
     }
 }
 
@@ -163,18 +756,24 @@ impl<'a> Iterator for ValueIter<'a> {
                         return Some(StringValuePathPair {
                             value: (if *b { "true" } else { "false" }).to_owned(),
                             path: pair_ref.path,
+                            feature: None,
+                            bytes: None,
                         })
                     }
                     Value::Number(n) => {
                         return Some(StringValuePathPair {
                             value: format!("{}", n),
                             path: pair_ref.path,
+                            feature: None,
+                            bytes: None,
                         })
                     }
                     Value::String(s) => {
                         return Some(StringValuePathPair {
                             value: s.clone(),
                             path: pair_ref.path,
+                            feature: None,
+                            bytes: None,
                         })
                     }
                     Value::Sequence(_) => continue,
@@ -183,6 +782,8 @@ impl<'a> Iterator for ValueIter<'a> {
                             return Some(StringValuePathPair {
                                 value: tv.to_string(),
                                 path: pair_ref.path,
+                                feature: tv.feature,
+                                bytes: tv.bytes,
                             })
                         }
                         Err(_) => {
@@ -207,19 +808,25 @@ enum ValueFormat {
     Text,
     #[serde(rename = "md")]
     Markdown,
+    /// (small) binary value, base64 encoded in source
+    #[serde(rename = "base64")]
+    Bytes,
 }
 
 #[derive(Debug, Deserialize)]
 struct TypedValue {
     value: String,
     format: Option<ValueFormat>,
+    feature: Option<String>,
+    #[serde(skip)]
+    bytes: Option<Vec<u8>>,
 }
 
-impl ToString for TypedValue {
-    fn to_string(&self) -> String {
+impl fmt::Display for TypedValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let format = self.format.as_ref().unwrap_or(&ValueFormat::Text);
         match format {
-            ValueFormat::Text => self.value.clone(),
+            ValueFormat::Text | ValueFormat::Bytes => f.write_str(&self.value),
             ValueFormat::Markdown => {
                 let mut options = Options::empty();
                 options.insert(Options::ENABLE_STRIKETHROUGH);
@@ -228,7 +835,7 @@ impl ToString for TypedValue {
                 let mut output = String::new();
                 html::push_html(&mut output, parser);
 
-                output
+                f.write_str(&output)
             }
         }
     }
@@ -242,9 +849,412 @@ impl TryFrom<&Value> for TypedValue {
             return Ok(TypedValue {
                 value: s.to_owned(),
                 format: Some(ValueFormat::Text),
+                feature: None,
+                bytes: None,
             });
         }
-        let value: TypedValue = from_value(value.clone())?;
+        let mut value: TypedValue = from_value(value.clone())?;
+        if let Some(ValueFormat::Bytes) = value.format {
+            value.bytes = Some(base64::decode(value.value.trim())?);
+        }
         Ok(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(storage: &Storage, locale: &str) -> Vec<String> {
+        storage
+            .get(locale)
+            .unwrap()
+            .iter()
+            .map(|pair| format!("{}={}", pair.path.join("."), pair.value))
+            .collect()
+    }
+
+    #[test]
+    fn keys_are_renamed_in_every_locale() {
+        let mut storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "nav:\n  login: login\n  logout: logout\nauth:\n  title: Welcome\n",
+            ),
+            ("nl", "nav:\n  login: inloggen\n"),
+            ("fr", "site:\n  name: Nouvelles\n"),
+        ])
+        .unwrap();
+        assert_eq!(
+            storage.rename_key("nav.login", "auth.form.login").unwrap(),
+            vec!["en", "nl"]
+        );
+        assert_eq!(
+            paths(&storage, "en"),
+            vec![
+                "auth.form.login=login",
+                "auth.title=Welcome",
+                "nav.logout=logout"
+            ]
+        );
+        // objects left empty are removed
+        assert_eq!(paths(&storage, "nl"), vec!["auth.form.login=inloggen"]);
+        assert!(storage.get("nl").unwrap().lookup(&["nav"]).is_none());
+        assert_eq!(paths(&storage, "fr"), vec!["site.name=Nouvelles"]);
+
+        // whole objects can be moved as well
+        storage.rename_key("auth", "account").unwrap();
+        assert_eq!(paths(&storage, "nl"), vec!["account.form.login=inloggen"]);
+    }
+
+    #[test]
+    fn key_renames_edit_the_locale_sources() {
+        let dir = std::env::temp_dir().join("plabayo_news_builder_rename_sources");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let sources = [
+            (
+                "en.yml",
+                "# navigation\nnav:\n  # link\n  login: login\n  logout: logout\nauth:\n  title: Welcome\n",
+            ),
+            ("nl.json", r#"{"nav": {"login": "inloggen"}, "auth": {"title": "Welkom"}}"#),
+            ("de.toml", "[auth]\ntitle = \"Willkommen\"\n"),
+            ("fr.toml", "[nav]\nlogin = \"connexion\"\n"),
+        ];
+        for (file, source) in sources.iter() {
+            std::fs::write(dir.join(file), source).unwrap();
+        }
+        let mut storage = Storage::load(dir.to_str().unwrap(), &["en", "nl", "de", "fr"]).unwrap();
+        let (old, new) = (["nav", "login"], ["auth", "login"]);
+        assert_eq!(
+            storage.rename_key("nav.login", "auth.login").unwrap(),
+            vec!["en", "fr", "nl"]
+        );
+        let rename = |locale: &str, file: &str| {
+            let source = std::fs::read_to_string(dir.join(file)).unwrap();
+            storage
+                .get(locale)
+                .unwrap()
+                .rename_in_source(&dir.join(file), &source, &old, &new)
+        };
+        assert_eq!(
+            rename("en", "en.yml").unwrap(),
+            "# navigation\nnav:\n  logout: logout\nauth:\n  title: Welcome\n  # link\n  login: login\n"
+        );
+        assert_eq!(
+            rename("nl", "nl.json").unwrap(),
+            "{\n  \"auth\": {\n    \"title\": \"Welkom\",\n    \"login\": \"inloggen\"\n  }\n}\n"
+        );
+        assert!(rename("fr", "fr.toml").is_err());
+
+        // the edit has to result in the moved values
+        let source = std::fs::read_to_string(dir.join("en.yml")).unwrap();
+        assert!(storage
+            .get("en")
+            .unwrap()
+            .rename_in_source(&dir.join("en.yml"), &source, &old, &["auth", "signin"])
+            .is_err());
+    }
+
+    #[test]
+    fn key_renames_reject_collisions() {
+        let mut storage = Storage::from_yaml_sources(&[
+            ("en", "nav:\n  login: login\nauth:\n  title: Welcome\n"),
+            ("nl", "nav:\n  login: inloggen\nsignin: aanmelden\n"),
+        ])
+        .unwrap();
+        let err = storage.rename_key("nav.login", "auth.title").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot rename key 'nav.login' to 'auth.title' as it conflicts with a key already defined by locale en"
+        );
+        // a string can't become an object
+        let err = storage.rename_key("nav.login", "signin.login").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot rename key 'nav.login' to 'signin.login' as it conflicts with a key already defined by locale nl"
+        );
+        assert!(storage.rename_key("nav.missing", "auth.missing").is_err());
+        assert!(storage.rename_key("nav", "nav.sub").is_err());
+        // nothing was changed
+        assert_eq!(
+            paths(&storage, "nl"),
+            vec!["nav.login=inloggen", "signin=aanmelden"]
+        );
+    }
+
+    #[test]
+    fn locale_files_with_byte_order_mark_are_loaded() {
+        let dir = std::env::temp_dir().join("plabayo_news_builder_bom_locales");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("en.yml"), "\u{feff}site:\n  name: News\n").unwrap();
+        let locales = Locales::load_file(&dir.join("en.yml")).unwrap();
+        let pairs: Vec<(String, String)> = locales
+            .iter()
+            .map(|pair| (pair.path.join("."), pair.value))
+            .collect();
+        assert_eq!(pairs, vec![("site.name".to_owned(), "News".to_owned())]);
+    }
+
+    #[test]
+    fn locale_files_can_mix_yaml_and_json() {
+        let dir = std::env::temp_dir().join("plabayo_news_builder_mixed_locales");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("en.yml"),
+            "# comments are welcome\nsite:\n  name: News\n  title: Welcome\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("nl.json"), r#"{"site": {"name": "Nieuws"}}"#).unwrap();
+        std::fs::write(dir.join("de.yaml"), "site:\n  name: Nachrichten\n").unwrap();
+        let storage = Storage::load(dir.to_str().unwrap(), &["en", "nl", "de"]).unwrap();
+        assert_eq!(
+            storage.all_locales().collect::<Vec<_>>(),
+            vec!["de", "en", "nl"]
+        );
+        assert_eq!(paths(&storage, "nl"), vec!["site.name=Nieuws"]);
+        assert_eq!(paths(&storage, "de"), vec!["site.name=Nachrichten"]);
+        assert_eq!(
+            storage.source_path("nl"),
+            Some(dir.join("nl.json").as_path())
+        );
+
+        // parse errors name the file
+        std::fs::write(dir.join("nl.json"), r#"{"site": "#).unwrap();
+        let err = Storage::load(dir.to_str().unwrap(), &["en", "nl"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("load locale file {}", dir.join("nl.json").display())
+        );
+        // a locale can only be defined once
+        std::fs::write(dir.join("nl.yml"), "site:\n  name: Nieuws\n").unwrap();
+        let err = Storage::load(dir.to_str().unwrap(), &["en", "nl"]).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("multiple locale files found for locale nl"));
+        let err = Storage::load(dir.to_str().unwrap(), &["en", "fr"]).unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("expected one of: fr.yml, fr.yaml, fr.json, fr.toml"));
+    }
+
+    #[test]
+    fn keys_defined_more_than_once_are_rejected() {
+        let dir = std::env::temp_dir().join("plabayo_news_builder_duplicate_keys");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("en.yml"), "site:\n  name: News\n").unwrap();
+        for (file, source) in [
+            (
+                "nl.yml",
+                "site:\n  name: Nieuws\n  title: Welkom\n  name: Nieuwtjes\n",
+            ),
+            (
+                "nl.json",
+                r#"{"site": {"name": "Nieuws"}, "site.name": "Nieuwtjes"}"#,
+            ),
+        ] {
+            std::fs::write(dir.join(file), source).unwrap();
+            let err = Storage::load(dir.to_str().unwrap(), &["en", "nl"]).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "key 'site.name' is defined more than once in locale file {}",
+                    dir.join(file).display()
+                )
+            );
+            std::fs::remove_file(dir.join(file)).unwrap();
+        }
+        // nested paths of a repeated object are not listed on their own
+        std::fs::write(
+            dir.join("nl.yml"),
+            "site:\n  name: Nieuws\nnav:\n  login: inloggen\nsite:\n  name: Nieuwtjes\n",
+        )
+        .unwrap();
+        let err = Storage::load(dir.to_str().unwrap(), &["en", "nl"]).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("key 'site' is defined more than once in locale file"));
+        std::fs::write(
+            dir.join("nl.yml"),
+            "site:\n  name: Nieuws\n404: niet gevonden\n",
+        )
+        .unwrap();
+        Storage::load(dir.to_str().unwrap(), &["en", "nl"]).unwrap();
+    }
+
+    #[test]
+    fn feature_names_are_validated() {
+        let dir = std::env::temp_dir().join("plabayo_news_builder_feature_names");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("en.yml"),
+            "site:\n  name: News\n  beta:\n    value: Try beta\n    feature: new-ui_2\n",
+        )
+        .unwrap();
+        Storage::load(dir.to_str().unwrap(), &["en"]).unwrap();
+
+        std::fs::write(
+            dir.join("en.yml"),
+            "site:\n  name: News\n  beta:\n    value: Try beta\n    feature: 'beta\")]'\n",
+        )
+        .unwrap();
+        let err = Storage::load(dir.to_str().unwrap(), &["en"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "key 'site.beta' is gated behind invalid feature name \"beta\\\")]\" in locale file {}, expected only alphanumeric characters, '-' and '_'",
+                dir.join("en.yml").display()
+            )
+        );
+    }
+
+    #[test]
+    fn locale_files_can_be_toml() {
+        let dir = std::env::temp_dir().join("plabayo_news_builder_toml_locales");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("en.toml"),
+            "[site]\nname = \"News\"\nintro = { value = \"**News**\", format = \"md\" }\n\n[site.nav]\nlogin = \"login\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("nl.yml"), "site:\n  name: Nieuws\n").unwrap();
+        let storage = Storage::load(dir.to_str().unwrap(), &["en", "nl"]).unwrap();
+        assert_eq!(storage.default_locale(), "en");
+        assert_eq!(
+            paths(&storage, "en"),
+            vec![
+                "site.intro=<p><strong>News</strong></p>\n",
+                "site.name=News",
+                "site.nav.login=login"
+            ]
+        );
+        assert_eq!(
+            storage.source_path("en"),
+            Some(dir.join("en.toml").as_path())
+        );
+
+        // strings only, the key of other values is reported
+        std::fs::write(
+            dir.join("en.toml"),
+            "[site]\nname = \"News\"\n\n[site.nav]\nitems = 3\n",
+        )
+        .unwrap();
+        let err = Storage::load(dir.to_str().unwrap(), &["en", "nl"]).unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            format!(
+                "load locale file {}: key 'site.nav.items' is of type integer, only strings and tables are supported",
+                dir.join("en.toml").display()
+            )
+        );
+    }
+
+    #[test]
+    fn source_paths_include_locale_files_without_keys() {
+        let dir = std::env::temp_dir().join("plabayo_news_builder_source_paths");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("en.yml"), "site:\n  name: News\n").unwrap();
+        std::fs::write(dir.join("nl.yml"), "{}\n").unwrap();
+        let storage = Storage::load(dir.to_str().unwrap(), &["en", "nl"]).unwrap();
+        assert_eq!(
+            storage.source_paths(),
+            &[dir.join("en.yml"), dir.join("nl.yml")][..]
+        );
+        assert_eq!(
+            storage.source_path("nl"),
+            Some(dir.join("nl.yml").as_path())
+        );
+        assert_eq!(storage.source_path("fr"), None);
+    }
+
+    #[test]
+    fn plural_forms_are_completed_with_the_other_form() {
+        let mut storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "site:\n  name: News\ncomments:\n  one: comment\n  other: comments\nnav:\n  other: other\n  login: login\n",
+            ),
+            (
+                "ru",
+                "comments:\n  one: komentariy\n  few: komentariya\n  other: komentariev\n",
+            ),
+            ("nl", "site:\n  name: Nieuws\n"),
+        ])
+        .unwrap();
+        assert_eq!(storage.plural_paths(), vec!["comments"]);
+        storage.add_plural_forms().unwrap();
+        assert_eq!(
+            paths(&storage, "ru"),
+            vec![
+                "comments.few=komentariya",
+                "comments.many=komentariev",
+                "comments.one=komentariy",
+                "comments.other=komentariev",
+                "comments.two=komentariev",
+                "comments.zero=komentariev",
+            ]
+        );
+        assert_eq!(paths(&storage, "en").len(), 9);
+        assert_eq!(paths(&storage, "nl"), vec!["site.name=Nieuws"]);
+
+        let mut storage = Storage::from_yaml_sources(&[
+            ("en", "comments:\n  one: comment\n  other: comments\n"),
+            ("nl", "comments:\n  one: reactie\n"),
+        ])
+        .unwrap();
+        assert_eq!(
+            storage.add_plural_forms().unwrap_err().to_string(),
+            "plural string 'comments' of locale nl has no 'other' form"
+        );
+    }
+
+    #[test]
+    fn mirror_locale_reverses_text_but_keeps_placeholders() {
+        assert_eq!(mirror_text("Hello {name}!"), " olleH{name}!");
+        assert_eq!(mirror_text("{} of {total}"), "{} fo {total}");
+        assert_eq!(mirror_text("a {b"), "b{ a");
+        assert_eq!(
+            mirror_text("Read the <a href=\"/faq\">FAQ</a> &amp; more"),
+            " eht daeR<a href=\"/faq\">QAF</a> &amp;erom "
+        );
+        assert_eq!(mirror_text("Tom & Jerry"), "yrreJ & moT");
+        // combining marks stay with their base character
+        assert_eq!(
+            mirror_text("cafe\u{301} noe\u{308}l"),
+            "le\u{308}on e\u{301}fac"
+        );
+
+        let mut storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "site:\n  greeting: Hi {user}\n  locales:\n    en: English\n  intro:\n    value: \"**News**\"\n    format: md\n  label:\n    value: Submit\n    feature: submit\n",
+            ),
+            ("nl", "site:\n  greeting: Hallo {user}\n"),
+        ])
+        .unwrap();
+        storage.add_mirror_locale().unwrap();
+        let pairs: Vec<(String, String)> = storage
+            .get(MIRROR_LOCALE)
+            .unwrap()
+            .iter()
+            .map(|pair| (pair.path.join("."), pair.value))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("site.greeting".to_owned(), " iH{user}".to_owned()),
+                (
+                    "site.intro".to_owned(),
+                    "<p><strong>News</strong></p>\n".to_owned()
+                ),
+                ("site.label".to_owned(), "timbuS".to_owned()),
+                ("site.locales.en".to_owned(), "hsilgnE".to_owned()),
+            ]
+        );
+        assert!(storage.add_mirror_locale().is_err());
+    }
+}
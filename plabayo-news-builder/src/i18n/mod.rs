@@ -14,16 +14,103 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use anyhow::Result;
+use std::path::Path;
 
+use anyhow::{anyhow, Context, Result};
+
+mod changelog;
 mod codegen;
 mod config;
+mod encoding;
 mod locales;
+mod rename;
+mod scaffold;
+mod validation;
+
+pub use validation::{Diagnostic, Severity};
 
 /// build the i18n locale structs and (Askama) templates
 /// for the project
 pub fn build(cargo_toml: &str) -> Result<()> {
     let i18n_cfg = config::load(cargo_toml)?;
-    let locales_storage = locales::Storage::load(&i18n_cfg.path, &i18n_cfg.locales[..])?;
-    codegen::generate_all(&i18n_cfg.out, &locales_storage, &i18n_cfg.pages)
+    let locales_storage = load_storage(&i18n_cfg)?;
+    codegen::generate_all(&i18n_cfg, &locales_storage)
+}
+
+/// find all problems with the locale strings of the project, without failing on them,
+/// for tooling (e.g. editor integrations) to report as it sees fit
+pub fn check(cargo_toml: &str) -> Result<Vec<Diagnostic>> {
+    let i18n_cfg = config::load(cargo_toml)?;
+    let locales_storage = load_storage(&i18n_cfg)?;
+    Ok(locales_storage.validate(validation::ValidationRules::from_config(&i18n_cfg)))
+}
+
+fn load_storage(cfg: &config::I18n) -> Result<locales::Storage> {
+    let mut storage = locales::Storage::load(&cfg.path, &cfg.locales[..])?;
+    if cfg.plurals {
+        storage.add_plural_forms()?;
+    }
+    if cfg.mirror_locale {
+        storage.add_mirror_locale()?;
+    }
+    Ok(storage)
+}
+
+/// summarize the strings added, removed and changed between the locales
+/// found in two directories (e.g. the locales directory checked out at two git revisions),
+/// grouped by locale and key, for the given supported locales (which both directories define)
+pub fn changelog<T: AsRef<str>>(
+    old_path: &str,
+    new_path: &str,
+    supported_locales: &[T],
+) -> Result<String> {
+    let old = locales::Storage::load(old_path, supported_locales)?;
+    let new = locales::Storage::load(new_path, supported_locales)?;
+    Ok(changelog::format_changelog(&old, &new))
+}
+
+/// move the string (or object of strings) at the given dotted path (e.g. `nav.login`)
+/// to the new dotted path (e.g. `auth.login`) in all locale files of the project defining it,
+/// editing these locale files (keeping their comments) only if the move succeeds for all of them
+pub fn rename_key(cargo_toml: &str, old_path: &str, new_path: &str) -> Result<()> {
+    let i18n_cfg = config::load(cargo_toml)?;
+    let mut locales_storage = locales::Storage::load(&i18n_cfg.path, &i18n_cfg.locales[..])?;
+    let renamed_locales = locales_storage.rename_key(old_path, new_path)?;
+    let old: Vec<&str> = old_path.split('.').collect();
+    let new: Vec<&str> = new_path.split('.').collect();
+    let mut files = Vec::new();
+    for locale in renamed_locales.iter() {
+        let locale_path = locales_storage
+            .source_path(locale)
+            .ok_or_else(|| anyhow!("get locale file of locale {}", locale))?;
+        let source = encoding::read_text_file(locale_path)
+            .with_context(|| format!("open locale file {}", locale_path.display()))?;
+        let source = locales_storage
+            .get(locale)
+            .ok_or_else(|| anyhow!("get strings of locale {}", locale))?
+            .rename_in_source(locale_path, &source, &old[..], &new[..])?;
+        files.push((locale_path.to_owned(), source));
+    }
+    rename::write_files(&files[..])
+}
+
+/// stub locale file for the given (new) locale tag, containing every key of the default locale,
+/// and any key referenced by the templates it doesn't define, with empty values for translators
+pub fn scaffold_locale(cargo_toml: &str, tag: &str) -> Result<String> {
+    let i18n_cfg = config::load(cargo_toml)?;
+    let default_locale = i18n_cfg
+        .locales
+        .first()
+        .ok_or_else(|| anyhow!("no locales configured in {}", cargo_toml))?;
+    let locales_storage = locales::Storage::load(&i18n_cfg.path, &[default_locale])?;
+    // the pages directory is a sub directory of the templates (e.g. next to the layouts)
+    let templates_path = Path::new(&i18n_cfg.pages.path)
+        .parent()
+        .ok_or_else(|| anyhow!("get templates directory of {}", i18n_cfg.pages.path))?;
+    let references = scaffold::template_references(templates_path)?;
+    Ok(scaffold::format_scaffold(
+        tag,
+        &locales_storage,
+        &references[..],
+    ))
 }
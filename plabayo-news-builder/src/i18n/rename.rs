@@ -0,0 +1,468 @@
+// Plabayo News
+// Copyright (C) 2021  Glen Henri J. De Cauwsemaecker
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+
+/// move the (block style) key at the old path of the given yaml source to the new path,
+/// editing the text such that the comments, formatting and order of all other keys are kept,
+/// removing the mappings it leaves empty and creating the missing mappings of the new path
+pub fn rename_yaml_key(source: &str, old: &[&str], new: &[&str]) -> Result<String> {
+    let mut lines: Vec<String> = source.split_inclusive('\n').map(String::from).collect();
+    let keys = yaml_keys(&lines);
+    let moved = keys
+        .iter()
+        .find(|key| key.path == old)
+        .ok_or_else(|| anyhow!("key '{}' not found", old.join(".")))?;
+    let block: Vec<String> = lines[moved.start..moved.end].to_vec();
+    let (block_line, block_indent, quote) = (moved.line - moved.start, moved.indent, moved.quote);
+
+    // remove the key, as well as the mappings only containing the key
+    let mut removed = moved;
+    for len in (1..old.len()).rev() {
+        let only_child = keys
+            .iter()
+            .filter(|key| key.path.len() > len && key.path[..len] == old[..len])
+            .all(|key| {
+                let len = key.path.len().min(old.len());
+                key.path[..len] == old[..len]
+            });
+        match keys.iter().find(|key| key.path == old[..len]) {
+            Some(parent) if only_child => removed = parent,
+            _ => break,
+        }
+    }
+    // as well as one of the blank lines around it, if any
+    let (mut start, mut end) = (removed.start, removed.end);
+    let blank_before = start > 0 && is_blank(&lines[start - 1]);
+    if (start == 0 || blank_before) && lines.get(end).is_some_and(|line| is_blank(line)) {
+        end += 1;
+    } else if blank_before && end == lines.len() {
+        start -= 1;
+    }
+    lines.drain(start..end);
+
+    // insert it at the end of the deepest mapping of the new path already defined
+    let keys = yaml_keys(&lines);
+    let unit = keys
+        .iter()
+        .map(|key| key.indent)
+        .filter(|indent| *indent > 0)
+        .min()
+        .unwrap_or(2);
+    let parent = (1..new.len())
+        .rev()
+        .find_map(|len| keys.iter().find(|key| key.path == new[..len]));
+    let (at, defined, indent) = match parent {
+        Some(parent) => {
+            if !parent.nested {
+                return Err(anyhow!(
+                    "key '{}' is not a block style mapping",
+                    parent.path.join(".")
+                ));
+            }
+            let indent = keys
+                .iter()
+                .find(|key| {
+                    key.path.len() == parent.path.len() + 1 && key.path.starts_with(&parent.path)
+                })
+                .map(|key| key.indent)
+                .unwrap_or(parent.indent + unit);
+            (parent.end, parent.path.len(), indent)
+        }
+        None => (keys.iter().map(|key| key.end).max().unwrap_or(0), 0, 0),
+    };
+    let mut inserted = Vec::new();
+    for (depth, key) in new[defined..new.len() - 1].iter().enumerate() {
+        inserted.push(format!(
+            "{}{}:\n",
+            " ".repeat(indent + depth * unit),
+            yaml_key_text(key, None)
+        ));
+    }
+    let leaf_indent = indent + (new.len() - 1 - defined) * unit;
+    for (i, line) in block.iter().enumerate() {
+        if i == block_line {
+            let rest = &line.trim_start_matches(' ')[yaml_key_len(line)..];
+            inserted.push(format!(
+                "{}{}{}",
+                " ".repeat(leaf_indent),
+                yaml_key_text(new[new.len() - 1], quote),
+                rest
+            ));
+        } else {
+            inserted.push(reindent(line, block_indent, leaf_indent));
+        }
+    }
+    if let Some(last) = inserted.last_mut() {
+        if !last.ends_with('\n') {
+            last.push('\n');
+        }
+    }
+    if at > 0 && !lines[at - 1].ends_with('\n') {
+        lines[at - 1].push('\n');
+    }
+    lines.splice(at..at, inserted);
+    Ok(lines.concat())
+}
+
+/// write all given files, each to a temporary file next to it first,
+/// replacing the files only once all of them are written
+pub fn write_files(files: &[(PathBuf, String)]) -> Result<()> {
+    let mut temp_paths = Vec::new();
+    for (path, content) in files {
+        let temp_path = temp_path(path);
+        if let Err(err) = fs::write(&temp_path, content) {
+            for temp_path in temp_paths {
+                let _ = fs::remove_file(temp_path);
+            }
+            return Err(err)
+                .with_context(|| format!("write temporary file {}", temp_path.display()));
+        }
+        temp_paths.push(temp_path);
+    }
+    for ((path, _), temp_path) in files.iter().zip(temp_paths) {
+        fs::rename(&temp_path, path).with_context(|| {
+            format!(
+                "replace file {} with {}",
+                path.display(),
+                temp_path.display()
+            )
+        })?;
+    }
+    Ok(())
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.tmp", file_name))
+}
+
+/// a key of a block style mapping, as found in a yaml source
+#[derive(Debug)]
+struct YamlKey {
+    path: Vec<String>,
+    indent: usize,
+    quote: Option<char>,
+    /// whether or not its value is defined on the lines below (e.g. a nested mapping)
+    nested: bool,
+    /// index of the first line, including the comments right above the key
+    start: usize,
+    /// index of the line defining the key
+    line: usize,
+    /// index of the line after the last line of its value
+    end: usize,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LineKind {
+    Blank,
+    Comment,
+    Key,
+    Content,
+}
+
+fn yaml_keys(lines: &[String]) -> Vec<YamlKey> {
+    // lines of multi-line values (e.g. `|` blocks) are content, even if they look like a key
+    let mut kinds = Vec::with_capacity(lines.len());
+    let mut value_indent = None;
+    for line in lines {
+        let indent = indentation(line);
+        let kind = if is_blank(line) {
+            LineKind::Blank
+        } else if value_indent.is_some_and(|value_indent| indent > value_indent) {
+            LineKind::Content
+        } else if line.trim_start().starts_with('#') {
+            value_indent = None;
+            LineKind::Comment
+        } else if let Some((_, _, nested)) = parse_yaml_key(line) {
+            value_indent = if nested { None } else { Some(indent) };
+            LineKind::Key
+        } else {
+            value_indent = None;
+            LineKind::Content
+        };
+        kinds.push(kind);
+    }
+
+    let mut keys = Vec::new();
+    let mut parents: Vec<(usize, String)> = Vec::new();
+    for (line, kind) in kinds.iter().enumerate() {
+        if *kind != LineKind::Key {
+            continue;
+        }
+        let (key, quote, nested) = match parse_yaml_key(&lines[line]) {
+            Some(key) => key,
+            None => continue,
+        };
+        let indent = indentation(&lines[line]);
+        while parents
+            .last()
+            .is_some_and(|(parent_indent, _)| *parent_indent >= indent)
+        {
+            parents.pop();
+        }
+        let mut path: Vec<String> = parents.iter().map(|(_, key)| key.clone()).collect();
+        path.push(key.clone());
+        if nested {
+            parents.push((indent, key));
+        }
+
+        let mut start = line;
+        while start > 0 && kinds[start - 1] == LineKind::Comment {
+            start -= 1;
+        }
+        let boundary = (line + 1..lines.len())
+            .find(|&next| {
+                matches!(kinds[next], LineKind::Key | LineKind::Content)
+                    && indentation(&lines[next]) <= indent
+            })
+            .unwrap_or(lines.len());
+        let end = (line..boundary)
+            .rev()
+            .find(|&last| matches!(kinds[last], LineKind::Key | LineKind::Content))
+            .map_or(line + 1, |last| last + 1);
+
+        keys.push(YamlKey {
+            path,
+            indent,
+            quote,
+            nested,
+            start,
+            line,
+            end,
+        });
+    }
+    keys
+}
+
+/// key of a `key: value` line, its quote character if quoted
+/// and whether or not its value is defined on the lines below
+fn parse_yaml_key(line: &str) -> Option<(String, Option<char>, bool)> {
+    let trimmed = line.trim();
+    let len = yaml_key_len(line);
+    if len == 0 {
+        return None;
+    }
+    let (key, quote) = match trimmed.chars().next()? {
+        quote @ ('"' | '\'') => (trimmed[1..len - 1].to_owned(), Some(quote)),
+        _ => (trimmed[..len].trim_end().to_owned(), None),
+    };
+    let value = trimmed[len..].strip_prefix(':')?;
+    if !(value.is_empty() || value.starts_with([' ', '\t'])) {
+        return None;
+    }
+    let value = value.trim_start();
+    Some((key, quote, value.is_empty() || value.starts_with('#')))
+}
+
+/// length of the (possibly quoted) key of a `key: value` line, zero if the line defines no key
+fn yaml_key_len(line: &str) -> usize {
+    let trimmed = line.trim_start();
+    match trimmed.chars().next() {
+        Some(quote @ ('"' | '\'')) => trimmed[1..]
+            .find(quote)
+            .map_or(0, |close| close + 2)
+            .min(trimmed.len()),
+        Some('#' | '-' | '[' | '{' | '?' | '&' | '*' | '!' | '|' | '>' | '%' | '@' | '`')
+        | None => 0,
+        Some(_) => trimmed
+            .match_indices(':')
+            .map(|(i, _)| i)
+            .find(|&i| {
+                trimmed[i + 1..]
+                    .chars()
+                    .next()
+                    .is_none_or(char::is_whitespace)
+            })
+            .unwrap_or(0),
+    }
+}
+
+fn yaml_key_text(key: &str, quote: Option<char>) -> String {
+    match quote {
+        Some('\'') => format!("'{}'", key.replace('\'', "''")),
+        Some(_) => format!("\"{}\"", key.replace('\\', "\\\\").replace('"', "\\\"")),
+        None if key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+            && !key.starts_with('-') =>
+        {
+            key.to_owned()
+        }
+        None => yaml_key_text(key, Some('"')),
+    }
+}
+
+/// move the given line from the given indentation to the new indentation
+fn reindent(line: &str, from: usize, to: usize) -> String {
+    // whitespace of blank lines is content in block scalars (e.g. `|` values)
+    if is_blank(line) && indentation(line) == 0 {
+        return line.to_owned();
+    }
+    if to >= from {
+        return format!("{}{}", " ".repeat(to - from), line);
+    }
+    let strip = (from - to).min(indentation(line));
+    line[strip..].to_owned()
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn is_blank(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rename(source: &str, old: &str, new: &str) -> String {
+        let old: Vec<&str> = old.split('.').collect();
+        let new: Vec<&str> = new.split('.').collect();
+        rename_yaml_key(source, &old[..], &new[..]).unwrap()
+    }
+
+    #[test]
+    fn yaml_keys_are_moved_keeping_comments_and_order() {
+        let source = "# navigation
+nav:
+  # the login link
+  login: Log in # short
+  logout: Log out
+
+auth:
+  title: Welcome
+  help: |
+    login: not a key
+    # not a comment
+";
+        assert_eq!(
+            rename(source, "nav.login", "auth.form.login"),
+            "# navigation
+nav:
+  logout: Log out
+
+auth:
+  title: Welcome
+  help: |
+    login: not a key
+    # not a comment
+  form:
+    # the login link
+    login: Log in # short
+"
+        );
+        assert_eq!(
+            rename(source, "auth.help", "nav.help"),
+            "# navigation
+nav:
+  # the login link
+  login: Log in # short
+  logout: Log out
+  help: |
+    login: not a key
+    # not a comment
+
+auth:
+  title: Welcome
+"
+        );
+    }
+
+    #[test]
+    fn yaml_mappings_left_empty_are_removed() {
+        let source = "site:
+  name: News
+
+nav:
+  'login': Log in
+
+footer:
+    about: About
+";
+        assert_eq!(
+            rename(source, "nav.login", "footer.signin"),
+            "site:
+  name: News
+
+footer:
+    about: About
+    'signin': Log in
+"
+        );
+        // whole mappings are moved, re-indented to their new parent
+        assert_eq!(
+            rename(source, "footer", "site.footer"),
+            "site:
+  name: News
+  footer:
+      about: About
+
+nav:
+  'login': Log in
+"
+        );
+        assert_eq!(
+            rename("a:\n  b:\n    c: d\ne: f\n", "a.b.c", "g"),
+            "e: f\ng: d\n"
+        );
+        assert_eq!(rename("a:\n  b: c", "a.b", "d"), "d: c\n");
+    }
+
+    #[test]
+    fn yaml_keys_in_flow_mappings_are_not_moved() {
+        let old = ["nav", "login"];
+        assert!(rename_yaml_key("nav: { login: Log in }\n", &old, &["login"]).is_err());
+        assert!(rename_yaml_key(
+            "nav:\n  login: x\nauth: { a: b }\n",
+            &old,
+            &["auth", "login"]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn files_are_written_all_at_once() {
+        let dir = std::env::temp_dir().join("plabayo_news_builder_write_files");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("en.yml"), "a: b\n").unwrap();
+        let files = [
+            (dir.join("en.yml"), "c: d\n".to_owned()),
+            (dir.join("missing").join("nl.yml"), "c: e\n".to_owned()),
+        ];
+        assert!(write_files(&files).is_err());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("en.yml")).unwrap(),
+            "a: b\n"
+        );
+        assert!(!dir.join(".en.yml.tmp").exists());
+
+        write_files(&files[..1]).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("en.yml")).unwrap(),
+            "c: d\n"
+        );
+        assert!(!dir.join(".en.yml.tmp").exists());
+    }
+}
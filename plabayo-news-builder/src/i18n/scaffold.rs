@@ -0,0 +1,229 @@
+// Plabayo News
+// Copyright (C) 2021  Glen Henri J. De Cauwsemaecker
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use convert_case::{Case, Casing};
+
+use crate::i18n::encoding::read_text_file;
+use crate::i18n::locales::Storage;
+
+/// string paths referenced (as `strings().a.b`) by all templates found in the given directory,
+/// including the templates found in its sub directories
+pub fn template_references(templates_path: &Path) -> Result<Vec<Vec<String>>> {
+    Ok(template_sources(templates_path)?
+        .iter()
+        .flat_map(|(_, source)| template_references_in(source))
+        .collect())
+}
+
+/// path and source of all (html) templates found in the given directory,
+/// including the templates found in its sub directories
+pub fn template_sources(templates_path: &Path) -> Result<Vec<(PathBuf, String)>> {
+    let mut sources = Vec::new();
+    let entries = fs::read_dir(templates_path)
+        .with_context(|| format!("list all templates in {}", templates_path.display()))?;
+    for entry in entries {
+        let path = entry
+            .with_context(|| format!("list a template found in {}", templates_path.display()))?
+            .path();
+        if path.is_dir() {
+            sources.extend(template_sources(&path)?);
+        } else if path.extension().is_some_and(|ext| ext == "html") {
+            let source = read_text_file(&path)
+                .with_context(|| format!("read template {}", path.display()))?;
+            sources.push((path, source));
+        }
+    }
+    Ok(sources)
+}
+
+/// string paths referenced (as `strings().a.b`) by the given template source,
+/// ignoring methods called on them (e.g. `strings().site.locales.iter()`)
+fn template_references_in(source: &str) -> Vec<Vec<String>> {
+    source
+        .match_indices("strings().")
+        .filter_map(|(index, pattern)| {
+            let rest = &source[index + pattern.len()..];
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+                .unwrap_or(rest.len());
+            let mut path: Vec<String> = rest[..end]
+                .split('.')
+                .filter(|key| !key.is_empty())
+                .map(|key| key.to_owned())
+                .collect();
+            if rest[end..].starts_with('(') {
+                path.pop();
+            }
+            if path.is_empty() {
+                None
+            } else {
+                Some(path)
+            }
+        })
+        .collect()
+}
+
+/// stub locale file for the given locale tag, containing every key of the default locale,
+/// as well as the keys referenced by the templates which it doesn't define yet,
+/// all with empty values to be translated
+pub fn format_scaffold(tag: &str, storage: &Storage, references: &[Vec<String>]) -> String {
+    let mut paths: Vec<Vec<String>> = storage
+        .get_default()
+        .map(|locales| locales.iter().map(|pair| pair.path).collect())
+        .unwrap_or_default();
+    // template references use the (snake case) field names of the generated strings
+    let field_paths: Vec<Vec<String>> = paths
+        .iter()
+        .map(|path| path.iter().map(|key| key.to_case(Case::Snake)).collect())
+        .collect();
+    let missing_references: Vec<Vec<String>> = references
+        .iter()
+        .filter(|reference| {
+            !field_paths
+                .iter()
+                .any(|path| path.starts_with(reference) || reference.starts_with(path))
+        })
+        .cloned()
+        .collect();
+    paths.extend(missing_references);
+    paths.sort();
+    paths.dedup();
+
+    let mut scaffold = format!(
+        "# {tag}.yml, scaffolded from the {default}.yml keys, please translate all values\n",
+        tag = tag,
+        default = storage.default_locale()
+    );
+    let mut previous_path: &[String] = &[];
+    for path in paths.iter() {
+        let overlap = path
+            .iter()
+            .zip(previous_path)
+            .take_while(|(a, b)| a == b)
+            .count();
+        for (depth, key) in path.iter().enumerate().skip(overlap) {
+            scaffold.push_str(&format!(
+                "{}{}:{}\n",
+                "  ".repeat(depth),
+                yaml_key(key),
+                if depth + 1 == path.len() { " \"\"" } else { "" }
+            ));
+        }
+        previous_path = path;
+    }
+    scaffold
+}
+
+fn yaml_key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        key.to_owned()
+    } else {
+        format!("{:?}", key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_references_ignore_method_calls() {
+        assert_eq!(
+            template_references_in(
+                "<title>{{ page.locale.strings().site.name }}</title>
+{% for (locale, name) in page.locale.strings().site.locales.iter() %}{% endfor %}
+{{ strings(). }}"
+            ),
+            vec![
+                vec!["site".to_owned(), "name".to_owned()],
+                vec!["site".to_owned(), "locales".to_owned()],
+            ]
+        );
+    }
+
+    #[test]
+    fn templates_with_byte_order_mark_are_read() {
+        let dir = std::env::temp_dir().join("plabayo_news_builder_bom_templates");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("base.html"),
+            "\u{feff}<title>{{ page.locale.strings().site.name }}</title>",
+        )
+        .unwrap();
+        assert_eq!(
+            template_references(&dir).unwrap(),
+            vec![vec!["site".to_owned(), "name".to_owned()]]
+        );
+    }
+
+    #[test]
+    fn scaffold_contains_every_default_key() {
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "site:\n  name: News\n  nav:\n    login: Log in\n    logout: Log out\npage:\n  faq:\n    intro:\n      value: \"**FAQ**\"\n      format: md\n",
+            ),
+            ("nl", "site:\n  name: Nieuws\n"),
+        ])
+        .unwrap();
+        let references = [
+            vec!["site".to_owned(), "name".to_owned()],
+            vec!["site".to_owned(), "motto".to_owned()],
+            // conflicts with the site.name string of the default locale
+            vec!["site".to_owned(), "name".to_owned(), "short".to_owned()],
+        ];
+        let scaffold = format_scaffold("pt-br", &storage, &references[..]);
+        assert_eq!(
+            scaffold,
+            r#"# pt-br.yml, scaffolded from the en.yml keys, please translate all values
+page:
+  faq:
+    intro: ""
+site:
+  motto: ""
+  name: ""
+  nav:
+    login: ""
+    logout: ""
+"#
+        );
+        let default_keys: Vec<String> = storage
+            .get_default()
+            .unwrap()
+            .iter()
+            .map(|pair| pair.path.join("."))
+            .collect();
+        // the scaffold is a valid locale file on its own
+        let keys: Vec<String> = Storage::from_yaml_sources(&[("pt-br", scaffold.as_str())])
+            .unwrap()
+            .get_default()
+            .unwrap()
+            .iter()
+            .map(|pair| pair.path.join("."))
+            .collect();
+        for key in default_keys {
+            assert!(keys.contains(&key), "scaffold misses key {}", key);
+        }
+    }
+}
@@ -0,0 +1,615 @@
+// Plabayo News
+// Copyright (C) 2021  Glen Henri J. De Cauwsemaecker
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::i18n::config::I18n;
+use crate::i18n::locales::{Storage, StringValuePathPair};
+
+/// conventional accessibility strings (and their english fallback) under the `a11y` key,
+/// which the default locale is expected to define
+pub const A11Y_KEYS: &[(&str, &str)] = &[
+    ("skip_to_content", "Skip to content"),
+    ("main_nav", "Main navigation"),
+    ("user_nav", "User navigation"),
+    ("footer_nav", "Footer navigation"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// worth knowing (e.g. a string falling back to the default locale)
+    Info,
+    /// a problem reported as cargo warning, failing the build in strict mode
+    Warning,
+    /// a problem always failing the build
+    Error,
+}
+
+/// a problem found in the locale strings, for the given locale and (dotted) key path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub locale: String,
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// the (configured) checks to validate the locale strings with
+#[derive(Debug, Default, Clone)]
+pub struct ValidationRules {
+    /// dotted key paths of strings (including the nested ones) which may not contain html
+    pub plain_text: Vec<String>,
+    /// whether or not the default locale is expected to define the conventional `a11y` keys
+    pub a11y: bool,
+    /// whether or not to check that `{name}` placeholders are spelled consistently
+    pub placeholders: bool,
+    /// whether or not placeholders which differ between locales are errors, as their strings
+    /// are interpolated by generated methods taking the placeholders of the default locale
+    pub interpolation: bool,
+    /// whether or not strings of a locale which the default locale doesn't define are errors,
+    /// rather than skipped with a warning
+    pub unknown_keys: bool,
+}
+
+impl ValidationRules {
+    pub fn from_config(cfg: &I18n) -> ValidationRules {
+        ValidationRules {
+            plain_text: cfg.plain_text.clone(),
+            a11y: true,
+            placeholders: cfg.check_placeholders,
+            interpolation: cfg.interpolation,
+            unknown_keys: cfg.deny_unknown_keys,
+        }
+    }
+}
+
+impl Storage {
+    /// find all problems with the locale strings, without reporting them,
+    /// sorted by locale (default locale first) and path
+    pub fn validate(&self, rules: ValidationRules) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let default_locale = self.default_locale();
+        let default_pairs: Vec<StringValuePathPair> = self
+            .get_default()
+            .map(|locales| locales.iter().collect())
+            .unwrap_or_default();
+
+        if rules.a11y {
+            for (key, fallback) in A11Y_KEYS {
+                let path = format!("a11y.{}", key);
+                if !default_pairs.iter().any(|pair| pair.path.join(".") == path) {
+                    diagnostics.push(Diagnostic {
+                        severity: Severity::Warning,
+                        locale: default_locale.to_owned(),
+                        message: format!(
+                            "default locale {locale} is missing accessibility string '{path}', using {fallback:?} for all locales; please add it to {locale}.yml!",
+                            locale = default_locale,
+                            path = path,
+                            fallback = fallback,
+                        ),
+                        path,
+                    });
+                }
+            }
+        }
+
+        if rules.placeholders {
+            diagnostics.extend(similar_placeholders(default_locale, &default_pairs[..]));
+        }
+
+        let locales = std::iter::once(default_locale).chain(
+            self.all_locales()
+                .filter(|locale| *locale != default_locale),
+        );
+        for locale in locales {
+            let pairs: Vec<StringValuePathPair> = self
+                .get(locale)
+                .map(|locales| locales.iter().collect())
+                .unwrap_or_default();
+
+            for pair in pairs.iter() {
+                let path = pair.path.join(".");
+                if pair.bytes.is_none()
+                    && is_plain_text(&rules.plain_text[..], &path)
+                    && contains_html_tag(&pair.value)
+                {
+                    diagnostics.push(Diagnostic {
+                        severity: Severity::Error,
+                        locale: locale.to_owned(),
+                        message: format!(
+                            "plain text string '{}' of locale {} contains html: {:?}",
+                            path, locale, pair.value
+                        ),
+                        path,
+                    });
+                }
+            }
+            if locale == default_locale {
+                continue;
+            }
+
+            let default_by_path: HashMap<&[String], &StringValuePathPair> = default_pairs
+                .iter()
+                .map(|pair| (&pair.path[..], pair))
+                .collect();
+            let locale_by_path: HashMap<&[String], &StringValuePathPair> =
+                pairs.iter().map(|pair| (&pair.path[..], pair)).collect();
+
+            let mut locale_diagnostics = Vec::new();
+            for pair in pairs.iter() {
+                let path = pair.path.join(".");
+                match default_by_path.get(&pair.path[..]) {
+                    Some(default_pair) => {
+                        if (rules.placeholders || rules.interpolation) && pair.bytes.is_none() {
+                            locale_diagnostics.extend(mismatched_placeholders(
+                                if rules.interpolation {
+                                    Severity::Error
+                                } else {
+                                    Severity::Warning
+                                },
+                                locale,
+                                &path,
+                                &default_pair.value,
+                                &pair.value,
+                            ));
+                        }
+                        if pair.bytes.is_some() != default_pair.bytes.is_some() {
+                            locale_diagnostics.push(Diagnostic {
+                                severity: Severity::Warning,
+                                locale: locale.to_owned(),
+                                message: format!(
+                                    "locale {locale} defines '{path}' as a different type (string or base64 bytes) than the default locale; the translation is discarded, please align {locale}.yml with the default locale!",
+                                    locale = locale,
+                                    path = path,
+                                ),
+                                path,
+                            });
+                        }
+                    }
+                    // accessibility strings are used even when the default locale misses them
+                    None if pair.path.len() == 2
+                        && pair.path[0] == "a11y"
+                        && A11Y_KEYS.iter().any(|(key, _)| *key == pair.path[1]) => {}
+                    None => {
+                        // a string nested deeper than a default string, or given for a default object,
+                        // is not a typo but a structural mistake
+                        let conflict = default_pairs.iter().find(|default_pair| {
+                            pair.path.starts_with(&default_pair.path)
+                                || default_pair.path.starts_with(&pair.path)
+                        });
+                        locale_diagnostics.push(match conflict {
+                            Some(default_pair) => Diagnostic {
+                                severity: Severity::Error,
+                                locale: locale.to_owned(),
+                                message: if pair.path.len() > default_pair.path.len() {
+                                    format!(
+                                        "locale {locale} defines '{default_path}' as an object (with '{path}') while the default locale defines it as a string, please align {locale}.yml with the default locale!",
+                                        locale = locale,
+                                        path = path,
+                                        default_path = default_pair.path.join("."),
+                                    )
+                                } else {
+                                    format!(
+                                        "locale {locale} defines '{path}' as a string while the default locale defines it as an object (with '{default_path}'), please align {locale}.yml with the default locale!",
+                                        locale = locale,
+                                        path = path,
+                                        default_path = default_pair.path.join("."),
+                                    )
+                                },
+                                path,
+                            },
+                            None if rules.unknown_keys => Diagnostic {
+                                severity: Severity::Error,
+                                locale: locale.to_owned(),
+                                message: format!(
+                                    "string '{}' of locale {}{} is not found in the default locale, please fix or remove it!",
+                                    path,
+                                    locale,
+                                    self.source_path(locale)
+                                        .map(|source| format!(" ({})", source.display()))
+                                        .unwrap_or_default()
+                                ),
+                                path,
+                            },
+                            None => Diagnostic {
+                                severity: Severity::Warning,
+                                locale: locale.to_owned(),
+                                message: format!(
+                                    "string '{path}' of locale {locale} is not found in the default locale and is skipped, please remove it from {locale}.yml!",
+                                    path = path,
+                                    locale = locale
+                                ),
+                                path,
+                            },
+                        });
+                    }
+                }
+            }
+            for default_pair in default_pairs.iter() {
+                if !locale_by_path.contains_key(&default_pair.path[..]) {
+                    let path = default_pair.path.join(".");
+                    locale_diagnostics.push(Diagnostic {
+                        severity: Severity::Info,
+                        locale: locale.to_owned(),
+                        message: format!(
+                            "string '{}' is missing for locale {}, using the default locale's string",
+                            path, locale
+                        ),
+                        path,
+                    });
+                }
+            }
+            locale_diagnostics.sort_by(|a, b| a.path.cmp(&b.path));
+            diagnostics.extend(locale_diagnostics);
+        }
+        diagnostics
+    }
+}
+
+/// placeholder names of the default locale which differ by a single edit (e.g. `{username}`
+/// and `{user_name}`), and are thus likely to name the same concept
+fn similar_placeholders(locale: &str, pairs: &[StringValuePathPair]) -> Vec<Diagnostic> {
+    let mut names: Vec<(String, String)> = Vec::new();
+    for pair in pairs.iter().filter(|pair| pair.bytes.is_none()) {
+        let path = pair.path.join(".");
+        for name in named_placeholders(&pair.value) {
+            if !names.iter().any(|(other, _)| *other == name) {
+                names.push((name, path.clone()));
+            }
+        }
+    }
+    let mut diagnostics = Vec::new();
+    for (index, (name, path)) in names.iter().enumerate() {
+        for (other_name, other_path) in names[index + 1..].iter() {
+            if is_single_edit(name, other_name) {
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Warning,
+                    locale: locale.to_owned(),
+                    message: format!(
+                        "placeholder '{{{}}}' of '{}' and placeholder '{{{}}}' of '{}' in locale {} are spelled suspiciously similar, please use a single name",
+                        name, path, other_name, other_path, locale
+                    ),
+                    path: other_path.clone(),
+                });
+            }
+        }
+    }
+    diagnostics
+}
+
+/// placeholders found in only the default or only the translated version of a string
+fn mismatched_placeholders(
+    severity: Severity,
+    locale: &str,
+    path: &str,
+    default_value: &str,
+    value: &str,
+) -> Vec<Diagnostic> {
+    let default_names = named_placeholders(default_value);
+    let names = named_placeholders(value);
+    let missing = default_names.iter().filter(|name| !names.contains(name));
+    let unknown = names.iter().filter(|name| !default_names.contains(name));
+    missing
+        .map(|name| (name, "the default locale but not in locale"))
+        .chain(unknown.map(|name| (name, "locale")))
+        .map(|(name, found_in)| Diagnostic {
+            severity,
+            locale: locale.to_owned(),
+            path: path.to_owned(),
+            message: format!(
+                "placeholder '{{{}}}' of '{}' is only found in {} {}, please align {}.yml with the default locale!",
+                name,
+                path,
+                found_in,
+                locale,
+                locale
+            ),
+        })
+        .collect()
+}
+
+/// names of the `{name}` placeholders of the given string, in order of first occurrence,
+/// positional placeholders (`{}`) are not named
+pub fn named_placeholders(value: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1..];
+        if let Some(name) = rest.find('}').map(|end| &rest[..end]).filter(|name| {
+            !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        }) {
+            if !names.iter().any(|other| other == name) {
+                names.push(name.to_owned());
+            }
+        }
+    }
+    names
+}
+
+/// true if both (different) names are an insertion, deletion or substitution apart
+fn is_single_edit(a: &str, b: &str) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if long.len() - short.len() > 1 || short == long {
+        return false;
+    }
+    let prefix = short
+        .iter()
+        .zip(long.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix_start = if short.len() == long.len() {
+        prefix + 1
+    } else {
+        prefix
+    };
+    short[suffix_start.min(short.len())..] == long[prefix + 1..]
+}
+
+fn is_plain_text(plain_text_paths: &[String], path: &str) -> bool {
+    plain_text_paths.iter().any(|plain_text_path| {
+        path == plain_text_path
+            || (path.starts_with(plain_text_path.as_str())
+                && path[plain_text_path.len()..].starts_with('.'))
+    })
+}
+
+/// detect `<tag>`, `</tag>` and `<tag attr>` patterns
+fn contains_html_tag(s: &str) -> bool {
+    s.match_indices('<').any(|(index, _)| {
+        let tag = s[index + 1..].strip_prefix('/').unwrap_or(&s[index + 1..]);
+        tag.starts_with(|c: char| c.is_ascii_alphabetic()) && tag.contains('>')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn warnings(diagnostics: &[Diagnostic]) -> Vec<(&str, &str)> {
+        diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Warning)
+            .map(|d| (d.locale.as_str(), d.path.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn plain_text_values_containing_html_are_rejected() {
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "email:\n  subject: Welcome\n  body: Hello\nsite:\n  title: <b>News</b>\n",
+            ),
+            (
+                "nl",
+                "email:\n  subject: Welkom\n  body: Hallo <b>daar</b>\nsite:\n  title: Nieuws\n",
+            ),
+        ])
+        .unwrap();
+        let errors = |plain_text: &[&str]| {
+            storage
+                .validate(ValidationRules {
+                    plain_text: plain_text.iter().map(|s| s.to_string()).collect(),
+                    ..ValidationRules::default()
+                })
+                .into_iter()
+                .filter(|d| d.severity == Severity::Error)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            errors(&["email"]),
+            vec![Diagnostic {
+                severity: Severity::Error,
+                locale: "nl".to_owned(),
+                path: "email.body".to_owned(),
+                message: "plain text string 'email.body' of locale nl contains html: \"Hallo <b>daar</b>\""
+                    .to_owned(),
+            }]
+        );
+        // html is fine for strings not marked as plain text
+        assert!(errors(&["email.subject"]).is_empty());
+        // prefixes only match whole path segments
+        assert!(errors(&["site.tit"]).is_empty());
+    }
+
+    #[test]
+    fn similar_and_mismatched_placeholders_are_flagged() {
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "comment:\n  by: by {username}\n  reply: \"{user_name} replied to {count} {}\"\nitem:\n  points: \"{count} points\"\n",
+            ),
+            (
+                "nl",
+                "comment:\n  by: door {username}\n  reply: \"{user} antwoordde op {count} {}\"\nitem:\n  points: \"{count} punten\"\n",
+            ),
+        ])
+        .unwrap();
+        let rules = ValidationRules {
+            placeholders: true,
+            ..ValidationRules::default()
+        };
+        let diagnostics = storage.validate(rules);
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|d| (d.locale.as_str(), d.path.as_str(), d.message.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "en",
+                    "comment.reply",
+                    "placeholder '{username}' of 'comment.by' and placeholder '{user_name}' of 'comment.reply' in locale en are spelled suspiciously similar, please use a single name"
+                ),
+                (
+                    "nl",
+                    "comment.reply",
+                    "placeholder '{user_name}' of 'comment.reply' is only found in the default locale but not in locale nl, please align nl.yml with the default locale!"
+                ),
+                (
+                    "nl",
+                    "comment.reply",
+                    "placeholder '{user}' of 'comment.reply' is only found in locale nl, please align nl.yml with the default locale!"
+                ),
+            ]
+        );
+        assert!(storage.validate(ValidationRules::default()).is_empty());
+    }
+
+    #[test]
+    fn single_edits_are_detected() {
+        assert!(is_single_edit("username", "user_name"));
+        assert!(is_single_edit("user_name", "username"));
+        assert!(is_single_edit("count", "counts"));
+        assert!(is_single_edit("item", "iten"));
+        assert!(!is_single_edit("count", "count"));
+        assert!(!is_single_edit("count", "amount"));
+        assert!(!is_single_edit("user", "username"));
+    }
+
+    #[test]
+    fn html_tags_are_detected() {
+        assert!(contains_html_tag("<b>bold</b>"));
+        assert!(contains_html_tag("a <a href=\"/\">link"));
+        assert!(contains_html_tag("</p>"));
+        assert!(!contains_html_tag("1 < 2 and 3 > 2"));
+        assert!(!contains_html_tag("<3"));
+        assert!(!contains_html_tag("<open"));
+    }
+
+    #[test]
+    fn structure_conflicts_are_detected_in_both_directions() {
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "nav:\n  submit: submit\n  title: News\n  user:\n    login: login\n",
+            ),
+            (
+                "nl",
+                "nav:\n  submit:\n    label: deel\n  title: Nieuws\n  user: gebruiker\n",
+            ),
+        ])
+        .unwrap();
+        let diagnostics = storage.validate(ValidationRules::default());
+        let errors: Vec<(&str, &str, &str)> = diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .map(|d| (d.locale.as_str(), d.path.as_str(), d.message.as_str()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    "nl",
+                    "nav.submit.label",
+                    "locale nl defines 'nav.submit' as an object (with 'nav.submit.label') while the default locale defines it as a string, please align nl.yml with the default locale!"
+                ),
+                (
+                    "nl",
+                    "nav.user",
+                    "locale nl defines 'nav.user' as a string while the default locale defines it as an object (with 'nav.user.login'), please align nl.yml with the default locale!"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_strings_are_informational_and_unknown_strings_warnings() {
+        let storage = Storage::from_yaml_sources(&[
+            ("en", "site:\n  name: News\n  title: Welcome\n"),
+            ("nl", "site:\n  name: Nieuws\n  motto: Blijf nieuwsgierig\n"),
+        ])
+        .unwrap();
+        let diagnostics = storage.validate(ValidationRules::default());
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|d| (d.severity, d.locale.as_str(), d.path.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Severity::Warning, "nl", "site.motto"),
+                (Severity::Info, "nl", "site.title"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_strings_are_errors_when_denied() {
+        let dir = std::env::temp_dir().join("plabayo_news_builder_unknown_keys");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("en.yml"),
+            "site:\n  name: News\n  title: Welcome\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("nl.yml"),
+            "site:\n  name: Nieuws\n  titel: Welkom\n  motto: Blijf nieuwsgierig\n",
+        )
+        .unwrap();
+        let storage = Storage::load(dir.to_str().unwrap(), &["en", "nl"]).unwrap();
+        let rules = ValidationRules {
+            unknown_keys: true,
+            ..ValidationRules::default()
+        };
+        let errors: Vec<String> = storage
+            .validate(rules)
+            .into_iter()
+            .filter(|d| d.severity == Severity::Error)
+            .map(|d| d.message)
+            .collect();
+        let nl_path = dir.join("nl.yml");
+        assert_eq!(
+            errors,
+            vec![
+                format!("string 'site.motto' of locale nl ({}) is not found in the default locale, please fix or remove it!", nl_path.display()),
+                format!("string 'site.titel' of locale nl ({}) is not found in the default locale, please fix or remove it!", nl_path.display()),
+            ]
+        );
+    }
+
+    #[test]
+    fn a11y_keys_are_expected_in_the_default_locale() {
+        let storage = Storage::from_yaml_sources(&[
+            (
+                "en",
+                "a11y:\n  skip_to_content: Skip\n  main_nav: Main\n  user_nav: User\n",
+            ),
+            ("nl", "a11y:\n  footer_nav: Voettekst\n"),
+        ])
+        .unwrap();
+        let rules = ValidationRules {
+            a11y: true,
+            ..ValidationRules::default()
+        };
+        assert_eq!(
+            warnings(&storage.validate(rules)[..]),
+            vec![("en", "a11y.footer_nav")]
+        );
+        assert!(warnings(&storage.validate(ValidationRules::default())[..]).is_empty());
+    }
+}
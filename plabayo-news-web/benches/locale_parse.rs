@@ -0,0 +1,35 @@
+// Plabayo News
+// Copyright (C) 2021  Glen Henri J. De Cauwsemaecker
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use plabayo_news_web::site::l18n::locales::Locale;
+
+fn locale_parse(c: &mut Criterion) {
+    c.bench_function("locale try_from all tags", |b| {
+        b.iter(|| {
+            for locale in Locale::all() {
+                let _ = black_box(Locale::try_from(black_box(locale.as_str())));
+            }
+        })
+    });
+    c.bench_function("locale try_from unknown tag", |b| {
+        b.iter(|| black_box(Locale::try_from(black_box(" Xx-Unknown "))))
+    });
+}
+
+criterion_group!(benches, locale_parse);
+criterion_main!(benches);
@@ -32,6 +32,10 @@ struct Opt {
     /// interface to bind to
     #[structopt(short, long, default_value = "127.0.0.1:8080")]
     interface: String,
+
+    /// scheme and host the site is served from, used for its absolute urls
+    #[structopt(long, default_value = "https://news.plabayo.tech")]
+    origin: String,
 }
 
 #[actix_web::main]
@@ -46,7 +50,7 @@ async fn main() -> Result<()> {
     env_logger::init();
 
     // create app state used by all routes
-    let state = web::Data::new(AppState::new());
+    let state = web::Data::new(AppState::new(&opt.origin));
 
     // start http server
     HttpServer::new(move || {
@@ -54,8 +58,8 @@ async fn main() -> Result<()> {
             .app_data(state.clone())
             .wrap(middleware::Logger::default())
             .wrap(middleware::Compress::default())
-            .wrap(pn_middleware::Cache::default())
-            .wrap(pn_middleware::SiteInfo::default())
+            .wrap(pn_middleware::Cache)
+            .wrap(pn_middleware::SiteInfo)
             .wrap(middleware::NormalizePath::new(
                 middleware::normalize::TrailingSlash::Trim,
             ))
@@ -21,7 +21,8 @@ include!(concat!(env!("OUT_DIR"), "/generated.rs"));
 
 use actix_web::dev::HttpServiceFactory;
 
-pub const ROOT: &str = "assets";
+// generated from the i18n pages `assets_root` in Cargo.toml
+pub use crate::site::l18n::pages::ASSETS_ROOT as ROOT;
 
 pub fn factory() -> impl HttpServiceFactory + 'static {
     let generated = generate();
@@ -18,7 +18,6 @@ use std::collections::BTreeMap;
 use std::time::SystemTime;
 
 use actix_web::dev::Payload;
-use actix_web::http::header::ACCEPT_LANGUAGE;
 use actix_web::web::Query;
 use actix_web::{Error, FromRequest, HttpRequest};
 use anyhow::Result;
@@ -27,27 +26,17 @@ use futures::future::{ready, Ready};
 use plabayo_news_data::models::{User, UserID, UserState};
 
 use crate::site::l18n::locales::Locale;
+use crate::site::l18n::pages::negotiate_with_preference;
 
 #[derive(Default)]
 pub struct Session {
-    headers: Headers,
+    locale: Locale,
     user_ref: Option<UserReference>,
 }
 
 impl Session {
     pub fn locale(&self) -> Locale {
-        if let Some(locale) = self
-            .user()
-            .as_ref()
-            .and_then(|user| user.locale.as_ref())
-            .and_then(|s| Locale::try_from(s.as_str()).ok())
-        {
-            return locale;
-        }
-        if let Some(locale) = self.headers.locale {
-            return locale;
-        }
-        Locale::default()
+        self.locale
     }
 
     pub fn user(&self) -> Option<User> {
@@ -58,64 +47,36 @@ impl Session {
     }
 }
 
-#[derive(Default)]
-struct Headers {
-    locale: Option<Locale>,
-}
-
 struct UserReference {
     user_id: UserID,
     last_fetch_time: SystemTime,
 }
 
-impl Headers {
-    fn from_request(req: &HttpRequest) -> Headers {
-        const HEADER_ACCEPT_LANGUAGE_VALUE_ANY: &str = "*";
-
-        let locale = req
-            .headers()
-            .get(ACCEPT_LANGUAGE)
-            .and_then(|hv| hv.to_str().ok())
-            .unwrap_or(HEADER_ACCEPT_LANGUAGE_VALUE_ANY)
-            .split(&[',', '-', '.', ';'])
-            .map(|language| language.trim())
-            .filter_map(|s| {
-                if s == HEADER_ACCEPT_LANGUAGE_VALUE_ANY {
-                    Some(Locale::default())
-                } else {
-                    Locale::try_from(s).ok()
-                }
-            })
-            .next();
-
-        Headers { locale }
-    }
-}
-
 impl FromRequest for Session {
     type Error = Error;
     type Future = Ready<Result<Self, Error>>;
     type Config = SessionConfig;
 
     fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
-        let headers = Headers::from_request(req);
         // TODO: delete this hack once we have a reg/login system
         let query_opt: Option<Query<BTreeMap<String, String>>> =
             Query::from_query(req.query_string()).ok();
-        if let Some(user_id) = query_opt.as_ref().and_then(|q| q.get("id")) {
-            if let Ok(user_id_num) = user_id.parse::<UserID>() {
-                return ready(Ok(Session {
-                    headers,
-                    user_ref: Some(UserReference {
-                        user_id: user_id_num,
-                        last_fetch_time: SystemTime::UNIX_EPOCH,
-                    }),
-                }));
-            }
-        }
+        let user_ref = query_opt
+            .as_ref()
+            .and_then(|q| q.get("id"))
+            .and_then(|user_id| user_id.parse::<UserID>().ok())
+            .map(|user_id| UserReference {
+                user_id,
+                last_fetch_time: SystemTime::UNIX_EPOCH,
+            });
+        let user_locale = user_ref
+            .as_ref()
+            .and_then(|user_ref| user_ref.user().ok())
+            .and_then(|user| user.locale)
+            .and_then(|s| Locale::try_from(s.as_str()).ok());
         ready(Ok(Session {
-            headers,
-            user_ref: None,
+            locale: negotiate_with_preference(req, user_locale).0,
+            user_ref,
         }))
     }
 }
@@ -143,3 +104,65 @@ impl UserReference {
 
 #[derive(Clone, Default)]
 pub struct SessionConfig {}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::header::{ACCEPT_LANGUAGE, COOKIE};
+    use actix_web::rt::System;
+    use actix_web::{test, web, App, HttpResponse};
+
+    use super::*;
+
+    async fn session_locale_handler(session: Session) -> HttpResponse {
+        HttpResponse::Ok().body(session.locale().as_str())
+    }
+
+    #[test]
+    fn session_locale_is_negotiated_like_the_resolved_locale() {
+        System::new("test").block_on(async {
+            let mut app =
+                test::init_service(App::new().route("/", web::get().to(session_locale_handler)))
+                    .await;
+            for (uri, cookie, accept_language, expected) in [
+                ("/", None, None, "en"),
+                ("/", None, Some("nl-BE, fr;q=0.8"), "nl"),
+                ("/", Some("lang=de"), Some("nl-BE, fr;q=0.8"), "de"),
+                ("/?id=42", Some("lang=de"), Some("nl"), "de"),
+                ("/?lang=es", Some("lang=de"), Some("nl"), "es"),
+            ] {
+                let mut req = test::TestRequest::get().uri(uri);
+                if let Some(cookie) = cookie {
+                    req = req.header(COOKIE, cookie);
+                }
+                if let Some(accept_language) = accept_language {
+                    req = req.header(ACCEPT_LANGUAGE, accept_language);
+                }
+                let body = test::read_response(&mut app, req.to_request()).await;
+                assert_eq!(body, expected, "{} {:?} {:?}", uri, cookie, accept_language);
+            }
+        });
+    }
+
+    #[test]
+    fn negotiate_with_default_prefers_header_match() {
+        assert_eq!(
+            Locale::negotiate_with_default("fr-BE;q=0.5, nl-BE, en;q=0.8", Locale::De),
+            Locale::Nl
+        );
+        assert_eq!(Locale::negotiate_with_default("*", Locale::De), Locale::De);
+    }
+
+    #[test]
+    fn negotiate_with_default_falls_back_to_tenant_default() {
+        let tenant_default = Locale::Nl;
+        assert_ne!(tenant_default, Locale::default());
+        assert_eq!(
+            Locale::negotiate_with_default("ja-JP, zh;q=0.9, en;q=0", tenant_default),
+            tenant_default
+        );
+        assert_eq!(
+            Locale::from_accept_language("ja-JP, zh;q=0.9, en;q=0"),
+            Locale::default()
+        );
+    }
+}
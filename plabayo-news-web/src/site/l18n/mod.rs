@@ -31,3 +31,432 @@
 
 pub mod locales;
 pub mod pages;
+
+#[cfg(test)]
+mod tests {
+    use super::locales::{locale_diagnostics, Direction, Locale};
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn strings_render_json_context() {
+        let strings = Locale::En.strings();
+        assert_eq!(
+            strings
+                .render_json(
+                    "page.search.no_results",
+                    &serde_json::json!({ "q": "rust", "page": 2 })
+                )
+                .unwrap(),
+            "We found no stories matching \"rust\"."
+        );
+        // unnamed placeholders are not rendered
+        assert_eq!(
+            strings
+                .render_json("relative_time.past", &serde_json::json!({}))
+                .unwrap(),
+            "{} ago"
+        );
+
+        for context in [
+            serde_json::json!({ "query": "rust" }),
+            serde_json::json!({ "q": null }),
+        ] {
+            assert_eq!(
+                strings
+                    .render_json("page.search.no_results", &context)
+                    .unwrap_err()
+                    .to_string(),
+                "missing field 'q' to render string 'page.search.no_results'"
+            );
+        }
+        assert_eq!(
+            strings
+                .render_json("page.search.none", &serde_json::json!({}))
+                .unwrap_err()
+                .to_string(),
+            "unknown string 'page.search.none'"
+        );
+    }
+
+    #[cfg(feature = "icu")]
+    #[test]
+    fn locales_round_trip_through_icu_language_identifiers() {
+        for locale in Locale::all() {
+            let langid: icu_locid::LanguageIdentifier = locale.into();
+            assert_eq!(langid.to_string().parse::<Locale>(), Ok(locale));
+        }
+    }
+
+    #[test]
+    fn parse_query_present() {
+        assert_eq!(
+            Locale::parse_query("q=rust&lang=nl", "lang"),
+            Some(Locale::Nl)
+        );
+        assert_eq!(Locale::parse_query("?lang=FR", "lang"), Some(Locale::Fr));
+        // duplicate params, first one wins
+        assert_eq!(
+            Locale::parse_query("lang=de&lang=es", "lang"),
+            Some(Locale::De)
+        );
+        assert_eq!(Locale::parse_query("lang=xx", "lang"), None);
+    }
+
+    #[test]
+    fn parse_query_absent() {
+        assert_eq!(Locale::parse_query("", "lang"), None);
+        assert_eq!(Locale::parse_query("q=nl&language=nl", "lang"), None);
+    }
+
+    #[test]
+    fn parse_query_url_encoded() {
+        assert_eq!(Locale::parse_query("lang=%65%73", "lang"), Some(Locale::Es));
+        assert_eq!(Locale::parse_query("lang=+en+", "lang"), Some(Locale::En));
+        assert_eq!(Locale::parse_query("l%61ng=de", "lang"), Some(Locale::De));
+        assert_eq!(Locale::parse_query("lang=%FF", "lang"), None);
+    }
+
+    #[test]
+    fn from_subdomain() {
+        assert_eq!(Locale::from_subdomain("fr.example.com"), Some(Locale::Fr));
+        assert_eq!(Locale::from_subdomain("NL.example.com."), Some(Locale::Nl));
+        assert_eq!(
+            Locale::from_subdomain("de.example.com:8080"),
+            Some(Locale::De)
+        );
+        // apex domain
+        assert_eq!(Locale::from_subdomain("example.com"), None);
+        assert_eq!(Locale::from_subdomain("es.com"), None);
+        assert_eq!(Locale::from_subdomain("localhost:8080"), None);
+        // www is not a locale
+        assert_eq!(Locale::from_subdomain("www.example.com"), None);
+        assert_eq!(Locale::from_subdomain("www.fr.example.com"), None);
+        assert_eq!(Locale::from_subdomain("[::1]:8080"), None);
+    }
+
+    #[test]
+    fn truncate_within_the_grapheme_budget() {
+        assert_eq!(Locale::En.truncate("Plabayo News", 20), "Plabayo News");
+        assert_eq!(Locale::En.truncate("Plabayo News", 10), "Plabayo N…");
+        // trailing whitespace is trimmed before the ellipsis
+        assert_eq!(Locale::En.truncate("Plabayo News", 9), "Plabayo…");
+        assert_eq!(Locale::En.truncate("Plabayo News", 1), "…");
+        // the ellipsis itself does not fit
+        assert_eq!(Locale::En.truncate("Plabayo News", 0), "");
+    }
+
+    #[test]
+    fn resolve_returns_consistent_pair() {
+        let (locale, strings) = Locale::resolve("NL");
+        assert_eq!(locale, Locale::Nl);
+        assert!(std::ptr::eq(strings, Locale::Nl.strings()));
+
+        let (locale, strings) = Locale::resolve("xx-unknown");
+        assert_eq!(locale, Locale::default());
+        assert!(std::ptr::eq(strings, Locale::default().strings()));
+    }
+
+    #[test]
+    fn alternate_links_excluding_current_locale() {
+        let links = Locale::alternate_links_excluding("/faq", Locale::Nl);
+        assert_eq!(links.len(), Locale::all().count());
+        assert!(links.iter().all(|link| link.hreflang != "nl"));
+        assert!(links
+            .iter()
+            .any(|link| link.hreflang == "fr" && link.href == "/faq?lang=fr"));
+
+        // x-default is kept, even for the default locale
+        let links = Locale::alternate_links_excluding("faq", Locale::default());
+        assert!(links
+            .iter()
+            .all(|link| link.hreflang != Locale::default().as_str()));
+        assert_eq!(links.last().unwrap().hreflang, "x-default");
+        assert_eq!(links.last().unwrap().href, "/faq");
+    }
+
+    #[test]
+    fn canonical_url_of_locale() {
+        assert_eq!(
+            Locale::Fr.canonical_url("https://news.plabayo.tech/", "/faq"),
+            "https://news.plabayo.tech/faq?lang=fr"
+        );
+        assert_eq!(
+            Locale::En.canonical_url("http://localhost:8888", ""),
+            "http://localhost:8888/?lang=en"
+        );
+    }
+
+    #[test]
+    fn variant_seed_is_deterministic() {
+        let seed = Locale::Nl.variant_seed("user-42", "signup-copy");
+        assert_eq!(seed, Locale::Nl.variant_seed("user-42", "signup-copy"));
+        // pinned, so variants remain stable across runs and builds
+        assert_eq!(seed, 4216691511555733138);
+        assert_ne!(seed, Locale::Fr.variant_seed("user-42", "signup-copy"));
+        assert_ne!(seed, Locale::Nl.variant_seed("user-43", "signup-copy"));
+        assert_ne!(seed, Locale::Nl.variant_seed("user-42", "signup-cta"));
+        assert_ne!(
+            Locale::Nl.variant_seed("ab", "c"),
+            Locale::Nl.variant_seed("a", "bc")
+        );
+    }
+
+    #[test]
+    fn try_from_ignores_case_and_whitespace() {
+        for locale in Locale::all() {
+            assert_eq!(Locale::try_from(locale.as_str()).unwrap(), locale);
+            let tag = format!(" {} ", locale.as_str().to_uppercase());
+            assert_eq!(Locale::try_from(tag.as_str()).unwrap(), locale);
+        }
+        assert!(Locale::try_from("\u{00E9}s").is_err());
+        assert!(Locale::try_from("").is_err());
+        assert!(Locale::try_from("nl-be").is_err());
+    }
+
+    #[test]
+    fn from_str_is_strict() {
+        assert_eq!("NL".parse::<Locale>(), Ok(Locale::Nl));
+        let err = "xx".parse::<Locale>().unwrap_err();
+        assert_eq!(err.0, "xx");
+        assert_eq!(
+            err.to_string(),
+            "unknown locale 'xx', expected one of: de, en, es, fr, nl"
+        );
+        // unlike the lenient conversion, which falls back to the default locale
+        assert_eq!(Locale::from("xx"), Locale::En);
+    }
+
+    #[test]
+    fn direction_displays_as_html_dir_attribute() {
+        assert_eq!(Locale::En.direction(), Direction::Ltr);
+        assert_eq!(Locale::Nl.direction().to_string(), "ltr");
+        assert_eq!(Direction::Rtl.to_string(), "rtl");
+    }
+
+    #[test]
+    fn html_lang_meta() {
+        assert_eq!(Locale::Fr.html_lang_meta(), "lang=\"fr\" dir=\"ltr\"");
+        assert_eq!(Locale::Fr.hreflang(), "fr");
+    }
+
+    #[test]
+    fn format_percent_per_locale() {
+        assert_eq!(Locale::En.format_percent(12.5, 1), "12.5%");
+        assert_eq!(Locale::Fr.format_percent(12.5, 1), "12,5\u{a0}%");
+        assert_eq!(Locale::Nl.format_percent(12.5, 2), "12,50%");
+        assert_eq!(Locale::Fr.format_percent(-3.25, 1), "-3,2\u{a0}%");
+        assert_eq!(Locale::En.format_percent(-12.0, 0), "-12%");
+        // rounding to nearest, ties to even
+        assert_eq!(Locale::En.format_percent(99.96, 1), "100.0%");
+        assert_eq!(Locale::En.format_percent(2.5, 0), "2%");
+        assert_eq!(Locale::En.format_percent(-0.04, 1), "0.0%");
+    }
+
+    #[test]
+    fn format_currency_per_locale() {
+        assert_eq!(Locale::En.format_currency(1234.5, "USD"), "$1,234.50");
+        assert_eq!(Locale::En.format_currency(-5.0, "eur"), "-\u{20ac}5.00");
+        // symbol after the amount
+        assert_eq!(
+            Locale::Fr.format_currency(1234567.891, "EUR"),
+            "1\u{202f}234\u{202f}567,89\u{a0}\u{20ac}"
+        );
+        assert_eq!(
+            Locale::De.format_currency(999.999, "GBP"),
+            "1.000,00\u{a0}\u{a3}"
+        );
+        assert_eq!(
+            Locale::Nl.format_currency(12.0, "EUR"),
+            "\u{20ac}\u{a0}12,00"
+        );
+        // unknown currencies are shown by their code
+        assert_eq!(Locale::De.format_currency(3.0, "CHF"), "3,00\u{a0}CHF");
+        assert_eq!(Locale::En.format_currency(-0.001, "CHF"), "CHF0.00");
+    }
+
+    #[test]
+    fn datetime_patterns_per_locale() {
+        assert_eq!(Locale::En.date_pattern(), "%Y-%m-%d");
+        assert_eq!(Locale::De.date_pattern(), "%d.%m.%Y");
+        assert_eq!(Locale::Nl.datetime_pattern(), "%d-%m-%Y %H:%M");
+        assert_eq!(Locale::Fr.time_pattern(), "%H:%M");
+    }
+
+    #[test]
+    fn flattened_accessors_return_the_locale_strings() {
+        assert_eq!(Locale::En.strings().site_name(), "Plabayo News");
+        assert_eq!(Locale::Nl.strings().site_name(), "Plabayo Nieuws");
+        assert_eq!(Locale::Nl.strings().site_locales_nl(), "Nederlands");
+        // strings missing for a locale return the default locale's string
+        assert_eq!(
+            Locale::Nl.strings().page_search_no_results(),
+            Locale::En.strings().page_search_no_results()
+        );
+        for locale in Locale::all() {
+            assert_eq!(locale.strings().site_name(), locale.strings().site.name);
+        }
+    }
+
+    #[test]
+    fn locale_diagnostics_list_every_locale() {
+        let diagnostics = locale_diagnostics();
+        assert_eq!(diagnostics.lines().count(), Locale::all().count());
+        for line in diagnostics.lines() {
+            assert!(line.ends_with("%), ltr"), "{}", line);
+        }
+        assert!(diagnostics
+            .lines()
+            .any(|line| line.starts_with("en: ") && line.ends_with("(100.0%), ltr")));
+    }
+
+    #[test]
+    fn relative_time_per_locale() {
+        assert_eq!(Locale::En.relative_time(3 * 60 * 60), "3 hours ago");
+        assert_eq!(Locale::Nl.relative_time(3 * 60 * 60 + 59), "3 uur geleden");
+        assert_eq!(Locale::Fr.relative_time(3 * 60 * 60), "il y a 3 heures");
+        assert_eq!(Locale::En.relative_time(60), "1 minute ago");
+        assert_eq!(Locale::De.relative_time(-2 * 24 * 60 * 60), "in 2 Tagen");
+        // zero is singular in french, plural in english
+        assert_eq!(Locale::En.relative_time(0), "0 seconds ago");
+        assert_eq!(Locale::Fr.relative_time(0), "il y a 0 seconde");
+    }
+
+    #[test]
+    fn variants_for_language() {
+        assert_eq!(Locale::variants_for_language("nl"), vec![Locale::Nl]);
+        assert_eq!(Locale::variants_for_language("FR"), vec![Locale::Fr]);
+        assert_eq!(Locale::Fr.language_subtag(), "fr");
+        assert!(Locale::variants_for_language("pt").is_empty());
+    }
+
+    #[test]
+    fn parse_accept_language_ranked() {
+        let header = "fr-BE;q=0.8, nl, fr;q=0.8, de-DE;q=0.9, nl-BE;q=0.7, xx, *;q=0.1";
+        assert_eq!(
+            Locale::parse_accept_language_ranked(header),
+            vec![Locale::Nl, Locale::De, Locale::Fr, Locale::En]
+        );
+        assert_eq!(Locale::from_accept_language(header), Locale::Nl);
+        assert!(Locale::parse_accept_language_ranked("xx, en;q=0").is_empty());
+    }
+
+    #[test]
+    fn from_accept_language_without_whitespace() {
+        assert_eq!(
+            Locale::from_accept_language("fr-CH,fr;q=0.9,en;q=0.8"),
+            Locale::Fr
+        );
+        assert_eq!(Locale::from_accept_language("NL-be,de;q=1"), Locale::Nl);
+        assert_eq!(Locale::from_accept_language("xx-YY,zz"), Locale::default());
+    }
+
+    #[test]
+    fn accept_language_with_malformed_weights() {
+        // malformed weights drop their range, but not the rest of the header
+        for header in [
+            "nl;q=abc, fr;q=0.5",
+            "nl;q=, fr;q=0.5",
+            "nl;q=1.5, fr;q=0.5",
+            "nl;q=-1, fr;q=0.5",
+            "nl;q=inf, fr;q=0.5",
+            "nl;q=NaN, fr;q=0.5",
+        ] {
+            assert_eq!(
+                Locale::parse_accept_language_ranked(header),
+                vec![Locale::Fr],
+                "{}",
+                header
+            );
+        }
+        // equal weights keep the order of the header
+        assert_eq!(
+            Locale::from_accept_language("de;q=0.5, es;q=0.5, nl;q=0.5"),
+            Locale::De
+        );
+        assert_eq!(
+            Locale::from_accept_language("fr-CH, fr;q=0.9, en;q=0.8, *;q=0.5"),
+            Locale::Fr
+        );
+        assert_eq!(
+            Locale::parse_accept_language_ranked("de-Latn-AT;q=0.9, ;q=1, nl-BE"),
+            vec![Locale::Nl, Locale::De]
+        );
+    }
+
+    #[test]
+    fn from_tag_const() {
+        const NL: Locale = Locale::from_tag_const(" NL ");
+        const UNKNOWN: Locale = Locale::from_tag_const("xx");
+        // evaluated at compile time
+        const _: () = assert!(matches!(Locale::from_tag_const("de"), Locale::De));
+        assert_eq!(NL, Locale::Nl);
+        assert_eq!(UNKNOWN, Locale::En);
+        assert_eq!(Locale::from("fr"), Locale::Fr);
+    }
+
+    #[test]
+    fn from_drops_trailing_subtags() {
+        const DE: Locale = Locale::from_tag_const("de-AT");
+        assert_eq!(DE, Locale::De);
+        assert_eq!(Locale::from("en-US"), Locale::En);
+        assert_eq!(Locale::from(" nl-BE "), Locale::Nl);
+        assert_eq!(Locale::from("fr_CA"), Locale::Fr);
+        assert_eq!(Locale::from("es-Latn-419"), Locale::Es);
+        assert_eq!(Locale::from("ES-\u{0130}"), Locale::Es);
+        assert_eq!(Locale::from("zh-Hant-TW"), Locale::En);
+        assert_eq!(Locale::from("-"), Locale::En);
+        assert_eq!(Locale::from(""), Locale::En);
+        // strict conversions only accept supported tags
+        assert!(Locale::try_from("en-US").is_err());
+    }
+
+    #[test]
+    fn strings_diff() {
+        assert!(Locale::En.strings().diff(Locale::En.strings()).is_empty());
+        let diff = Locale::En.strings().diff(Locale::Nl.strings());
+        assert!(diff.contains(&("site.name", "Plabayo News", "Plabayo Nieuws")));
+        assert!(diff.contains(&("site.nav.header.news", "news", "nieuws")));
+        // untranslated strings fall back to the default locale, and thus do not differ
+        assert!(!diff
+            .iter()
+            .any(|(path, _, _)| *path == "site.nav.header.logout"));
+    }
+
+    #[test]
+    fn all_lists_every_locale_once() {
+        assert_eq!(Locale::ALL[0], Locale::default());
+        let tags: Vec<&str> = Locale::all().map(|locale| locale.as_str()).collect();
+        assert_eq!(tags.len(), Locale::ALL.len());
+        for (i, tag) in tags.iter().enumerate() {
+            assert!(!tags[..i].contains(tag));
+            assert_eq!(Locale::try_from(*tag).unwrap().as_str(), *tag);
+        }
+    }
+
+    #[test]
+    fn locale_formats_as_its_tag_and_keys_maps() {
+        assert_eq!(format!("lang={}", Locale::Nl), "lang=nl");
+        assert_eq!(format!("{:?}", Locale::Nl), "Nl");
+        let names: std::collections::HashMap<Locale, String> = Locale::all()
+            .map(|locale| (locale, locale.to_string()))
+            .collect();
+        assert_eq!(names[&Locale::Fr], "fr");
+        assert_eq!(names.len(), Locale::ALL.len());
+    }
+
+    #[test]
+    fn strings_by_path_of_keys() {
+        let strings = Locale::Nl.strings();
+        assert_eq!(
+            strings.get_by_path(&["site", "name"]),
+            Some("Plabayo Nieuws")
+        );
+        let key = String::from("name");
+        assert_eq!(
+            strings.get_by_path(&["site", key.as_str()]),
+            strings.get("site.name")
+        );
+        assert_eq!(strings.get_by_path(&["site"]), None);
+        assert_eq!(strings.get_by_path(&[]), None);
+    }
+}
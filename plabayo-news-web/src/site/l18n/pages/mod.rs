@@ -17,7 +17,12 @@
 mod generated;
 pub mod models;
 
-pub use generated::{static_response, PageItem, PageItems, PageSearch};
+pub use generated::{
+    endpoint_kind, negotiate_from_request, negotiate_with_cookie_persistence,
+    negotiate_with_preference, resolve_localized_endpoint, resolve_localized_path, static_response,
+    static_response_head, PageItem, PageItems, PageKind, PageSearch, ResolvedLocale, StaticPage,
+    StaticPageResponse, ASSETS_ROOT,
+};
 
 use crate::site::assets;
 
@@ -29,3 +34,127 @@ pub fn page_max_cache_age_sec(root: &str) -> u32 {
     // not sure that granular level belongs on this layer either
     0
 }
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::header::{ACCEPT_LANGUAGE, COOKIE};
+    use actix_web::http::StatusCode;
+    use actix_web::rt::System;
+    use actix_web::{test, web, App, HttpResponse};
+
+    use super::{
+        negotiate_with_cookie_persistence, negotiate_with_preference, ResolvedLocale,
+        StaticPageResponse,
+    };
+    use crate::site::l18n::locales::Locale;
+
+    async fn locale_handler(locale: ResolvedLocale) -> HttpResponse {
+        HttpResponse::Ok().body(locale.0.as_str())
+    }
+
+    #[test]
+    fn resolved_locale_follows_precedence() {
+        System::new("test").block_on(async {
+            let mut app =
+                test::init_service(App::new().route("/", web::get().to(locale_handler))).await;
+            for (uri, cookie, accept_language, expected) in [
+                ("/", None, None, "en"),
+                ("/", None, Some("nl-BE, fr;q=0.8"), "nl"),
+                ("/", Some("lang=de"), Some("nl-BE, fr;q=0.8"), "de"),
+                ("/", Some("lang=xx"), Some("fr"), "fr"),
+                ("/?lang=es", Some("lang=de"), Some("nl"), "es"),
+                ("/?lang=xx", None, None, "en"),
+            ] {
+                let mut req = test::TestRequest::get().uri(uri);
+                if let Some(cookie) = cookie {
+                    req = req.header(COOKIE, cookie);
+                }
+                if let Some(accept_language) = accept_language {
+                    req = req.header(ACCEPT_LANGUAGE, accept_language);
+                }
+                let body = test::read_response(&mut app, req.to_request()).await;
+                assert_eq!(body, expected, "{} {:?} {:?}", uri, cookie, accept_language);
+            }
+        });
+    }
+
+    async fn gone_handler() -> StaticPageResponse {
+        StaticPageResponse {
+            status: StatusCode::GONE,
+            content_type: "text/plain; charset=utf-8",
+            body: "gone".to_owned(),
+        }
+    }
+
+    #[test]
+    fn static_page_response_is_a_responder() {
+        System::new("test").block_on(async {
+            let mut app =
+                test::init_service(App::new().route("/", web::get().to(gone_handler))).await;
+            let resp =
+                test::call_service(&mut app, test::TestRequest::get().uri("/").to_request()).await;
+            assert_eq!(resp.status(), StatusCode::GONE);
+            assert_eq!(
+                resp.headers().get("content-type").unwrap(),
+                "text/plain; charset=utf-8"
+            );
+            assert_eq!(test::read_body(resp).await, "gone");
+        });
+    }
+
+    #[test]
+    fn only_header_negotiated_locales_are_persisted() {
+        for (uri, cookie, accept_language, expected) in [
+            ("/", None, None, (Locale::En, false)),
+            ("/", None, Some("nl-BE, fr;q=0.8"), (Locale::Nl, true)),
+            ("/", None, Some("xx"), (Locale::En, true)),
+            ("/", Some("lang=de"), Some("nl"), (Locale::De, false)),
+            ("/", Some("lang=xx"), Some("fr"), (Locale::Fr, true)),
+            (
+                "/?lang=es",
+                Some("lang=de"),
+                Some("nl"),
+                (Locale::Es, false),
+            ),
+        ] {
+            let mut req = test::TestRequest::get().uri(uri);
+            if let Some(cookie) = cookie {
+                req = req.header(COOKIE, cookie);
+            }
+            if let Some(accept_language) = accept_language {
+                req = req.header(ACCEPT_LANGUAGE, accept_language);
+            }
+            assert_eq!(
+                negotiate_with_cookie_persistence(&req.to_http_request()),
+                expected,
+                "{} {:?} {:?}",
+                uri,
+                cookie,
+                accept_language
+            );
+        }
+    }
+
+    #[test]
+    fn preferred_locale_only_yields_to_the_query() {
+        for (uri, cookie, expected) in [
+            ("/", None, (Locale::De, false)),
+            ("/", Some("lang=fr"), (Locale::De, false)),
+            ("/?lang=es", Some("lang=fr"), (Locale::Es, false)),
+        ] {
+            let mut req = test::TestRequest::get()
+                .uri(uri)
+                .header(ACCEPT_LANGUAGE, "nl");
+            if let Some(cookie) = cookie {
+                req = req.header(COOKIE, cookie);
+            }
+            assert_eq!(
+                negotiate_with_preference(&req.to_http_request(), Some(Locale::De)),
+                expected,
+                "{} {:?}",
+                uri,
+                cookie
+            );
+        }
+    }
+}
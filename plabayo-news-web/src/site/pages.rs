@@ -24,9 +24,12 @@ use chrono::{DateTime, Utc};
 use plabayo_news_data::models::User;
 
 use crate::site::extractors::Session;
-use crate::site::l18n::locales::Locale;
+use crate::site::l18n::locales::{AlternateLink, Locale, LOCALE_QUERY_KEY};
 use crate::site::l18n::pages::models::{ContentItem, ContentItems, ContentSearch, Item};
-use crate::site::l18n::pages::{static_response, PageItem, PageItems, PageSearch};
+use crate::site::l18n::pages::{
+    resolve_localized_endpoint, resolve_localized_path, static_response, static_response_head,
+    PageItem, PageItems, PageSearch, StaticPage,
+};
 use crate::site::state::AppState;
 
 //---------------------------------------
@@ -34,7 +37,9 @@ use crate::site::state::AppState;
 //---------------------------------------
 
 pub fn factory() -> impl HttpServiceFactory + 'static {
-    web::resource("/{resource:.*}").route(web::get().to(serve_page))
+    web::resource("/{resource:.*}")
+        .route(web::get().to(serve_page))
+        .route(web::head().to(serve_page_head))
 }
 
 //---------------------------------------
@@ -44,6 +49,10 @@ pub fn factory() -> impl HttpServiceFactory + 'static {
 pub struct PageState {
     pub locale: Locale,
     pub path: String,
+    /// absolute url of this page in its locale
+    pub canonical: String,
+    /// absolute urls of this page in all other locales (and `x-default`)
+    pub alternates: Vec<AlternateLink>,
     pub query: BTreeMap<String, String>,
     pub gen_date_time: DateTime<Utc>,
     pub user: Option<User>,
@@ -57,12 +66,46 @@ pub struct PageState {
 impl PageState {
     pub fn new(
         locale: Locale,
+        origin: &str,
         path: String,
         query: BTreeMap<String, String>,
         user: Option<User>,
     ) -> PageState {
+        let origin = origin.trim_end_matches('/');
+        // static pages link to their path localized for each locale
+        let page = resolve_static_page(path.trim_start_matches('/'), locale).0;
+        let url = |locale: Locale| {
+            let path = page
+                .map(|page| page.localized_path(locale))
+                .unwrap_or(path.as_str());
+            match resolve_localized_path(path.trim_start_matches('/')) {
+                Some((_, path_locale)) if path_locale == locale => format!("{}{}", origin, path),
+                _ => format!(
+                    "{}{}?{}={}",
+                    origin,
+                    path,
+                    LOCALE_QUERY_KEY,
+                    locale.as_str()
+                ),
+            }
+        };
         PageState {
             locale,
+            canonical: url(locale),
+            alternates: Locale::all()
+                .filter(|other| *other != locale)
+                .map(|other| AlternateLink {
+                    hreflang: other.hreflang(),
+                    href: url(other),
+                })
+                .chain(std::iter::once(AlternateLink {
+                    hreflang: "x-default",
+                    href: match page {
+                        Some(page) => format!("{}/{}", origin, page.endpoint()),
+                        None => format!("{}{}", origin, path),
+                    },
+                }))
+                .collect(),
             path,
             query,
             gen_date_time: chrono::offset::Utc::now(),
@@ -128,9 +171,29 @@ async fn serve_page(
     match path.as_str() {
         "" | "index" => serve_news_ranked("/", query, app_state, session).await,
         "news" => serve_news_ranked("/news", query, app_state, session).await,
-        "search" => serve_search("/search", query, session).await,
-        "item" => serve_item("/item", query, session).await,
-        _ => serve_static(path.as_str(), query, session),
+        "search" => serve_search("/search", query, app_state, session).await,
+        "item" => serve_item("/item", query, app_state, session).await,
+        _ => serve_static(path.as_str(), query, app_state, session),
+    }
+}
+
+async fn serve_page_head(
+    path: web::Path<(String,)>,
+    query: web::Query<BTreeMap<String, String>>,
+    session: Session,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let endpoint = path.0 .0.to_lowercase();
+    match endpoint.as_str() {
+        "" | "index" | "news" | "search" | "item" => {
+            let mut response = serve_page(path, query, session, app_state).await?;
+            response.take_body();
+            Ok(response)
+        }
+        // static pages are not rendered to answer a HEAD request
+        _ => Ok(static_response_head(
+            resolve_static_page(endpoint.as_str(), session.locale()).0,
+        )),
     }
 }
 
@@ -153,7 +216,7 @@ async fn serve_news_ranked(
             .collect(),
     };
 
-    let page_state = PageState::new(locale, path.to_string(), query, user);
+    let page_state = PageState::new(locale, &app_state.origin, path.to_string(), query, user);
 
     PageItems::new_response(page_state, content)
 }
@@ -161,6 +224,7 @@ async fn serve_news_ranked(
 async fn serve_search(
     path: &str,
     query: BTreeMap<String, String>,
+    app_state: Arc<AppState>,
     session: Session,
 ) -> Result<HttpResponse> {
     let locale = session.locale();
@@ -171,7 +235,7 @@ async fn serve_search(
 
     let content = ContentSearch { q };
 
-    let page_state = PageState::new(locale, path.to_string(), query, user);
+    let page_state = PageState::new(locale, &app_state.origin, path.to_string(), query, user);
 
     PageSearch::new_response(page_state, content)
 }
@@ -179,6 +243,7 @@ async fn serve_search(
 async fn serve_item(
     path: &str,
     query: BTreeMap<String, String>,
+    app_state: Arc<AppState>,
     session: Session,
 ) -> Result<HttpResponse> {
     let locale = session.locale();
@@ -189,7 +254,7 @@ async fn serve_item(
 
     let content = ContentItem { q };
 
-    let page_state = PageState::new(locale, path.to_string(), query, user);
+    let page_state = PageState::new(locale, &app_state.origin, path.to_string(), query, user);
 
     PageItem::new_response(page_state, content)
 }
@@ -197,17 +262,191 @@ async fn serve_item(
 fn serve_static(
     endpoint: &str,
     query: BTreeMap<String, String>,
+    app_state: Arc<AppState>,
     session: Session,
 ) -> Result<HttpResponse> {
-    let locale = session.locale();
+    let (page, locale) = resolve_static_page(endpoint, session.locale());
     let user = session.user();
 
-    let page_state = PageState::new(locale, format!("/{}", endpoint), query, user);
+    let page_state = PageState::new(
+        locale,
+        &app_state.origin,
+        format!("/{}", endpoint),
+        query,
+        user,
+    );
+    static_response(page, page_state)
+}
 
-    static_response(endpoint, page_state)
+fn resolve_static_page(endpoint: &str, locale: Locale) -> (Option<StaticPage>, Locale) {
+    let page = StaticPage::from_endpoint(endpoint).or_else(|| {
+        resolve_localized_endpoint(endpoint, locale).and_then(StaticPage::from_endpoint)
+    });
+    // a path localized for another locale is served in that locale
+    match page {
+        Some(page) => (Some(page), locale),
+        None => match resolve_localized_path(endpoint) {
+            Some((page, locale)) => (Some(page), locale),
+            None => (None, locale),
+        },
+    }
 }
 
 // TODO(2): provide data source trait in /data package,
 // and use it within here to start to get data,
 // so we are working towards that structure already,
 // probably will require plenty of iterations on its own right
+
+#[cfg(test)]
+mod tests {
+    use actix_web::dev::{BodySize, MessageBody};
+    use actix_web::http::header::{CONTENT_LENGTH, CONTENT_TYPE, ETAG, HOST, LAST_MODIFIED};
+    use actix_web::http::{Method, StatusCode};
+    use actix_web::rt::System;
+    use actix_web::{test, App};
+
+    use super::*;
+
+    #[test]
+    fn head_requests_answer_with_the_page_status_and_headers_and_no_body() {
+        System::new("test").block_on(async {
+            let mut app =
+                test::init_service(App::new().data(AppState::default()).service(factory())).await;
+            for (uri, status) in [
+                ("/faq", StatusCode::OK),
+                ("/FAQ", StatusCode::OK),
+                ("/news", StatusCode::OK),
+                ("/does-not-exist", StatusCode::NOT_FOUND),
+            ] {
+                let req = test::TestRequest::get().uri(uri).to_request();
+                let get = test::call_service(&mut app, req).await;
+                let req = test::TestRequest::default()
+                    .method(Method::HEAD)
+                    .uri(uri)
+                    .to_request();
+                let res = test::call_service(&mut app, req).await;
+                assert_eq!(res.status(), status, "HEAD {}", uri);
+                assert_eq!(res.status(), get.status(), "HEAD {}", uri);
+                assert_eq!(
+                    res.headers().get(CONTENT_TYPE).unwrap(),
+                    "text/html",
+                    "HEAD {}",
+                    uri
+                );
+                // the length is unknown without rendering, and thus not sent,
+                // rather than a length of 0 which doesn't match the GET response
+                assert!(get.response().body().size() != BodySize::Empty);
+                assert_eq!(res.response().body().size(), BodySize::None, "HEAD {}", uri);
+                assert!(res.headers().get(CONTENT_LENGTH).is_none(), "HEAD {}", uri);
+                // like the GET response, there are no validators to answer with
+                for header in [ETAG, LAST_MODIFIED] {
+                    assert_eq!(
+                        res.headers().get(&header),
+                        get.headers().get(&header),
+                        "HEAD {} {}",
+                        uri,
+                        header
+                    );
+                }
+                assert!(test::read_body(res).await.is_empty(), "HEAD {}", uri);
+            }
+        });
+    }
+
+    #[test]
+    fn localized_paths_resolve_to_their_page_and_locale() {
+        let pages = [
+            StaticPage::Api,
+            StaticPage::Contribute,
+            StaticPage::Faq,
+            StaticPage::Guidelines,
+            StaticPage::Security,
+        ];
+        for locale in Locale::all() {
+            for page in pages {
+                let path = page.localized_path(locale);
+                // a localized path wins over the locale of the request
+                assert_eq!(
+                    resolve_static_page(path.trim_start_matches('/'), Locale::De),
+                    (
+                        Some(page),
+                        if path == format!("/{}", page.endpoint()) {
+                            Locale::De
+                        } else {
+                            locale
+                        }
+                    ),
+                    "{}",
+                    path
+                );
+            }
+        }
+        assert_eq!(
+            StaticPage::Faq.localized_path(Locale::Nl),
+            "/veelgestelde-vragen"
+        );
+
+        System::new("test").block_on(async {
+            let mut app =
+                test::init_service(App::new().data(AppState::default()).service(factory())).await;
+            let req = test::TestRequest::get()
+                .uri(StaticPage::Faq.localized_path(Locale::Nl))
+                .to_request();
+            let res = test::call_service(&mut app, req).await;
+            assert_eq!(res.status(), StatusCode::OK);
+            let body = test::read_body(res).await;
+            assert!(String::from_utf8_lossy(&body).contains("<html lang=\"nl\""));
+        });
+    }
+
+    #[test]
+    fn pages_link_their_localized_canonical_and_alternate_urls() {
+        System::new("test").block_on(async {
+            let mut app = test::init_service(
+                App::new()
+                    .data(AppState::new("https://news.plabayo.tech/"))
+                    .service(factory()),
+            )
+            .await;
+            for (uri, links) in [
+                (
+                    "/veelgestelde-vragen",
+                    vec![
+                        "<link rel=\"canonical\" href=\"https://news.plabayo.tech/veelgestelde-vragen\">",
+                        "<link rel=\"alternate\" hreflang=\"en\" href=\"https://news.plabayo.tech/faq?lang=en\">",
+                        "<link rel=\"alternate\" hreflang=\"x-default\" href=\"https://news.plabayo.tech/faq\">",
+                    ],
+                ),
+                (
+                    "/faq?lang=fr",
+                    vec![
+                        "<link rel=\"canonical\" href=\"https://news.plabayo.tech/faq?lang=fr\">",
+                        "<link rel=\"alternate\" hreflang=\"nl\" href=\"https://news.plabayo.tech/veelgestelde-vragen\">",
+                        "<link rel=\"alternate\" hreflang=\"x-default\" href=\"https://news.plabayo.tech/faq\">",
+                    ],
+                ),
+                (
+                    "/news?lang=nl",
+                    vec![
+                        "<link rel=\"canonical\" href=\"https://news.plabayo.tech/news?lang=nl\">",
+                        "<link rel=\"alternate\" hreflang=\"en\" href=\"https://news.plabayo.tech/news?lang=en\">",
+                        "<link rel=\"alternate\" hreflang=\"x-default\" href=\"https://news.plabayo.tech/news\">",
+                    ],
+                ),
+            ] {
+                // the host header is up to the client, and thus not to be trusted
+                let req = test::TestRequest::get()
+                    .uri(uri)
+                    .header(HOST, "attacker.example")
+                    .to_request();
+                let body = test::read_response(&mut app, req).await;
+                // (older) askama versions escape the slashes of urls
+                let body = String::from_utf8_lossy(&body).replace("&#x2f;", "/");
+                for link in links {
+                    assert!(body.contains(link), "{}: {}", uri, link);
+                }
+                assert!(!body.contains("attacker.example"), "{}", uri);
+            }
+        });
+    }
+}
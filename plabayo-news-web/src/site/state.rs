@@ -16,19 +16,28 @@
 
 use plabayo_news_data::Database;
 
-#[derive(Clone, Copy)]
+/// scheme and host the site is served from by default
+pub const DEFAULT_ORIGIN: &str = "https://news.plabayo.tech";
+
+#[derive(Clone)]
 pub struct AppState {
     pub db: Database,
+    /// scheme and host the site is served from (e.g. `https://news.plabayo.tech`),
+    /// used for absolute urls rather than the Host header controlled by the client
+    pub origin: String,
 }
 
 impl AppState {
-    pub fn new() -> AppState {
-        AppState { db: Database {} }
+    pub fn new(origin: &str) -> AppState {
+        AppState {
+            db: Database {},
+            origin: origin.trim_end_matches('/').to_owned(),
+        }
     }
 }
 
 impl Default for AppState {
     fn default() -> AppState {
-        AppState::new()
+        AppState::new(DEFAULT_ORIGIN)
     }
 }